@@ -1,7 +1,7 @@
 use std::{hint::black_box, time::Duration};
 
 use divan::{bench, counter::BytesCount, Bencher, Divan};
-use keyvalues_parser::Vdf;
+use keyvalues_parser::{cst::Document, stream::Reader, Parser, Vdf};
 
 fn main() {
     // Run registered benchmarks
@@ -18,6 +18,27 @@ pub fn parse() {
     Vdf::parse(black_box(VDF_TEXT)).unwrap();
 }
 
+#[bench(bytes_count = VDF_TEXT.len())]
+pub fn parse_raw() {
+    Parser::new()
+        .literal_special_chars(true)
+        .parse(black_box(VDF_TEXT))
+        .unwrap();
+}
+
+// The other parsers that share the same grammar
+#[bench(bytes_count = VDF_TEXT.len())]
+pub fn parse_cst() {
+    Document::parse(black_box(VDF_TEXT)).unwrap();
+}
+
+#[bench(bytes_count = VDF_TEXT.len())]
+pub fn parse_stream() {
+    for event in Reader::new(black_box(VDF_TEXT.as_bytes())) {
+        black_box(event.unwrap());
+    }
+}
+
 #[bench]
 pub fn render(bencher: Bencher) {
     let vdf = Vdf::parse(VDF_TEXT).unwrap();
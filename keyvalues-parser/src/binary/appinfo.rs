@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+
+use super::{parse::Reader, sha1::sha1};
+use crate::{
+    error::{BinaryParseErrorKind, Result},
+    Vdf,
+};
+
+const MAGIC_V27: u32 = 0x07_56_44_27;
+const MAGIC_V28: u32 = 0x07_56_44_28;
+const MAGIC_V29: u32 = 0x07_56_44_29;
+
+/// An index over the entries in Steam's `appcache/appinfo.vdf`
+///
+/// `appinfo.vdf` is a container of binary KeyValues blobs (one per app) where each blob is
+/// prefixed by a header with some metadata. Parsing only reads the headers, so the (potentially
+/// very large) file can be indexed quickly and then individual apps can be decoded on demand with
+/// [`AppInfo::vdf()`]
+///
+/// Versions 27, 28, and 29 of the format are supported
+///
+/// ```no_run
+/// use keyvalues_parser::binary::AppInfo;
+///
+/// let bytes = std::fs::read("/home/user/.steam/steam/appcache/appinfo.vdf")?;
+/// let app_info = AppInfo::parse(&bytes)?;
+/// let tf2 = app_info.get(440).unwrap();
+/// assert_eq!(tf2.verify_checksum(), Some(true));
+/// let vdf = app_info.vdf(tf2)?;
+/// println!("{vdf}");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct AppInfo<'a> {
+    /// The version of the container format e.g. `29`
+    pub version: u32,
+    /// The Steam universe that the info is for. `1` is the public universe
+    pub universe: u32,
+    entries: Vec<AppInfoEntry<'a>>,
+    index: BTreeMap<u32, usize>,
+    // Version 29 stores all of the keys in a table at the end of the file
+    key_table: Option<Vec<&'a str>>,
+}
+
+/// The header for a single app within [`AppInfo`] along with its undecoded payload
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AppInfoEntry<'a> {
+    pub app_id: u32,
+    pub info_state: u32,
+    /// Unix timestamp of when the info was last updated
+    pub last_updated: u32,
+    pub pics_token: u64,
+    /// SHA-1 of the app's info when rendered as text
+    pub text_sha1: [u8; 20],
+    pub change_number: u32,
+    /// SHA-1 of the binary payload. Only present in version 28 and later
+    pub binary_sha1: Option<[u8; 20]>,
+    payload: &'a [u8],
+    payload_offset: usize,
+}
+
+impl<'a> AppInfoEntry<'a> {
+    /// The raw binary KeyValues payload
+    pub fn payload(&self) -> &'a [u8] {
+        self.payload
+    }
+
+    /// Checks the payload against its SHA-1
+    ///
+    /// Returns `None` for versions that don't include a checksum of the binary payload
+    pub fn verify_checksum(&self) -> Option<bool> {
+        self.binary_sha1
+            .map(|checksum| sha1(self.payload) == checksum)
+    }
+}
+
+impl<'a> AppInfo<'a> {
+    /// Indexes all of the apps without decoding their payloads
+    pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        let magic = reader.u32()?;
+        let version = match magic {
+            MAGIC_V27 => 27,
+            MAGIC_V28 => 28,
+            MAGIC_V29 => 29,
+            _ => {
+                return Err(reader
+                    .err_at(0, BinaryParseErrorKind::UnsupportedMagic(magic))
+                    .into())
+            }
+        };
+        let universe = reader.u32()?;
+        let key_table = if version >= 29 {
+            let table_offset = reader.u64()?;
+            Some(read_key_table(bytes, table_offset)?)
+        } else {
+            None
+        };
+
+        let mut entries = Vec::new();
+        let mut index = BTreeMap::new();
+        loop {
+            let app_id = reader.u32()?;
+            // An app id of 0 marks the end of the entries
+            if app_id == 0 {
+                break;
+            }
+
+            let size_pos = reader.pos();
+            let entry_size = reader.u32()?;
+            let header_len = if version >= 28 { 60 } else { 40 };
+            let payload_len = (entry_size as usize)
+                .checked_sub(header_len)
+                .ok_or_else(|| {
+                    reader.err_at(size_pos, BinaryParseErrorKind::InvalidEntrySize(entry_size))
+                })?;
+
+            let info_state = reader.u32()?;
+            let last_updated = reader.u32()?;
+            let pics_token = reader.u64()?;
+            let text_sha1 = reader.array()?;
+            let change_number = reader.u32()?;
+            let binary_sha1 = if version >= 28 {
+                Some(reader.array()?)
+            } else {
+                None
+            };
+            let payload_offset = reader.pos();
+            let payload = reader.bytes(payload_len)?;
+
+            index.insert(app_id, entries.len());
+            entries.push(AppInfoEntry {
+                app_id,
+                info_state,
+                last_updated,
+                pics_token,
+                text_sha1,
+                change_number,
+                binary_sha1,
+                payload,
+                payload_offset,
+            });
+        }
+
+        Ok(Self {
+            version,
+            universe,
+            entries,
+            index,
+            key_table,
+        })
+    }
+
+    /// All of the app entries in the order that they're stored
+    pub fn entries(&self) -> &[AppInfoEntry<'a>] {
+        &self.entries
+    }
+
+    /// Looks up the entry for a specific app
+    pub fn get(&self, app_id: u32) -> Option<&AppInfoEntry<'a>> {
+        self.index.get(&app_id).map(|&i| &self.entries[i])
+    }
+
+    /// Decodes the payload for an entry
+    ///
+    /// This doesn't verify the checksum. Use [`AppInfoEntry::verify_checksum()`] for that
+    pub fn vdf(&self, entry: &AppInfoEntry<'a>) -> Result<Vdf<'a>> {
+        let vdf = Reader::with_base(entry.payload, entry.payload_offset)
+            .key_table(self.key_table.as_deref())
+            .top_level_pair()?;
+        Ok(vdf)
+    }
+}
+
+fn read_key_table(bytes: &[u8], table_offset: u64) -> Result<Vec<&str>> {
+    let mut reader = Reader::new(bytes);
+    let table_offset = usize::try_from(table_offset)
+        .ok()
+        .filter(|&offset| offset <= bytes.len())
+        .ok_or_else(|| reader.err_at(8, BinaryParseErrorKind::UnexpectedEof))?;
+    reader.seek(table_offset);
+
+    let len = reader.u32()?;
+    // Don't trust `len` for the allocation, but every key takes at least a byte
+    let mut keys = Vec::with_capacity((len as usize).min(bytes.len() - table_offset));
+    for _ in 0..len {
+        keys.push(reader.c_str()?);
+    }
+
+    Ok(keys)
+}
@@ -0,0 +1,54 @@
+//! Binary KeyValues support
+//!
+//! Steam stores a lot of its data (`shortcuts.vdf`, the payloads within `appinfo.vdf`, etc.) with
+//! a binary encoding of KeyValues where each pair is prefixed with a type tag. Parsing produces the
+//! same [`Vdf`][crate::Vdf] tree as text, but with [`TypedValue`][crate::TypedValue] leaves for
+//! anything that isn't a plain string
+//!
+//! The encoding for each pair is
+//!
+//! | Tag | Type | Value |
+//! | :---: | :--- | :--- |
+//! | `0x00` | Object | Nested pairs terminated by an end tag |
+//! | `0x01` | String | Null-terminated UTF-8 |
+//! | `0x02` | Int | Little-endian `i32` |
+//! | `0x03` | Float | Little-endian `f32` |
+//! | `0x04` | Pointer | Little-endian `u32` |
+//! | `0x05` | Wide string | Null-terminated UTF-16LE |
+//! | `0x06` | Color | 4 bytes of RGBA |
+//! | `0x07` | UInt64 | Little-endian `u64` |
+//! | `0x08` | End | Terminates an object |
+//! | `0x0A` | Int64 | Little-endian `i64` |
+//! | `0x0B` | Alternate end | Terminates an object |
+//!
+//! where each tag (other than an end tag) is followed by the null-terminated UTF-8 key and then
+//! the value
+//!
+//! Rendering back to binary is done with [`Vdf::render_binary()`][crate::Vdf::render_binary]
+//!
+//! Steam's `appinfo.vdf` and `packageinfo.vdf` containers can be read with [`AppInfo`] and
+//! [`PackageInfo`] respectively
+
+mod appinfo;
+mod packageinfo;
+mod parse;
+mod render;
+mod sha1;
+
+pub use appinfo::{AppInfo, AppInfoEntry};
+pub use packageinfo::{PackageInfo, PackageInfoEntry};
+pub use parse::parse;
+
+// Type tags for each pair
+const TAG_OBJ: u8 = 0x00;
+const TAG_STR: u8 = 0x01;
+const TAG_INT: u8 = 0x02;
+const TAG_FLOAT: u8 = 0x03;
+const TAG_PTR: u8 = 0x04;
+const TAG_WIDE_STR: u8 = 0x05;
+const TAG_COLOR: u8 = 0x06;
+const TAG_UINT64: u8 = 0x07;
+const TAG_END: u8 = 0x08;
+const TAG_INT64: u8 = 0x0A;
+// Only ever read. We always write the regular end tag
+const TAG_ALT_END: u8 = 0x0B;
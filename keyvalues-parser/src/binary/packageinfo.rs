@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use super::parse::Reader;
+use crate::{
+    error::{BinaryParseErrorKind, Result},
+    Value, Vdf,
+};
+
+const MAGIC_V27: u32 = 0x06_56_55_27;
+const MAGIC_V28: u32 = 0x06_56_55_28;
+
+// Marks the end of the entries
+const END_PACKAGE_ID: u32 = 0xFFFF_FFFF;
+
+/// The packages from Steam's `appcache/packageinfo.vdf`
+///
+/// Unlike `appinfo.vdf` the entries aren't prefixed by their size, so each package's binary
+/// KeyValues body is decoded while parsing
+///
+/// Versions 27 and 28 of the format are supported
+///
+/// ```no_run
+/// use keyvalues_parser::binary::PackageInfo;
+///
+/// let bytes = std::fs::read("/home/user/.steam/steam/appcache/packageinfo.vdf")?;
+/// let package_info = PackageInfo::parse(&bytes)?;
+/// for package in package_info.packages() {
+///     println!("{}: apps {:?}", package.package_id, package.app_ids());
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct PackageInfo<'a> {
+    /// The version of the container format e.g. `28`
+    pub version: u32,
+    /// The Steam universe that the info is for. `1` is the public universe
+    pub universe: u32,
+    packages: Vec<PackageInfoEntry<'a>>,
+    index: BTreeMap<u32, usize>,
+}
+
+/// A single package's header and decoded body within [`PackageInfo`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PackageInfoEntry<'a> {
+    pub package_id: u32,
+    /// SHA-1 of the package's info
+    pub sha1: [u8; 20],
+    pub change_number: u32,
+    /// Only present in version 28 and later
+    pub pics_token: Option<u64>,
+    /// The package's info where the key is the package id
+    pub vdf: Vdf<'a>,
+}
+
+impl PackageInfoEntry<'_> {
+    /// The ids for the apps that the package grants
+    pub fn app_ids(&self) -> Vec<u32> {
+        self.ids("appids")
+    }
+
+    /// The ids for the depots that the package grants
+    pub fn depot_ids(&self) -> Vec<u32> {
+        self.ids("depotids")
+    }
+
+    // The ids are stored as an object of `"<index>" <id>` pairs. They're read in the order that
+    // they're stored since the keys sort as strings e.g. `"10"` before `"2"`
+    fn ids(&self, key: &str) -> Vec<u32> {
+        self.vdf
+            .value
+            .get_obj()
+            .and_then(|obj| obj.get(key))
+            .and_then(|values| values.first())
+            .and_then(Value::get_obj)
+            .map(|ids| {
+                ids.ordered_pairs()
+                    .into_iter()
+                    .filter_map(|(_, _, id)| match id {
+                        Value::Str(s) => s.parse().ok(),
+                        Value::Typed(typed) => typed.to_string().parse().ok(),
+                        Value::Obj(_) => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl<'a> PackageInfo<'a> {
+    /// Parses the headers and bodies for all of the packages
+    pub fn parse(bytes: &'a [u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        let magic = reader.u32()?;
+        let version = match magic {
+            MAGIC_V27 => 27,
+            MAGIC_V28 => 28,
+            _ => {
+                return Err(reader
+                    .err_at(0, BinaryParseErrorKind::UnsupportedMagic(magic))
+                    .into())
+            }
+        };
+        let universe = reader.u32()?;
+
+        let mut packages = Vec::new();
+        let mut index = BTreeMap::new();
+        loop {
+            let package_id = reader.u32()?;
+            if package_id == END_PACKAGE_ID {
+                break;
+            }
+
+            let sha1 = reader.array()?;
+            let change_number = reader.u32()?;
+            let pics_token = if version >= 28 {
+                Some(reader.u64()?)
+            } else {
+                None
+            };
+            let vdf = reader.embedded_pair()?;
+
+            index.insert(package_id, packages.len());
+            packages.push(PackageInfoEntry {
+                package_id,
+                sha1,
+                change_number,
+                pics_token,
+                vdf,
+            });
+        }
+
+        Ok(Self {
+            version,
+            universe,
+            packages,
+            index,
+        })
+    }
+
+    /// All of the packages in the order that they're stored
+    pub fn packages(&self) -> &[PackageInfoEntry<'a>] {
+        &self.packages
+    }
+
+    /// Looks up a specific package
+    pub fn get(&self, package_id: u32) -> Option<&PackageInfoEntry<'a>> {
+        self.index.get(&package_id).map(|&i| &self.packages[i])
+    }
+}
@@ -0,0 +1,293 @@
+use std::borrow::Cow;
+
+use super::{
+    TAG_ALT_END, TAG_COLOR, TAG_END, TAG_FLOAT, TAG_INT, TAG_INT64, TAG_OBJ, TAG_PTR, TAG_STR,
+    TAG_UINT64, TAG_WIDE_STR,
+};
+use crate::{
+    error::{BinaryParseError, BinaryParseErrorKind, Result},
+    Limits, Obj, Parser, TypedValue, Value, Vdf,
+};
+
+type BinResult<T> = std::result::Result<T, BinaryParseError>;
+
+/// Parses binary KeyValues to a [`Vdf`]
+///
+/// Strings and keys are borrowed from the input when possible. Wide strings always have to be
+/// converted, so they're owned
+///
+/// ```
+/// use keyvalues_parser::{binary, TypedValue, Value};
+///
+/// let bytes = b"\x00shortcut\x00\x01AppName\x00Game\x00\x02appid\x00\x0f\x00\x00\x00\x08\x08";
+/// let vdf = binary::parse(bytes)?;
+/// let obj = vdf.value.unwrap_obj();
+/// assert_eq!(obj.get("AppName").unwrap()[0].get_str(), Some("Game"));
+/// assert_eq!(
+///     obj.get("appid").unwrap()[0],
+///     Value::Typed(TypedValue::Int(15)),
+/// );
+/// # Ok::<(), keyvalues_parser::error::Error>(())
+/// ```
+///
+/// Objects can be nested up to [`Limits::DEFAULT_MAX_DEPTH`] deep. Use
+/// [`Parser::parse_binary()`] to pick a different limit
+pub fn parse(bytes: &[u8]) -> Result<Vdf<'_>> {
+    let mut reader = Reader::new(bytes);
+    let vdf = reader.top_level_pair()?;
+    Ok(vdf)
+}
+
+impl Parser {
+    /// Parse binary KeyValues to a [`Vdf`]
+    ///
+    /// Only [`Limits::max_depth`] applies to binary input. Going over it is a
+    /// [`BinaryParseErrorKind::DepthExceeded`] error
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use keyvalues_parser::{
+    ///     error::{BinaryParseErrorKind, Error},
+    ///     Limits, Parser,
+    /// };
+    /// let bytes = b"\x00Outer\x00\x00Inner\x00\x01Key\x00Value\x00\x08\x08\x08";
+    /// let limits = Limits {
+    ///     max_depth: Some(1),
+    ///     ..Limits::new()
+    /// };
+    /// let Err(Error::BinaryParseError(err)) = Parser::new().limits(limits).parse_binary(bytes)
+    /// else {
+    ///     unreachable!();
+    /// };
+    /// assert_eq!(err.kind, BinaryParseErrorKind::DepthExceeded);
+    /// ```
+    pub fn parse_binary<'a>(&self, bytes: &'a [u8]) -> Result<Vdf<'a>> {
+        let mut reader = Reader::new(bytes).max_depth(self.limits.max_depth);
+        let vdf = reader.top_level_pair()?;
+        Ok(vdf)
+    }
+}
+
+impl<'a> Vdf<'a> {
+    /// Attempts to parse binary KeyValues to a [`Vdf`]
+    ///
+    /// This is the same as [`binary::parse()`][parse]
+    pub fn parse_binary(bytes: &'a [u8]) -> Result<Self> {
+        parse(bytes)
+    }
+}
+
+/// A cursor over binary KeyValues input
+///
+/// This is also used for reading the containers that wrap binary KeyValues, so `base` is used to
+/// report error offsets relative to the full input and `key_table` handles newer formats that
+/// store keys as indices into a string table. Reading objects is recursive, so `max_depth` keeps
+/// deeply nested input from overflowing the stack
+pub(crate) struct Reader<'a, 'k> {
+    bytes: &'a [u8],
+    pos: usize,
+    base: usize,
+    key_table: Option<&'k [&'a str]>,
+    depth: usize,
+    max_depth: Option<usize>,
+}
+
+impl<'a, 'k> Reader<'a, 'k> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            pos: 0,
+            base: 0,
+            key_table: None,
+            depth: 0,
+            max_depth: Limits::new().max_depth,
+        }
+    }
+
+    /// Reads a section of a larger input that starts at `base`
+    pub(crate) fn with_base(bytes: &'a [u8], base: usize) -> Self {
+        Self {
+            base,
+            ..Self::new(bytes)
+        }
+    }
+
+    /// Keys are read as `u32` indices into the `key_table` instead of inline strings
+    pub(crate) fn key_table(mut self, key_table: Option<&'k [&'a str]>) -> Self {
+        self.key_table = key_table;
+        self
+    }
+
+    pub(crate) fn max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub(crate) fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub(crate) fn seek(&mut self, pos: usize) {
+        self.pos = pos;
+    }
+
+    pub(crate) fn err(&self, kind: BinaryParseErrorKind) -> BinaryParseError {
+        self.err_at(self.pos, kind)
+    }
+
+    pub(crate) fn err_at(&self, pos: usize, kind: BinaryParseErrorKind) -> BinaryParseError {
+        BinaryParseError {
+            offset: self.base + pos,
+            kind,
+        }
+    }
+
+    pub(crate) fn is_eof(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn u8(&mut self) -> BinResult<u8> {
+        let [b] = self.array()?;
+        Ok(b)
+    }
+
+    pub(crate) fn u32(&mut self) -> BinResult<u32> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn u64(&mut self) -> BinResult<u64> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    /// Takes the next `len` bytes as-is
+    pub(crate) fn bytes(&mut self, len: usize) -> BinResult<&'a [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.pos..)
+            .and_then(|rest| rest.get(..len))
+            .ok_or_else(|| self.err(BinaryParseErrorKind::UnexpectedEof))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    pub(crate) fn array<const N: usize>(&mut self) -> BinResult<[u8; N]> {
+        let bytes = self.bytes(N)?;
+        Ok(bytes.try_into().expect("Slice is the correct length"))
+    }
+
+    pub(crate) fn c_str(&mut self) -> BinResult<&'a str> {
+        let rest = &self.bytes[self.pos..];
+        let len = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| self.err_at(self.bytes.len(), BinaryParseErrorKind::UnexpectedEof))?;
+        let s = std::str::from_utf8(&rest[..len])
+            .map_err(|_| self.err(BinaryParseErrorKind::InvalidUtf8))?;
+        // Skip past the null terminator too
+        self.pos += len + 1;
+        Ok(s)
+    }
+
+    fn key(&mut self) -> BinResult<Cow<'a, str>> {
+        match self.key_table {
+            Some(table) => {
+                let start = self.pos;
+                let index = self.u32()?;
+                let key = table.get(index as usize).ok_or_else(|| {
+                    self.err_at(start, BinaryParseErrorKind::InvalidKeyIndex(index))
+                })?;
+                Ok(Cow::Borrowed(*key))
+            }
+            None => self.c_str().map(Cow::Borrowed),
+        }
+    }
+
+    fn wide_str(&mut self) -> BinResult<Cow<'a, str>> {
+        let start = self.pos;
+        let mut units = Vec::new();
+        loop {
+            let unit = u16::from_le_bytes(self.array()?);
+            if unit == 0 {
+                break;
+            }
+            units.push(unit);
+        }
+
+        let s = String::from_utf16(&units)
+            .map_err(|_| self.err_at(start, BinaryParseErrorKind::InvalidUtf16))?;
+        Ok(Cow::Owned(s))
+    }
+
+    fn value(&mut self, tag: u8) -> BinResult<Value<'a>> {
+        let value = match tag {
+            TAG_OBJ => Value::Obj(self.obj_body()?),
+            TAG_STR => Value::Str(Cow::Borrowed(self.c_str()?)),
+            TAG_INT => Value::Typed(TypedValue::Int(i32::from_le_bytes(self.array()?))),
+            TAG_FLOAT => Value::Typed(TypedValue::Float(f32::from_le_bytes(self.array()?))),
+            TAG_PTR => Value::Typed(TypedValue::Ptr(u32::from_le_bytes(self.array()?))),
+            TAG_WIDE_STR => Value::Typed(TypedValue::WideStr(self.wide_str()?)),
+            TAG_COLOR => Value::Typed(TypedValue::Color(self.array()?)),
+            TAG_UINT64 => Value::Typed(TypedValue::UInt64(u64::from_le_bytes(self.array()?))),
+            TAG_INT64 => Value::Typed(TypedValue::Int64(i64::from_le_bytes(self.array()?))),
+            _ => unreachable!("Tags are validated before reading the key"),
+        };
+
+        Ok(value)
+    }
+
+    // Reads the tag for the next pair returning `None` when it's an end tag
+    fn tag(&mut self) -> BinResult<Option<u8>> {
+        match self.u8()? {
+            TAG_END | TAG_ALT_END => Ok(None),
+            tag @ (TAG_OBJ | TAG_STR | TAG_INT | TAG_FLOAT | TAG_PTR | TAG_WIDE_STR | TAG_COLOR
+            | TAG_UINT64 | TAG_INT64) => Ok(Some(tag)),
+            invalid => {
+                self.pos -= 1;
+                Err(self.err(BinaryParseErrorKind::InvalidType(invalid)))
+            }
+        }
+    }
+
+    fn obj_body(&mut self) -> BinResult<Obj<'a>> {
+        self.depth += 1;
+        if self.max_depth.map_or(false, |max| self.depth > max) {
+            return Err(self.err(BinaryParseErrorKind::DepthExceeded));
+        }
+
+        let mut obj = Obj::new();
+        while let Some(tag) = self.tag()? {
+            let key = self.key()?;
+            let value = self.value(tag)?;
+            obj.push(key, value, None);
+        }
+
+        self.depth -= 1;
+        Ok(obj)
+    }
+
+    /// Reads a pair that's embedded in a larger input along with the end tag that follows it
+    pub(crate) fn embedded_pair(&mut self) -> BinResult<Vdf<'a>> {
+        let missing_pair = self.err(BinaryParseErrorKind::MissingPair);
+        let tag = if self.is_eof() { None } else { self.tag()? };
+        let tag = tag.ok_or(missing_pair)?;
+        let key = self.key()?;
+        let value = self.value(tag)?;
+
+        // The top-level pair is typically followed by the end tag for the implicit root object
+        if let Some(&(TAG_END | TAG_ALT_END)) = self.bytes.get(self.pos) {
+            self.pos += 1;
+        }
+
+        Ok(Vdf::new(key, value))
+    }
+
+    pub(crate) fn top_level_pair(&mut self) -> BinResult<Vdf<'a>> {
+        let vdf = self.embedded_pair()?;
+        if !self.is_eof() {
+            return Err(self.err(BinaryParseErrorKind::TrailingBytes));
+        }
+
+        Ok(vdf)
+    }
+}
@@ -0,0 +1,123 @@
+use std::io::{self, Write};
+
+use super::{
+    TAG_COLOR, TAG_END, TAG_FLOAT, TAG_INT, TAG_INT64, TAG_OBJ, TAG_PTR, TAG_STR, TAG_UINT64,
+    TAG_WIDE_STR,
+};
+use crate::{Obj, TypedValue, Value, Vdf};
+
+fn write_c_str(writer: &mut impl Write, s: &str) -> io::Result<()> {
+    if s.contains('\0') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "binary KeyValues strings can't contain null bytes",
+        ));
+    }
+
+    writer.write_all(s.as_bytes())?;
+    writer.write_all(&[0])
+}
+
+fn write_wide_str(writer: &mut impl Write, s: &str) -> io::Result<()> {
+    if s.contains('\0') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "binary KeyValues wide strings can't contain null characters",
+        ));
+    }
+
+    for unit in s.encode_utf16().chain(std::iter::once(0)) {
+        writer.write_all(&unit.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+// Writes everything for the pair other than the contents of an object value
+fn write_pair_start(writer: &mut impl Write, key: &str, value: &Value<'_>) -> io::Result<()> {
+    let tag = match value {
+        Value::Str(_) => TAG_STR,
+        Value::Obj(_) => TAG_OBJ,
+        Value::Typed(typed) => match typed {
+            TypedValue::Int(_) => TAG_INT,
+            TypedValue::Float(_) => TAG_FLOAT,
+            TypedValue::Ptr(_) => TAG_PTR,
+            TypedValue::WideStr(_) => TAG_WIDE_STR,
+            TypedValue::Color(_) => TAG_COLOR,
+            TypedValue::UInt64(_) => TAG_UINT64,
+            TypedValue::Int64(_) => TAG_INT64,
+        },
+    };
+    writer.write_all(&[tag])?;
+    write_c_str(writer, key)?;
+
+    match value {
+        Value::Str(s) => write_c_str(writer, s),
+        Value::Obj(_) => Ok(()),
+        Value::Typed(typed) => match typed {
+            TypedValue::Int(i) => writer.write_all(&i.to_le_bytes()),
+            TypedValue::Float(f) => writer.write_all(&f.to_le_bytes()),
+            TypedValue::Ptr(p) => writer.write_all(&p.to_le_bytes()),
+            TypedValue::WideStr(s) => write_wide_str(writer, s),
+            TypedValue::Color(c) => writer.write_all(c),
+            TypedValue::UInt64(u) => writer.write_all(&u.to_le_bytes()),
+            TypedValue::Int64(i) => writer.write_all(&i.to_le_bytes()),
+        },
+    }
+}
+
+// Writes the pairs within `obj` followed by its end tag
+fn write_obj(writer: &mut impl Write, obj: &Obj<'_>) -> io::Result<()> {
+    let mut stack = vec![obj.ordered_pairs().into_iter()];
+    while let Some(pairs) = stack.last_mut() {
+        let Some((key, _, value)) = pairs.next() else {
+            stack.pop();
+            writer.write_all(&[TAG_END])?;
+            continue;
+        };
+
+        write_pair_start(writer, key, value)?;
+        if let Value::Obj(inner) = value {
+            stack.push(inner.ordered_pairs().into_iter());
+        }
+    }
+
+    Ok(())
+}
+
+impl Vdf<'_> {
+    /// Renders the [`Vdf`] as binary KeyValues
+    ///
+    /// Pairs are written in the order that they were parsed (or [`pushed`][Obj::push]) in, and
+    /// each leaf keeps the wire type it was read with, so parsing and then rendering an unchanged
+    /// document gives back the same bytes. [`Conditional`][crate::Conditional]s can't be
+    /// represented in the binary format, so they're skipped
+    ///
+    /// This does lots of small writes, so wrapping something like a file in a
+    /// [`BufWriter`][std::io::BufWriter] is recommended
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with [`io::ErrorKind::InvalidInput`] if a key or string contains a null
+    /// character since that's used as the terminator. Any errors from the `writer` are returned
+    /// as-is
+    ///
+    /// ```
+    /// use keyvalues_parser::Vdf;
+    ///
+    /// let bytes = b"\x00shortcut\x00\x01AppName\x00Game\x00\x02appid\x00\x0f\x00\x00\x00\x08\x08";
+    /// let vdf = Vdf::parse_binary(bytes)?;
+    ///
+    /// let mut rendered = Vec::new();
+    /// vdf.render_binary(&mut rendered)?;
+    /// assert_eq!(rendered, bytes);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn render_binary(&self, mut writer: impl Write) -> io::Result<()> {
+        write_pair_start(&mut writer, &self.key, &self.value)?;
+        if let Value::Obj(obj) = &self.value {
+            write_obj(&mut writer, obj)?;
+        }
+        writer.write_all(&[TAG_END])
+    }
+}
@@ -0,0 +1,123 @@
+use std::{borrow::Cow, fmt};
+
+/// A conditional tag attached to a key-value pair e.g. `[$WIN32]` or `[!$X360 && $POSIX]`
+///
+/// Valve's KeyValues allows for pairs to only be conditionally included based on which platform
+/// symbols are defined. The symbols are stored without their leading `$`
+///
+/// ```
+/// use keyvalues_parser::Conditional;
+///
+/// let vdf = keyvalues_parser::parse(r#""key" "value" [$WIN32 || $OSX]"#)?;
+/// let cond = vdf.conditional.unwrap();
+/// assert_eq!(cond.to_string(), "[$WIN32 || $OSX]");
+/// assert!(cond.evaluate(&["OSX"]));
+/// assert!(!cond.evaluate(&["LINUX", "POSIX"]));
+/// # Ok::<(), keyvalues_parser::error::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Conditional<'text> {
+    /// A single symbol e.g. `$WIN32`
+    Symbol(Cow<'text, str>),
+    /// Negation e.g. `!$X360`
+    Not(Box<Conditional<'text>>),
+    /// Both sides must hold e.g. `$POSIX && $DECK`
+    And(Box<Conditional<'text>>, Box<Conditional<'text>>),
+    /// Either side must hold e.g. `$WIN32 || $OSX`
+    Or(Box<Conditional<'text>>, Box<Conditional<'text>>),
+}
+
+impl<'text> Conditional<'text> {
+    /// Creates a conditional for a single symbol
+    ///
+    /// A leading `$` is ignored, so both `"WIN32"` and `"$WIN32"` create the same conditional
+    pub fn symbol(name: impl Into<Cow<'text, str>>) -> Self {
+        match name.into() {
+            Cow::Borrowed(s) => Self::Symbol(Cow::Borrowed(s.strip_prefix('$').unwrap_or(s))),
+            Cow::Owned(s) => match s.strip_prefix('$') {
+                Some(stripped) => Self::Symbol(Cow::Owned(stripped.to_owned())),
+                None => Self::Symbol(Cow::Owned(s)),
+            },
+        }
+    }
+
+    /// Evaluates the conditional against a set of defined symbols
+    ///
+    /// Symbols are matched ASCII case-insensitively like Valve's implementation and a leading `$`
+    /// on the provided symbols is ignored
+    pub fn evaluate(&self, defined: &[&str]) -> bool {
+        match self {
+            Self::Symbol(name) => defined.iter().any(|sym| {
+                sym.strip_prefix('$')
+                    .unwrap_or(sym)
+                    .eq_ignore_ascii_case(name)
+            }),
+            Self::Not(inner) => !inner.evaluate(defined),
+            Self::And(left, right) => left.evaluate(defined) && right.evaluate(defined),
+            Self::Or(left, right) => left.evaluate(defined) || right.evaluate(defined),
+        }
+    }
+
+    /// Converts the conditional to one that owns all of its data
+    pub fn into_owned(self) -> Conditional<'static> {
+        match self {
+            Self::Symbol(name) => Conditional::Symbol(Cow::Owned(name.into_owned())),
+            Self::Not(inner) => Conditional::Not(Box::new(inner.into_owned())),
+            Self::And(left, right) => {
+                Conditional::And(Box::new(left.into_owned()), Box::new(right.into_owned()))
+            }
+            Self::Or(left, right) => {
+                Conditional::Or(Box::new(left.into_owned()), Box::new(right.into_owned()))
+            }
+        }
+    }
+
+    // Binding strength used to decide when sub-expressions need parens when rendering
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Or(..) => 0,
+            Self::And(..) => 1,
+            Self::Not(_) | Self::Symbol(_) => 2,
+        }
+    }
+
+    fn write_expr(&self, f: &mut fmt::Formatter<'_>, min_precedence: u8) -> fmt::Result {
+        let needs_parens = self.precedence() < min_precedence;
+        if needs_parens {
+            f.write_str("(")?;
+        }
+
+        match self {
+            Self::Symbol(name) => write!(f, "${name}")?,
+            Self::Not(inner) => {
+                f.write_str("!")?;
+                inner.write_expr(f, 2)?;
+            }
+            Self::And(left, right) => {
+                left.write_expr(f, 1)?;
+                f.write_str(" && ")?;
+                right.write_expr(f, 2)?;
+            }
+            Self::Or(left, right) => {
+                left.write_expr(f, 0)?;
+                f.write_str(" || ")?;
+                right.write_expr(f, 1)?;
+            }
+        }
+
+        if needs_parens {
+            f.write_str(")")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Renders the conditional including the surrounding brackets e.g. `[!$X360]`
+impl fmt::Display for Conditional<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[")?;
+        self.write_expr(f, 0)?;
+        f.write_str("]")
+    }
+}
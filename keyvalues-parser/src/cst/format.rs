@@ -0,0 +1,263 @@
+use std::borrow::Cow;
+
+use super::{Document, Obj, Pair, Token, Trivia, Value};
+use crate::{Renderer, Separator};
+
+impl Document<'_> {
+    /// Rewrites all of the formatting to the canonical style of `renderer`
+    ///
+    /// Every token is written out fresh with the renderer's quoting, indentation, separator, and
+    /// newlines. Comments and the order of everything are kept while runs of blank lines get
+    /// collapsed down to a single one. Formatting is idempotent and never changes what the
+    /// document parses to. Comments need to end their lines, so [`Renderer::minified()`] is
+    /// ignored
+    ///
+    /// ```
+    /// use keyvalues_parser::{cst::Document, Renderer};
+    /// let text = "// Volume\nConfig {   volume 80 // muted later\n\n\n   \"fov\" \"90\" }";
+    /// let mut doc = Document::parse(text)?;
+    /// doc.format(&Renderer::new().indent("    "));
+    /// assert_eq!(
+    ///     doc.to_string(),
+    ///     "// Volume\n\"Config\"\n{\n    \"volume\"\t\"80\" // muted later\n\n    \"fov\"\t\"90\"\n}\n",
+    /// );
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub fn format(&mut self, renderer: &Renderer) {
+        let formatter = Formatter {
+            renderer,
+            escape: !self.literal_special_chars,
+        };
+
+        for (i, directive) in self.directives.iter_mut().enumerate() {
+            let place = if i == 0 { Place::Start } else { Place::Line };
+            directive.leading = formatter.lines(&directive.leading, place, "", Some(("", true)));
+            directive.gap = formatter.gap(&directive.gap, "", " ");
+            // Paths are always quoted like the regular renderer does
+            directive.path.reset(true);
+        }
+
+        let place = if self.directives.is_empty() {
+            Place::Start
+        } else {
+            Place::AfterDirectives
+        };
+        formatter.reset_token(&mut self.pair.key);
+        let key_width = formatter.width(&self.pair.key);
+        formatter.pair(&mut self.pair, place, "", key_width);
+
+        // A trailing null byte stays at the very end
+        let (trivia, null) = match self.trailing.split_once('\0') {
+            Some((before, after)) => (format!("{before}{after}"), "\0"),
+            None => (self.trailing.to_string(), ""),
+        };
+        let mut trailing = formatter.lines(&trivia, Place::Line, "", None).into_owned();
+        trailing.push_str(null);
+        self.trailing = Cow::Owned(trailing);
+    }
+}
+
+/// Formats `text` in the canonical style of [`Renderer::new()`]
+///
+/// This is shorthand for parsing a document with default settings, formatting it with
+/// [`Document::format()`], and rendering it back out
+pub fn format(text: &str) -> crate::error::Result<String> {
+    let mut doc = Document::parse(text)?;
+    doc.format(&Renderer::new());
+    Ok(doc.to_string())
+}
+
+// Where some trivia sits, which decides where blank lines are allowed
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Place {
+    // The very start of the document
+    Start,
+    // Right after an opening brace
+    Open,
+    // Before the top-level pair when there are directives, which always get a blank line after
+    AfterDirectives,
+    // Anywhere else
+    Line,
+}
+
+struct Formatter<'a> {
+    renderer: &'a Renderer,
+    escape: bool,
+}
+
+impl Formatter<'_> {
+    fn newline(&self) -> &'static str {
+        self.renderer.newline.as_str()
+    }
+
+    fn reset_token(&self, token: &mut Token<'_>) {
+        token.reset(!self.renderer.quote_only_when_needed);
+    }
+
+    // The number of chars that the token takes up when written
+    fn width(&self, token: &Token<'_>) -> usize {
+        let mut rendered = String::new();
+        // Writing to a `String` never fails
+        let _ = token.write(&mut rendered, self.escape);
+        rendered.chars().count()
+    }
+
+    fn pair(&self, pair: &mut Pair<'_>, place: Place, indent: &str, key_width: usize) {
+        pair.leading = self.lines(&pair.leading, place, indent, Some((indent, true)));
+
+        let before_obj = format!("{}{indent}", self.newline());
+        match (&mut pair.value, &mut pair.conditional) {
+            (Value::Obj(_), None) => pair.key_gap = self.gap(&pair.key_gap, indent, &before_obj),
+            // Conditionals after an object get moved to where Valve puts them
+            (Value::Obj(_), Some(node)) => {
+                node.after_value = false;
+                pair.key_gap = self.gap(&pair.key_gap, indent, " ");
+                node.gap = self.gap(&node.gap, indent, &before_obj);
+            }
+            (Value::Str(token), conditional) => {
+                let separator = match &self.renderer.separator {
+                    Separator::Str(separator) => separator.clone(),
+                    Separator::Aligned => {
+                        let padding = key_width.saturating_sub(self.width(&pair.key)) + 1;
+                        Cow::Owned(" ".repeat(padding))
+                    }
+                };
+                pair.key_gap = self.gap(&pair.key_gap, indent, &separator);
+                self.reset_token(token);
+                if let Some(node) = conditional {
+                    node.gap = self.gap(&node.gap, indent, " ");
+                }
+            }
+        }
+        if let Some(node) = &mut pair.conditional {
+            node.raw = None;
+        }
+
+        if let Value::Obj(obj) = &mut pair.value {
+            self.obj(obj, indent);
+        }
+    }
+
+    // `indent` is the indentation of the pair that holds the object
+    fn obj(&self, obj: &mut Obj<'_>, indent: &str) {
+        for pair in &mut obj.pairs {
+            self.reset_token(&mut pair.key);
+        }
+        let key_width = match self.renderer.separator {
+            Separator::Aligned => obj
+                .pairs
+                .iter()
+                .filter(|pair| !pair.value.is_obj())
+                .map(|pair| self.width(&pair.key))
+                .max()
+                .unwrap_or(0),
+            Separator::Str(_) => 0,
+        };
+
+        let inner = format!("{indent}{}", self.renderer.indent);
+        for (i, pair) in obj.pairs.iter_mut().enumerate() {
+            let place = if i == 0 { Place::Open } else { Place::Line };
+            self.pair(pair, place, &inner, key_width);
+        }
+
+        let place = if obj.pairs.is_empty() {
+            Place::Open
+        } else {
+            Place::Line
+        };
+        // Never a blank line before the closing brace
+        obj.closing = self.lines(&obj.closing, place, &inner, Some((indent, false)));
+    }
+
+    // Lays out trivia that ends its line. Each comment gets its own line at `indent` unless it
+    // trails the previous token on the same line. `next` is the indentation of the following token
+    // and whether a blank line is allowed before it, or `None` at the end of the document
+    fn lines(
+        &self,
+        trivia: &str,
+        place: Place,
+        indent: &str,
+        next: Option<(&str, bool)>,
+    ) -> Trivia<'static> {
+        let newline = self.newline();
+        let (comments, trailing_newlines) = comments(trivia);
+        let mut out = String::new();
+        let mut first_line = true;
+        let mut start_line = |out: &mut String, newlines: usize, indent: &str, blank_ok: bool| {
+            if !(first_line && place == Place::Start) {
+                out.push_str(newline);
+                let collapsed = newlines >= 2 && blank_ok && !(first_line && place == Place::Open);
+                if collapsed || (first_line && place == Place::AfterDirectives) {
+                    out.push_str(newline);
+                }
+            }
+            out.push_str(indent);
+            first_line = false;
+        };
+
+        for (i, &(newlines, comment)) in comments.iter().enumerate() {
+            if i == 0 && newlines == 0 && place != Place::Start {
+                out.push(' ');
+            } else {
+                start_line(&mut out, newlines, indent, true);
+            }
+            out.push_str(comment);
+        }
+
+        match next {
+            Some((indent, blank_ok)) => start_line(&mut out, trailing_newlines, indent, blank_ok),
+            None => out.push_str(newline),
+        }
+
+        Cow::Owned(out)
+    }
+
+    // Lays out trivia between the parts of a pair or directive. That's just `plain` unless there
+    // are comments, in which case the next token starts a new line at `indent`
+    fn gap(&self, trivia: &str, indent: &str, plain: &str) -> Trivia<'static> {
+        let (comments, _) = comments(trivia);
+        if comments.is_empty() {
+            return Cow::Owned(plain.to_owned());
+        }
+
+        let newline = self.newline();
+        let mut out = String::new();
+        for (i, &(newlines, comment)) in comments.iter().enumerate() {
+            if i == 0 && newlines == 0 {
+                out.push(' ');
+            } else {
+                out.push_str(newline);
+                out.push_str(indent);
+            }
+            out.push_str(comment);
+        }
+        out.push_str(newline);
+        out.push_str(indent);
+
+        Cow::Owned(out)
+    }
+}
+
+// The comments within some trivia along with the number of newlines before each of them, and the
+// number of newlines after the last one
+fn comments(trivia: &str) -> (Vec<(usize, &str)>, usize) {
+    let mut comments = Vec::new();
+    let mut newlines = 0;
+    let mut rest = trivia;
+    loop {
+        let whitespace = rest
+            .find(|c| !matches!(c, ' ' | '\t' | '\r' | '\n'))
+            .unwrap_or(rest.len());
+        newlines += rest[..whitespace].matches('\n').count();
+        rest = &rest[whitespace..];
+        if rest.is_empty() {
+            return (comments, newlines);
+        }
+
+        // Anything else is a comment that runs to the end of its line
+        let len = rest.find('\n').unwrap_or(rest.len());
+        comments.push((newlines, rest[..len].trim_end()));
+        newlines = 0;
+        rest = &rest[len..];
+    }
+}
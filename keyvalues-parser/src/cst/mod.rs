@@ -0,0 +1,619 @@
+//! A lossless concrete syntax tree for format-preserving edits
+//!
+//! Parsing to a [`Vdf`][crate::Vdf] throws away everything that isn't data, so rendering it back
+//! out loses comments, blank lines, quoting, and the original order of the pairs. A [`Document`]
+//! instead keeps all of the trivia (whitespace and comments) along with the original text of every
+//! token, so rendering an unmodified document reproduces the input byte-for-byte. Edits only
+//! affect the nodes that they touch with new nodes following the indentation of their siblings.
+//! Going the other way, [`Document::format()`] rewrites a whole document into one canonical style
+//! while keeping its comments
+//!
+//! ```
+//! use keyvalues_parser::{cst::Document, Value};
+//!
+//! let text = r#"// Launch options
+//! "UserLocalConfigStore"
+//! {
+//!     "volume"    "0.5" // muted later
+//!     "fov"       90
+//! }
+//! "#;
+//! let mut doc = Document::parse(text)?;
+//!
+//! let obj = doc.pair_mut().value_mut().get_mut_obj().unwrap();
+//! obj.get_mut("fov").unwrap().set_value(Value::Str("100".into()));
+//! let volume = obj.position("volume").unwrap();
+//! obj.insert_after(volume, "muted".into(), Value::Str("1".into()));
+//!
+//! assert_eq!(
+//!     doc.to_string(),
+//!     r#"// Launch options
+//! "UserLocalConfigStore"
+//! {
+//!     "volume"    "0.5" // muted later
+//!     "muted"     "1"
+//!     "fov"       100
+//! }
+//! "#,
+//! );
+//! # Ok::<(), keyvalues_parser::error::Error>(())
+//! ```
+
+use std::borrow::Cow;
+
+use crate::{error::Result, Conditional, Encoding, Include, Key, Parser, PartialVdf};
+
+mod format;
+pub(crate) mod parse;
+mod render;
+
+pub use format::format;
+
+/// Whitespace and comments between tokens
+type Trivia<'text> = Cow<'text, str>;
+
+/// Parses a document with default settings aka `Parser::new().parse_cst(text)`
+pub fn parse(text: &str) -> Result<Document<'_>> {
+    Parser::new().parse_cst(text)
+}
+
+/// A lossless representation of VDF text
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Document<'text> {
+    directives: Vec<Directive<'text>>,
+    pair: Pair<'text>,
+    // Anything after the top-level pair (trivia and an optional trailing null byte)
+    trailing: Cow<'text, str>,
+    literal_special_chars: bool,
+}
+
+impl<'text> Document<'text> {
+    /// Parses a document with default settings aka `Parser::new().parse_cst(text)`
+    pub fn parse(text: &'text str) -> Result<Self> {
+        parse(text)
+    }
+
+    /// The `#base` and `#include` directives in their original order
+    pub fn directives(&self) -> &[Directive<'text>] {
+        &self.directives
+    }
+
+    /// The top-level pair
+    pub fn pair(&self) -> &Pair<'text> {
+        &self.pair
+    }
+
+    pub fn pair_mut(&mut self) -> &mut Pair<'text> {
+        &mut self.pair
+    }
+
+    /// Converts the document to the regular loosely typed representation
+    pub fn to_vdf(&self) -> PartialVdf<'text> {
+        let mut bases = Vec::new();
+        let mut includes = Vec::new();
+        for (position, directive) in self.directives.iter().enumerate() {
+            let path = directive.path.value.clone();
+            match directive.kind {
+                DirectiveKind::Base => bases.push(path),
+                DirectiveKind::Include => includes.push(Include { path, position }),
+            }
+        }
+
+        let (key, value, conditional) = self.pair.to_kv();
+        PartialVdf {
+            key,
+            value,
+            conditional,
+            bases,
+            includes,
+            spans: None,
+            encoding: Encoding::default(),
+        }
+    }
+}
+
+/// An `#base` or `#include` directive
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Directive<'text> {
+    leading: Trivia<'text>,
+    kind: DirectiveKind,
+    gap: Trivia<'text>,
+    path: Token<'text>,
+}
+
+impl<'text> Directive<'text> {
+    pub fn kind(&self) -> DirectiveKind {
+        self.kind
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path.value
+    }
+
+    pub fn set_path(&mut self, path: Cow<'text, str>) {
+        self.path.set(path);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DirectiveKind {
+    Base,
+    Include,
+}
+
+/// A string along with how it was originally written
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Token<'text> {
+    value: Cow<'text, str>,
+    // The original text including any quotes. This is cleared when the value gets changed
+    raw: Option<Cow<'text, str>>,
+    quoted: bool,
+}
+
+impl<'text> Token<'text> {
+    fn parsed(value: Cow<'text, str>, raw: &'text str, quoted: bool) -> Self {
+        Self {
+            value,
+            raw: Some(Cow::Borrowed(raw)),
+            quoted,
+        }
+    }
+
+    fn new(value: Cow<'text, str>) -> Self {
+        Self {
+            value,
+            raw: None,
+            quoted: true,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+
+    /// Whether the token is written within quotes
+    ///
+    /// Unquoted tokens that are changed to a value that can't be written unquoted get quoted
+    pub fn is_quoted(&self) -> bool {
+        self.quoted
+    }
+
+    // The (approximate) number of chars that the token takes up when written
+    fn width(&self) -> usize {
+        match &self.raw {
+            Some(raw) => raw.chars().count(),
+            None => self.value.chars().count() + 2,
+        }
+    }
+
+    /// The original text of the token (including quotes) if it hasn't been changed
+    pub fn raw(&self) -> Option<&str> {
+        self.raw.as_deref()
+    }
+
+    // Forgets the original text so that the token gets written out fresh
+    fn reset(&mut self, quoted: bool) {
+        self.raw = None;
+        self.quoted = quoted;
+    }
+
+    /// Changes the value while keeping the original quoting style when possible
+    pub fn set(&mut self, value: Cow<'text, str>) {
+        if value != self.value {
+            self.value = value;
+            self.raw = None;
+        }
+    }
+}
+
+/// A key-value pair along with its optional conditional
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pair<'text> {
+    leading: Trivia<'text>,
+    key: Token<'text>,
+    key_gap: Trivia<'text>,
+    value: Value<'text>,
+    conditional: Option<CondNode<'text>>,
+}
+
+// The conditional comes after the value for strings. Objects usually have it between the key and
+// value, but it can come after them too. `gap` is the trivia between the conditional and the value
+// either way
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct CondNode<'text> {
+    cond: Conditional<'text>,
+    raw: Option<Cow<'text, str>>,
+    gap: Trivia<'text>,
+    after_value: bool,
+}
+
+impl<'text> CondNode<'text> {
+    fn parsed(
+        cond: Conditional<'text>,
+        raw: &'text str,
+        gap: Trivia<'text>,
+        after_value: bool,
+    ) -> Self {
+        Self {
+            cond,
+            raw: Some(Cow::Borrowed(raw)),
+            gap,
+            after_value,
+        }
+    }
+}
+
+impl<'text> Pair<'text> {
+    // Creates a new pair at `indent` following Valve's layout
+    fn new(
+        leading: String,
+        key: Key<'text>,
+        value: crate::Value<'text>,
+        conditional: Option<Conditional<'text>>,
+        indent: &str,
+        unit: &str,
+    ) -> Self {
+        let mut pair = Self {
+            leading: Cow::Owned(leading),
+            key: Token::new(key),
+            key_gap: Cow::Borrowed(""),
+            value: Value::new(value, indent, unit),
+            conditional: None,
+        };
+        pair.reset_gaps(indent);
+        pair.set_conditional(conditional);
+        pair
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key.value
+    }
+
+    pub fn key_token(&self) -> &Token<'text> {
+        &self.key
+    }
+
+    pub fn set_key(&mut self, key: Key<'text>) {
+        self.key.set(key);
+    }
+
+    pub fn value(&self) -> &Value<'text> {
+        &self.value
+    }
+
+    pub fn value_mut(&mut self) -> &mut Value<'text> {
+        &mut self.value
+    }
+
+    /// Replaces the value
+    ///
+    /// Setting a string on a string keeps its original quoting style while anything else is
+    /// written out fresh using the pair's indentation
+    pub fn set_value(&mut self, value: crate::Value<'text>) {
+        let value = match (&mut self.value, value) {
+            (Value::Str(token), crate::Value::Str(s)) => return token.set(s),
+            (_, value) => value,
+        };
+
+        let indent = indent(&self.leading).to_owned();
+        let unit = match &self.value {
+            Value::Obj(obj) => obj.indent_unit(),
+            Value::Str(_) => "\t".to_owned(),
+        };
+        let was_obj = self.value.is_obj();
+        self.value = Value::new(value, &indent, &unit);
+        if was_obj != self.value.is_obj() {
+            self.reset_gaps(&indent);
+        }
+    }
+
+    pub fn conditional(&self) -> Option<&Conditional<'text>> {
+        self.conditional.as_ref().map(|node| &node.cond)
+    }
+
+    /// Sets (or clears with `None`) the pair's conditional
+    pub fn set_conditional(&mut self, conditional: Option<Conditional<'text>>) {
+        match (&mut self.conditional, conditional) {
+            (Some(node), Some(cond)) => {
+                if node.cond != cond {
+                    node.cond = cond;
+                    node.raw = None;
+                }
+            }
+            (None, Some(cond)) => {
+                let gap = if self.value.is_obj() {
+                    std::mem::replace(&mut self.key_gap, Cow::Borrowed(" "))
+                } else {
+                    Cow::Borrowed(" ")
+                };
+                self.conditional = Some(CondNode {
+                    cond,
+                    raw: None,
+                    gap,
+                    after_value: !self.value.is_obj(),
+                });
+            }
+            (Some(_), None) => {
+                let node = self.conditional.take().unwrap();
+                if !node.after_value {
+                    self.key_gap = node.gap;
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    // Lays out the gaps around the value like Valve does after the value's kind changes
+    fn reset_gaps(&mut self, indent: &str) {
+        let before_obj = Cow::Owned(format!("\n{indent}"));
+        match (&self.value, &mut self.conditional) {
+            (Value::Str(_), None) => self.key_gap = Cow::Borrowed("\t"),
+            (Value::Str(_), Some(node)) => {
+                self.key_gap = Cow::Borrowed("\t");
+                node.gap = Cow::Borrowed(" ");
+                node.after_value = true;
+            }
+            (Value::Obj(_), None) => self.key_gap = before_obj,
+            (Value::Obj(_), Some(node)) => {
+                self.key_gap = Cow::Borrowed(" ");
+                node.gap = before_obj;
+                node.after_value = false;
+            }
+        }
+    }
+
+    fn to_kv(&self) -> (Key<'text>, crate::Value<'text>, Option<Conditional<'text>>) {
+        (
+            self.key.value.clone(),
+            self.value.to_value(),
+            self.conditional().cloned(),
+        )
+    }
+}
+
+/// A value which is either a string or an object
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value<'text> {
+    Str(Token<'text>),
+    Obj(Obj<'text>),
+}
+
+impl<'text> Value<'text> {
+    // Creates a new value where `indent` is the indentation of its pair
+    fn new(value: crate::Value<'text>, indent: &str, unit: &str) -> Self {
+        match value {
+            crate::Value::Str(s) => Self::Str(Token::new(s)),
+            crate::Value::Typed(typed) => Self::Str(Token::new(Cow::Owned(typed.to_string()))),
+            crate::Value::Obj(obj) => {
+                let inner_indent = format!("{indent}{unit}");
+                let pairs = obj
+                    .ordered_pairs()
+                    .into_iter()
+                    .map(|(key, index, value)| {
+                        Pair::new(
+                            format!("\n{inner_indent}"),
+                            key.clone(),
+                            value.clone(),
+                            obj.conditional(key, index).cloned(),
+                            &inner_indent,
+                            unit,
+                        )
+                    })
+                    .collect();
+                Self::Obj(Obj {
+                    pairs,
+                    closing: Cow::Owned(format!("\n{indent}")),
+                })
+            }
+        }
+    }
+
+    pub fn is_obj(&self) -> bool {
+        self.get_obj().is_some()
+    }
+
+    pub fn get_str(&self) -> Option<&str> {
+        match self {
+            Self::Str(token) => Some(token.as_str()),
+            Self::Obj(_) => None,
+        }
+    }
+
+    pub fn get_obj(&self) -> Option<&Obj<'text>> {
+        match self {
+            Self::Obj(obj) => Some(obj),
+            Self::Str(_) => None,
+        }
+    }
+
+    pub fn get_mut_obj(&mut self) -> Option<&mut Obj<'text>> {
+        match self {
+            Self::Obj(obj) => Some(obj),
+            Self::Str(_) => None,
+        }
+    }
+
+    fn to_value(&self) -> crate::Value<'text> {
+        match self {
+            Self::Str(token) => crate::Value::Str(token.value.clone()),
+            Self::Obj(obj) => {
+                let mut kv_obj = crate::Obj::new();
+                for pair in &obj.pairs {
+                    let (key, value, conditional) = pair.to_kv();
+                    kv_obj.push(key, value, conditional);
+                }
+                crate::Value::Obj(kv_obj)
+            }
+        }
+    }
+}
+
+/// An object of pairs in their original order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Obj<'text> {
+    pairs: Vec<Pair<'text>>,
+    // The trivia before the closing `}`
+    closing: Trivia<'text>,
+}
+
+impl<'text> Obj<'text> {
+    pub fn pairs(&self) -> &[Pair<'text>] {
+        &self.pairs
+    }
+
+    pub fn pairs_mut(&mut self) -> &mut [Pair<'text>] {
+        &mut self.pairs
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Returns the index of the first pair with `key`
+    pub fn position(&self, key: &str) -> Option<usize> {
+        self.pairs.iter().position(|pair| pair.key() == key)
+    }
+
+    /// Returns the first pair with `key`
+    pub fn get(&self, key: &str) -> Option<&Pair<'text>> {
+        self.pairs.iter().find(|pair| pair.key() == key)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Pair<'text>> {
+        self.pairs.iter_mut().find(|pair| pair.key() == key)
+    }
+
+    /// Inserts a new pair directly after the pair at `index`
+    ///
+    /// The new pair uses the same indentation as the pair at `index`. A comment that trails the
+    /// pair at `index` on the same line stays with it
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds
+    pub fn insert_after(
+        &mut self,
+        index: usize,
+        key: Key<'text>,
+        value: crate::Value<'text>,
+    ) -> &mut Pair<'text> {
+        assert!(index < self.pairs.len(), "Index out of bounds");
+        self.insert(index + 1, key, value)
+    }
+
+    /// Appends a new pair after all of the existing pairs
+    pub fn push(&mut self, key: Key<'text>, value: crate::Value<'text>) -> &mut Pair<'text> {
+        self.insert(self.pairs.len(), key, value)
+    }
+
+    /// Removes the pair at `index` along with its leading comments and any comment trailing it on
+    /// the same line
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds
+    pub fn remove(&mut self, index: usize) -> Pair<'text> {
+        let removed = self.pairs.remove(index);
+        // The start of the removed pair's trivia can hold a comment that trails the previous pair
+        let (prev_comment, _) = split_same_line_comment(&removed.leading);
+        let next = self.trivia_at(index);
+        let (_, rest) = split_same_line_comment(next);
+        *next = Cow::Owned(format!("{prev_comment}{rest}"));
+        removed
+    }
+
+    fn insert(
+        &mut self,
+        index: usize,
+        key: Key<'text>,
+        value: crate::Value<'text>,
+    ) -> &mut Pair<'text> {
+        let unit = self.indent_unit();
+        let (leading, indent) = match index.checked_sub(1).map(|i| &self.pairs[i]) {
+            // Follow the previous sibling
+            Some(prev) if prev.leading.contains('\n') => {
+                let indent = indent(&prev.leading).to_owned();
+                (format!("\n{indent}"), indent)
+            }
+            Some(_) => (" ".to_owned(), String::new()),
+            // Or indent one level past the closing brace
+            None if self.closing.contains('\n') => {
+                let indent = format!("{}{unit}", indent(&self.closing));
+                (format!("\n{indent}"), indent)
+            }
+            None => {
+                if self.closing.is_empty() {
+                    self.closing = Cow::Borrowed(" ");
+                }
+                (" ".to_owned(), String::new())
+            }
+        };
+
+        // A comment trailing the previous pair on the same line stays with it
+        let next = self.trivia_at(index);
+        let (comment, rest) = split_same_line_comment(next);
+        let leading = format!("{comment}{leading}");
+        *next = Cow::Owned(rest.to_owned());
+
+        let mut pair = Pair::new(leading, key, value, None, &indent, &unit);
+        // Follow the previous pair's spacing between the key and value. Spaces are used to align
+        // values to the same column while tabs get copied as-is
+        if let Some(prev) = index.checked_sub(1).map(|i| &self.pairs[i]) {
+            let gap = &prev.key_gap;
+            if !prev.value.is_obj() && !pair.value.is_obj() && !gap.is_empty() {
+                if gap.chars().all(|c| c == ' ') {
+                    let column = prev.key.width() + gap.len();
+                    let spaces = column.saturating_sub(pair.key.width()).max(1);
+                    pair.key_gap = Cow::Owned(" ".repeat(spaces));
+                } else if gap.chars().all(|c| c == '\t') {
+                    pair.key_gap = gap.clone();
+                }
+            }
+        }
+        self.pairs.insert(index, pair);
+        &mut self.pairs[index]
+    }
+
+    // The leading trivia of the pair at `index` or the closing trivia when past the end
+    fn trivia_at(&mut self, index: usize) -> &mut Trivia<'text> {
+        match self.pairs.get_mut(index) {
+            Some(pair) => &mut pair.leading,
+            None => &mut self.closing,
+        }
+    }
+
+    // The extra indentation that pairs get over the closing brace
+    fn indent_unit(&self) -> String {
+        self.pairs
+            .first()
+            .and_then(|pair| {
+                let inner = indent(&pair.leading);
+                let unit = inner.strip_prefix(indent(&self.closing))?;
+                (!unit.is_empty() && self.closing.contains('\n')).then(|| unit.to_owned())
+            })
+            .unwrap_or_else(|| "\t".to_owned())
+    }
+}
+
+// The whitespace on the last line of some trivia
+fn indent(trivia: &str) -> &str {
+    let last_line = trivia.rsplit('\n').next().unwrap_or_default();
+    let len = last_line
+        .find(|c: char| !matches!(c, ' ' | '\t'))
+        .unwrap_or(last_line.len());
+    &last_line[..len]
+}
+
+// Splits off a comment at the start of some trivia that's on the same line as the previous token
+fn split_same_line_comment(trivia: &str) -> (&str, &str) {
+    let first_line_len = trivia.find('\n').unwrap_or(trivia.len());
+    if trivia[..first_line_len].contains("//") {
+        trivia.split_at(first_line_len)
+    } else {
+        ("", trivia)
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    borrow::Cow,
+    collections::{btree_map::Entry, BTreeMap},
+};
+
+use super::{CondNode, Directive, DirectiveKind, Document, Obj, Pair, Token, Value};
+use crate::{
+    error::{Limit, ParseError, ParseErrorKind},
+    span::LineIndex,
+    text::{
+        lex::{self, Cursor, RawStr},
+        parse::check_input_len,
+    },
+    Conditional, DuplicateKeys, Limits, Parser, Position,
+};
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+// Where each key in an object was first used. Only tracked when duplicate keys are errors
+type KeyOffsets<'text> = BTreeMap<Cow<'text, str>, usize>;
+
+// This reports the same errors as strict parsing with the regular parser, so each check happens in
+// the same order and points at the same place
+pub(crate) fn parse<'text>(text: &'text str, parser: &Parser) -> Result<Document<'text>> {
+    if let Some(err) = check_input_len(text.len(), parser) {
+        return Err(err);
+    }
+
+    let mut scanner = Scanner {
+        cursor: Cursor::new(text),
+        literal_special_chars: parser.literal_special_chars,
+        case_insensitive_keys: parser.case_insensitive_keys,
+        duplicate_keys: parser.duplicate_keys,
+        limits: parser.limits,
+        depth: 0,
+        pairs: 0,
+    };
+
+    let mut directives = Vec::new();
+    let mut leading = scanner.skip();
+    while let Some(directive) = scanner.try_directive(leading.clone()) {
+        directives.push(directive);
+        leading = scanner.skip();
+    }
+    match scanner.cursor.peek() {
+        None | Some(b'{' | b'}') => {
+            return scanner.error(ParseErrorKind::MissingKey, scanner.cursor.pos)
+        }
+        Some(_) => {}
+    }
+    let pair = scanner.pair(leading)?;
+
+    let trailing = Cow::Borrowed(scanner.cursor.rest());
+    scanner.skip();
+    scanner.cursor.eat(b"\0");
+    scanner.skip();
+    if scanner.cursor.peek().is_some() {
+        return scanner.error(ParseErrorKind::TrailingContent, scanner.cursor.pos);
+    }
+
+    Ok(Document {
+        directives,
+        pair,
+        trailing,
+        literal_special_chars: parser.literal_special_chars,
+    })
+}
+
+struct Scanner<'text> {
+    cursor: Cursor<'text>,
+    literal_special_chars: bool,
+    case_insensitive_keys: bool,
+    duplicate_keys: DuplicateKeys,
+    limits: Limits,
+    depth: usize,
+    pairs: usize,
+}
+
+impl<'text> Scanner<'text> {
+    fn position(&self, offset: usize) -> Position {
+        LineIndex::new(self.cursor.text).position(offset)
+    }
+
+    fn error<T>(&self, kind: ParseErrorKind, offset: usize) -> Result<T> {
+        let text = self.cursor.text;
+        Err(ParseError::new(text, &LineIndex::new(text), kind, offset))
+    }
+
+    fn check_limit(&self, limit: Limit, value: usize, offset: usize) -> Result<()> {
+        match self.limits.check(limit, value) {
+            Some(kind) => self.error(kind, offset),
+            None => Ok(()),
+        }
+    }
+
+    fn skip(&mut self) -> Cow<'text, str> {
+        let start = self.cursor.pos;
+        self.cursor.skip();
+        Cow::Borrowed(self.cursor.since(start))
+    }
+
+    // A directive that fails to parse is actually the key of the top-level pair
+    fn try_directive(&mut self, leading: Cow<'text, str>) -> Option<Directive<'text>> {
+        let start = self.cursor.pos;
+        let kind = if self.cursor.directive_keyword()? {
+            DirectiveKind::Base
+        } else {
+            DirectiveKind::Include
+        };
+
+        let gap = self.skip();
+        let path_start = self.cursor.pos;
+        match self.cursor.directive_path() {
+            Some((path, quoted)) => {
+                let raw = self.cursor.since(path_start);
+                Some(Directive {
+                    leading,
+                    kind,
+                    gap,
+                    path: Token::parsed(Cow::Borrowed(path), raw, quoted),
+                })
+            }
+            None => {
+                self.cursor.pos = start;
+                None
+            }
+        }
+    }
+
+    fn pair(&mut self, leading: Cow<'text, str>) -> Result<Pair<'text>> {
+        self.pairs += 1;
+        self.check_limit(Limit::Pairs, self.pairs, self.cursor.pos)?;
+        let key = self.string()?;
+        let key_gap = self.skip();
+
+        // A conditional between the key and value is only valid when the value is an object
+        let before_cond = self.cursor.pos;
+        if let Some((cond, raw)) = self.try_conditional()? {
+            let gap = self.skip();
+            if self.cursor.peek() == Some(b'{') {
+                return Ok(Pair {
+                    leading,
+                    key,
+                    key_gap,
+                    value: Value::Obj(self.obj()?),
+                    conditional: Some(CondNode::parsed(cond, raw, gap, false)),
+                });
+            }
+        }
+        self.cursor.pos = before_cond;
+
+        let value = match self.cursor.peek() {
+            None | Some(b'}') => return self.error(ParseErrorKind::MissingValue, self.cursor.pos),
+            Some(b'{') => Value::Obj(self.obj()?),
+            Some(_) => Value::Str(self.string()?),
+        };
+
+        let before_cond = self.cursor.pos;
+        let gap = self.skip();
+        let conditional = match self.try_conditional()? {
+            Some((cond, raw)) => Some(CondNode::parsed(cond, raw, gap, true)),
+            None => {
+                self.cursor.pos = before_cond;
+                None
+            }
+        };
+
+        Ok(Pair {
+            leading,
+            key,
+            key_gap,
+            value,
+            conditional,
+        })
+    }
+
+    fn obj(&mut self) -> Result<Obj<'text>> {
+        let opening = self.cursor.pos;
+        assert!(self.cursor.eat(b"{"), "Only called on an opening brace");
+        self.depth += 1;
+        self.check_limit(Limit::Depth, self.depth, opening)?;
+
+        let mut key_offsets = KeyOffsets::new();
+        let mut pairs = Vec::new();
+        loop {
+            let leading = self.skip();
+            let pos = self.cursor.pos;
+            match self.cursor.peek() {
+                None => {
+                    let opening = self.position(opening);
+                    return self.error(ParseErrorKind::UnbalancedBrace { opening }, pos);
+                }
+                Some(b'}') => {
+                    self.cursor.pos += 1;
+                    self.depth -= 1;
+                    return Ok(Obj {
+                        pairs,
+                        closing: leading,
+                    });
+                }
+                Some(b'{') => return self.error(ParseErrorKind::MissingKey, pos),
+                Some(_) => {
+                    let pair = self.pair(leading)?;
+                    self.check_duplicate(&mut key_offsets, &pair.key, pos)?;
+                    pairs.push(pair);
+                }
+            }
+        }
+    }
+
+    fn check_duplicate(
+        &self,
+        key_offsets: &mut KeyOffsets<'text>,
+        key: &Token<'text>,
+        offset: usize,
+    ) -> Result<()> {
+        if self.duplicate_keys != DuplicateKeys::Error {
+            return Ok(());
+        }
+
+        // Keys that only differ in case are the same key
+        let key = if self.case_insensitive_keys {
+            Cow::Owned(key.value.to_ascii_lowercase())
+        } else {
+            key.value.clone()
+        };
+        match key_offsets.entry(key) {
+            Entry::Occupied(first) => {
+                let first = self.position(*first.get());
+                self.error(ParseErrorKind::DuplicateKey { first }, offset)
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(offset);
+                Ok(())
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<Token<'text>> {
+        let start = self.cursor.pos;
+        let (inner, quoted) = match self.cursor.string(self.literal_special_chars) {
+            RawStr::Unquoted(s) => (s, false),
+            RawStr::Quoted(inner) => (inner, true),
+            RawStr::Unterminated(_) => {
+                return self.error(ParseErrorKind::UnterminatedString, start);
+            }
+        };
+        self.check_limit(Limit::StringLen, inner.len(), start)?;
+
+        let value = if quoted && !self.literal_special_chars {
+            let mut invalid = None;
+            let value = lex::unescape(inner, |other, offset| {
+                invalid.get_or_insert((other, offset));
+            });
+            if let Some((other, offset)) = invalid {
+                return self.error(ParseErrorKind::InvalidEscape(other), start + 1 + offset);
+            }
+            value
+        } else {
+            Cow::Borrowed(inner)
+        };
+        Ok(Token::parsed(value, self.cursor.since(start), quoted))
+    }
+
+    // Along with the conditional's text
+    fn try_conditional(&mut self) -> Result<Option<(Conditional<'text>, &'text str)>> {
+        let start = self.cursor.pos;
+        match self.cursor.conditional(self.limits.max_depth) {
+            Ok(cond) => Ok(cond.map(|cond| (cond, self.cursor.since(start)))),
+            Err(max) => {
+                let kind = ParseErrorKind::LimitExceeded {
+                    limit: Limit::Depth,
+                    max,
+                };
+                self.error(kind, start)
+            }
+        }
+    }
+}
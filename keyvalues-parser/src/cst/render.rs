@@ -0,0 +1,139 @@
+use std::fmt::{self, Write};
+
+use super::{CondNode, DirectiveKind, Document, Obj, Pair, Token, Value};
+use crate::{
+    error::{Error, Result},
+    text::lex::is_unquotable,
+};
+
+/// Renders the document with all unmodified regions exactly as they were parsed
+impl fmt::Display for Document<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f)
+    }
+}
+
+impl Document<'_> {
+    pub fn render(&self, writer: &mut impl Write) -> Result<()> {
+        if self.literal_special_chars {
+            if let Some(invalid_char) = self.pair.find_invalid_raw_char() {
+                return Err(Error::RawRenderError { invalid_char });
+            }
+        }
+
+        self.write(writer).map_err(Into::into)
+    }
+
+    fn write(&self, writer: &mut impl Write) -> fmt::Result {
+        let escape = !self.literal_special_chars;
+        for directive in &self.directives {
+            writer.write_str(&directive.leading)?;
+            writer.write_str(match directive.kind {
+                DirectiveKind::Base => "#base",
+                DirectiveKind::Include => "#include",
+            })?;
+            writer.write_str(&directive.gap)?;
+            // Paths are never escaped
+            directive.path.write(writer, false)?;
+        }
+
+        self.pair.write(writer, escape)?;
+        writer.write_str(&self.trailing)
+    }
+}
+
+impl Token<'_> {
+    pub(super) fn write(&self, writer: &mut impl Write, escape: bool) -> fmt::Result {
+        if let Some(raw) = &self.raw {
+            return writer.write_str(raw);
+        }
+        if !self.quoted && is_unquotable(&self.value) {
+            return writer.write_str(&self.value);
+        }
+
+        writer.write_char('"')?;
+        if escape {
+            for c in self.value.chars() {
+                match c {
+                    '\n' => writer.write_str(r"\n"),
+                    '\r' => writer.write_str(r"\r"),
+                    '\t' => writer.write_str(r"\t"),
+                    '\"' => writer.write_str(r#"\""#),
+                    '\\' => writer.write_str(r"\\"),
+                    reg => writer.write_char(reg),
+                }?
+            }
+        } else {
+            writer.write_str(&self.value)?;
+        }
+        writer.write_char('"')
+    }
+
+    // Only modified tokens get written out fresh
+    fn find_invalid_raw_char(&self) -> Option<char> {
+        match self.raw {
+            Some(_) => None,
+            None => self.value.chars().find(|&c| c == '"'),
+        }
+    }
+}
+
+impl Pair<'_> {
+    fn write(&self, writer: &mut impl Write, escape: bool) -> fmt::Result {
+        writer.write_str(&self.leading)?;
+        self.key.write(writer, escape)?;
+        writer.write_str(&self.key_gap)?;
+
+        match &self.conditional {
+            Some(node) if node.after_value => {
+                self.value.write(writer, escape)?;
+                writer.write_str(&node.gap)?;
+                node.write(writer)
+            }
+            Some(node) => {
+                node.write(writer)?;
+                writer.write_str(&node.gap)?;
+                self.value.write(writer, escape)
+            }
+            None => self.value.write(writer, escape),
+        }
+    }
+
+    fn find_invalid_raw_char(&self) -> Option<char> {
+        self.key
+            .find_invalid_raw_char()
+            .or_else(|| match &self.value {
+                Value::Str(token) => token.find_invalid_raw_char(),
+                Value::Obj(obj) => obj.pairs.iter().find_map(Pair::find_invalid_raw_char),
+            })
+    }
+}
+
+impl Value<'_> {
+    fn write(&self, writer: &mut impl Write, escape: bool) -> fmt::Result {
+        match self {
+            Self::Str(token) => token.write(writer, escape),
+            Self::Obj(obj) => obj.write(writer, escape),
+        }
+    }
+}
+
+impl CondNode<'_> {
+    fn write(&self, writer: &mut impl Write) -> fmt::Result {
+        match &self.raw {
+            Some(raw) => writer.write_str(raw),
+            None => write!(writer, "{}", self.cond),
+        }
+    }
+}
+
+impl Obj<'_> {
+    fn write(&self, writer: &mut impl Write, escape: bool) -> fmt::Result {
+        writer.write_char('{')?;
+        for pair in &self.pairs {
+            pair.write(writer, escape)?;
+        }
+        writer.write_str(&self.closing)?;
+        writer.write_char('}')
+    }
+}
@@ -0,0 +1,397 @@
+//! KeyValues2 (DMX text) support
+//!
+//! Source 1 tooling stores Data Model eXchange (DMX) files like SFM sessions, models, and
+//! particle systems with the `keyvalues2` text encoding. It looks like KeyValues, but every
+//! attribute is typed and elements can reference each other by their id
+//!
+//! ```text
+//! <!-- dmx encoding keyvalues2 1 format dmx 1 -->
+//! "DmElement"
+//! {
+//!     "id" "elementid" "df7c5b1e-3ab0-4b61-9d5c-08a4a5a7d3a2"
+//!     "name" "string" "root"
+//!     "scale" "float" "1.5"
+//!     "child" "DmeModel"
+//!     {
+//!         "id" "elementid" "0f3b0c3e-6f0c-4d7e-8b1e-4c0b7f0a6a11"
+//!         "position" "vector3" "0 0 64"
+//!     }
+//! }
+//! ```
+//!
+//! Parsing produces a [`Dmx`] which owns all of the [`Element`]s. Element references (both
+//! inline elements and references by id) are resolved to [`ElementId`]s which index back into the
+//! [`Dmx`]
+
+use std::{
+    borrow::Cow,
+    ops::{Index, IndexMut},
+    slice,
+};
+
+mod parse;
+mod render;
+
+pub use parse::parse;
+
+/// A parsed DMX document
+///
+/// ```
+/// use keyvalues_parser::dmx::{self, Attribute, Value};
+///
+/// let text = r#"<!-- dmx encoding keyvalues2 1 format dmx 1 -->
+/// "DmElement"
+/// {
+///     "id" "elementid" "a"
+///     "child" "element" "b"
+/// }
+///
+/// "DmeModel"
+/// {
+///     "id" "elementid" "b"
+///     "name" "string" "model"
+/// }
+/// "#;
+/// let dmx = dmx::parse(text)?;
+/// let root = &dmx[dmx.root().unwrap()];
+/// let Some(Attribute::Value(Value::Element(Some(child)))) = root.get("child") else {
+///     panic!("Missing child");
+/// };
+/// assert_eq!(dmx[*child].class, "DmeModel");
+/// # Ok::<(), keyvalues_parser::error::Error>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Dmx<'text> {
+    pub header: Header<'text>,
+    elements: Vec<Element<'text>>,
+}
+
+impl<'text> Dmx<'text> {
+    pub fn new(header: Header<'text>) -> Self {
+        Self {
+            header,
+            elements: Vec::new(),
+        }
+    }
+
+    /// Attempts to parse DMX text
+    ///
+    /// This is the same as [`dmx::parse()`][parse]
+    pub fn parse(text: &'text str) -> crate::error::Result<Self> {
+        parse(text)
+    }
+
+    /// The root element is the first top-level element in the document
+    pub fn root(&self) -> Option<ElementId> {
+        (!self.elements.is_empty()).then_some(ElementId(0))
+    }
+
+    pub fn get(&self, id: ElementId) -> Option<&Element<'text>> {
+        self.elements.get(id.0)
+    }
+
+    pub fn get_mut(&mut self, id: ElementId) -> Option<&mut Element<'text>> {
+        self.elements.get_mut(id.0)
+    }
+
+    /// Adds an element to the document returning its id for use in references
+    ///
+    /// The first element that's added is the root
+    pub fn push(&mut self, element: Element<'text>) -> ElementId {
+        self.elements.push(element);
+        ElementId(self.elements.len() - 1)
+    }
+
+    /// Looks up an element by its `"id"` attribute
+    pub fn find(&self, element_id: &str) -> Option<ElementId> {
+        self.elements
+            .iter()
+            .position(|element| element.id == element_id)
+            .map(ElementId)
+    }
+
+    /// All of the elements along with their ids
+    pub fn elements(&self) -> impl Iterator<Item = (ElementId, &Element<'text>)> {
+        self.elements
+            .iter()
+            .enumerate()
+            .map(|(i, element)| (ElementId(i), element))
+    }
+}
+
+/// Elements are matched up by their ids instead of their position since rendering can reorder
+/// them. References are compared by the ids that they point to
+impl PartialEq for Dmx<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        let ids_eq = |a: Option<ElementId>, b: Option<ElementId>| match (a, b) {
+            (Some(a), Some(b)) => self[a].id == other[b].id,
+            (None, None) => true,
+            _ => false,
+        };
+        let values_eq = |a: &Value<'_>, b: &Value<'_>| match (a, b) {
+            (Value::Element(a), Value::Element(b)) => ids_eq(*a, *b),
+            (a, b) => a == b,
+        };
+        let attrs_eq = |a: &Attribute<'_>, b: &Attribute<'_>| match (a, b) {
+            (Attribute::Value(a), Attribute::Value(b)) => values_eq(a, b),
+            (Attribute::Array(a_ty, a), Attribute::Array(b_ty, b)) => {
+                a_ty == b_ty && a.len() == b.len() && a.iter().zip(b).all(|(a, b)| values_eq(a, b))
+            }
+            _ => false,
+        };
+
+        self.header == other.header
+            && self.elements.len() == other.elements.len()
+            && ids_eq(self.root(), other.root())
+            && self.elements.iter().all(|element| {
+                let Some(other_element) = other.find(&element.id).map(|id| &other[id]) else {
+                    return false;
+                };
+                element.class == other_element.class
+                    && element.attributes.len() == other_element.attributes.len()
+                    && element
+                        .attributes()
+                        .zip(other_element.attributes())
+                        .all(|((a_name, a), (b_name, b))| a_name == b_name && attrs_eq(a, b))
+            })
+    }
+}
+
+impl<'text> Index<ElementId> for Dmx<'text> {
+    type Output = Element<'text>;
+
+    fn index(&self, id: ElementId) -> &Self::Output {
+        &self.elements[id.0]
+    }
+}
+
+impl IndexMut<ElementId> for Dmx<'_> {
+    fn index_mut(&mut self, id: ElementId) -> &mut Self::Output {
+        &mut self.elements[id.0]
+    }
+}
+
+/// A handle to an [`Element`] within a [`Dmx`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ElementId(usize);
+
+impl ElementId {
+    /// The element's position within [`Dmx::elements()`]
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// The header comment that starts a document
+///
+/// `<!-- dmx encoding keyvalues2 1 format dmx 1 -->`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Header<'text> {
+    /// Either `keyvalues2` or `keyvalues2_flat`. The flat encoding writes every element at the
+    /// top-level instead of nesting them
+    pub encoding: Cow<'text, str>,
+    pub encoding_version: u32,
+    /// The format of the data e.g. `dmx`, `sfm_session`, or `pcf`
+    pub format: Cow<'text, str>,
+    pub format_version: u32,
+}
+
+/// Uses the `keyvalues2` encoding with the `dmx` format
+impl Default for Header<'_> {
+    fn default() -> Self {
+        Self {
+            encoding: Cow::Borrowed("keyvalues2"),
+            encoding_version: 1,
+            format: Cow::Borrowed("dmx"),
+            format_version: 1,
+        }
+    }
+}
+
+/// An element with a class and a list of named attributes
+#[derive(Clone, Debug, PartialEq)]
+pub struct Element<'text> {
+    /// The element's type e.g. `DmElement` or `DmeModel`
+    pub class: Cow<'text, str>,
+    /// The unique id that references use. This is typically a GUID
+    pub id: Cow<'text, str>,
+    attributes: Vec<(Cow<'text, str>, Attribute<'text>)>,
+}
+
+impl<'text> Element<'text> {
+    pub fn new(class: impl Into<Cow<'text, str>>, id: impl Into<Cow<'text, str>>) -> Self {
+        Self {
+            class: class.into(),
+            id: id.into(),
+            attributes: Vec::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Attribute<'text>> {
+        self.attributes
+            .iter()
+            .find(|(attr_name, _)| attr_name == name)
+            .map(|(_, attr)| attr)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Attribute<'text>> {
+        self.attributes
+            .iter_mut()
+            .find(|(attr_name, _)| attr_name == name)
+            .map(|(_, attr)| attr)
+    }
+
+    /// Sets an attribute replacing any existing one with the same name
+    pub fn set(&mut self, name: impl Into<Cow<'text, str>>, attr: Attribute<'text>) {
+        let name = name.into();
+        match self.get_mut(&name) {
+            Some(existing) => *existing = attr,
+            None => self.attributes.push((name, attr)),
+        }
+    }
+
+    /// Iterates over the attributes in their original order
+    pub fn attributes(&self) -> slice::Iter<'_, (Cow<'text, str>, Attribute<'text>)> {
+        self.attributes.iter()
+    }
+}
+
+/// An attribute's value which is either a single value or a homogeneous array
+#[derive(Clone, Debug, PartialEq)]
+pub enum Attribute<'text> {
+    Value(Value<'text>),
+    /// The type is stored separately so that empty arrays keep their type
+    Array(AttributeType, Vec<Value<'text>>),
+}
+
+impl Attribute<'_> {
+    pub fn ty(&self) -> AttributeType {
+        match self {
+            Self::Value(value) => value.ty(),
+            Self::Array(ty, _) => *ty,
+        }
+    }
+}
+
+/// A single typed value
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<'text> {
+    /// A reference to another element. `None` is a null reference
+    Element(Option<ElementId>),
+    Int(i32),
+    Float(f32),
+    Bool(bool),
+    Str(Cow<'text, str>),
+    Binary(Vec<u8>),
+    /// A time in seconds
+    Time(f32),
+    /// RGBA
+    Color([u8; 4]),
+    Vector2([f32; 2]),
+    Vector3([f32; 3]),
+    Vector4([f32; 4]),
+    /// Pitch, yaw, and roll in degrees
+    QAngle([f32; 3]),
+    Quaternion([f32; 4]),
+    /// A 4x4 matrix in row-major order
+    Matrix([f32; 16]),
+    UInt64(u64),
+    UInt8(u8),
+}
+
+impl Value<'_> {
+    pub fn ty(&self) -> AttributeType {
+        match self {
+            Self::Element(_) => AttributeType::Element,
+            Self::Int(_) => AttributeType::Int,
+            Self::Float(_) => AttributeType::Float,
+            Self::Bool(_) => AttributeType::Bool,
+            Self::Str(_) => AttributeType::Str,
+            Self::Binary(_) => AttributeType::Binary,
+            Self::Time(_) => AttributeType::Time,
+            Self::Color(_) => AttributeType::Color,
+            Self::Vector2(_) => AttributeType::Vector2,
+            Self::Vector3(_) => AttributeType::Vector3,
+            Self::Vector4(_) => AttributeType::Vector4,
+            Self::QAngle(_) => AttributeType::QAngle,
+            Self::Quaternion(_) => AttributeType::Quaternion,
+            Self::Matrix(_) => AttributeType::Matrix,
+            Self::UInt64(_) => AttributeType::UInt64,
+            Self::UInt8(_) => AttributeType::UInt8,
+        }
+    }
+}
+
+/// The type of an [`Attribute`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AttributeType {
+    Element,
+    Int,
+    Float,
+    Bool,
+    Str,
+    Binary,
+    Time,
+    Color,
+    Vector2,
+    Vector3,
+    Vector4,
+    QAngle,
+    Quaternion,
+    Matrix,
+    UInt64,
+    UInt8,
+}
+
+impl AttributeType {
+    const ALL: [Self; 16] = [
+        Self::Element,
+        Self::Int,
+        Self::Float,
+        Self::Bool,
+        Self::Str,
+        Self::Binary,
+        Self::Time,
+        Self::Color,
+        Self::Vector2,
+        Self::Vector3,
+        Self::Vector4,
+        Self::QAngle,
+        Self::Quaternion,
+        Self::Matrix,
+        Self::UInt64,
+        Self::UInt8,
+    ];
+
+    /// The name used for the type in text e.g. `"vector3"`
+    ///
+    /// Arrays use the name with an `_array` suffix e.g. `"vector3_array"`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Element => "element",
+            Self::Int => "int",
+            Self::Float => "float",
+            Self::Bool => "bool",
+            Self::Str => "string",
+            Self::Binary => "binary",
+            Self::Time => "time",
+            Self::Color => "color",
+            Self::Vector2 => "vector2",
+            Self::Vector3 => "vector3",
+            Self::Vector4 => "vector4",
+            Self::QAngle => "qangle",
+            Self::Quaternion => "quaternion",
+            Self::Matrix => "matrix",
+            Self::UInt64 => "uint64",
+            Self::UInt8 => "uint8",
+        }
+    }
+
+    /// The inverse of [`AttributeType::name()`]. Older files use `"void"` for binary
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "void" => Some(Self::Binary),
+            _ => Self::ALL.into_iter().find(|ty| ty.name() == name),
+        }
+    }
+}
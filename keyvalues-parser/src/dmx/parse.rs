@@ -0,0 +1,417 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use super::{Attribute, AttributeType, Dmx, Element, ElementId, Header, Value};
+use crate::{
+    error::{DmxParseError, DmxParseErrorKind, Result},
+    header, Limits,
+};
+
+type DmxResult<T> = std::result::Result<T, DmxParseError>;
+
+/// Parses DMX text with the `keyvalues2` encoding to a [`Dmx`]
+///
+/// The header is optional. When it's missing the default [`Header`] is used
+///
+/// Inline elements can nest up to [`Limits::DEFAULT_MAX_DEPTH`] deep
+pub fn parse(text: &str) -> Result<Dmx<'_>> {
+    let mut parser = Parser {
+        text,
+        pos: 0,
+        depth: 0,
+        elements: Vec::new(),
+        element_positions: Vec::new(),
+        refs: Vec::new(),
+    };
+    let dmx = parser.document()?;
+    Ok(dmx)
+}
+
+// A reference by id that gets resolved once all of the elements are known
+struct PendingRef<'text> {
+    element: usize,
+    attr: usize,
+    // The index within an element array
+    index: Option<usize>,
+    id: Cow<'text, str>,
+    pos: usize,
+}
+
+struct Parser<'text> {
+    text: &'text str,
+    pos: usize,
+    depth: usize,
+    elements: Vec<Element<'text>>,
+    element_positions: Vec<usize>,
+    refs: Vec<PendingRef<'text>>,
+}
+
+fn parse_array<T: std::str::FromStr, const N: usize>(s: &str) -> Option<[T; N]> {
+    let mut parts = s.split_whitespace();
+    let mut values = Vec::with_capacity(N);
+    for _ in 0..N {
+        values.push(parts.next()?.parse().ok()?);
+    }
+    if parts.next().is_some() {
+        return None;
+    }
+
+    values.try_into().ok()
+}
+
+fn parse_binary(s: &str) -> Option<Vec<u8>> {
+    let hex: Vec<_> = s.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+
+    hex.chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).ok()?;
+            u8::from_str_radix(pair, 16).ok()
+        })
+        .collect()
+}
+
+fn parse_scalar<'text>(ty: AttributeType, s: Cow<'text, str>) -> Option<Value<'text>> {
+    let value = match ty {
+        AttributeType::Element => unreachable!("References are resolved separately"),
+        AttributeType::Str => Value::Str(s),
+        AttributeType::Int => Value::Int(s.trim().parse().ok()?),
+        AttributeType::Float => Value::Float(s.trim().parse().ok()?),
+        AttributeType::Time => Value::Time(s.trim().parse().ok()?),
+        AttributeType::Bool => match s.trim() {
+            "0" | "false" => Value::Bool(false),
+            "1" | "true" => Value::Bool(true),
+            _ => return None,
+        },
+        AttributeType::Binary => Value::Binary(parse_binary(&s)?),
+        AttributeType::Color => Value::Color(parse_array(&s)?),
+        AttributeType::Vector2 => Value::Vector2(parse_array(&s)?),
+        AttributeType::Vector3 => Value::Vector3(parse_array(&s)?),
+        AttributeType::Vector4 => Value::Vector4(parse_array(&s)?),
+        AttributeType::QAngle => Value::QAngle(parse_array(&s)?),
+        AttributeType::Quaternion => Value::Quaternion(parse_array(&s)?),
+        AttributeType::Matrix => Value::Matrix(parse_array(&s)?),
+        AttributeType::UInt64 => {
+            let s = s.trim();
+            let u = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+                Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+                None => s.parse().ok()?,
+            };
+            Value::UInt64(u)
+        }
+        AttributeType::UInt8 => Value::UInt8(s.trim().parse().ok()?),
+    };
+
+    Some(value)
+}
+
+impl<'text> Parser<'text> {
+    fn rest(&self) -> &'text str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn err_at(&self, pos: usize, kind: DmxParseErrorKind) -> DmxParseError {
+        let before = &self.text[..pos];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let col = before[line_start..].chars().count() + 1;
+        DmxParseError { line, col, kind }
+    }
+
+    fn err(&self, kind: DmxParseErrorKind) -> DmxParseError {
+        self.err_at(self.pos, kind)
+    }
+
+    // Errors for whatever is at the current position
+    fn unexpected(&self) -> DmxParseError {
+        match self.peek() {
+            Some(c) => self.err(DmxParseErrorKind::UnexpectedChar(c)),
+            None => self.err(DmxParseErrorKind::UnexpectedEof),
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> DmxResult<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    // Skips whitespace and comments
+    fn skip(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn str(&mut self) -> DmxResult<Cow<'text, str>> {
+        self.expect('"')?;
+        let start = self.pos;
+        let mut owned: Option<String> = None;
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| self.err(DmxParseErrorKind::UnexpectedEof))?;
+            match c {
+                '"' => {
+                    let s = match owned {
+                        Some(s) => Cow::Owned(s),
+                        None => Cow::Borrowed(&self.text[start..self.pos]),
+                    };
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                '\\' => {
+                    let s = owned.get_or_insert_with(|| self.text[start..self.pos].to_owned());
+                    self.pos += 1;
+                    let unescaped = match self.peek() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some(other) => {
+                            return Err(self.err(DmxParseErrorKind::InvalidEscape(other)))
+                        }
+                        None => return Err(self.err(DmxParseErrorKind::UnexpectedEof)),
+                    };
+                    s.push(unescaped);
+                    self.pos += 1;
+                }
+                c => {
+                    if let Some(s) = &mut owned {
+                        s.push(c);
+                    }
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    fn document(&mut self) -> DmxResult<Dmx<'text>> {
+        self.skip();
+        let header = if self.rest().starts_with("<!--") {
+            self.header()?
+        } else {
+            Header::default()
+        };
+
+        loop {
+            self.skip();
+            if self.peek().is_none() {
+                break;
+            }
+            let class = self.str()?;
+            self.skip();
+            self.element(class)?;
+        }
+
+        self.resolve_refs()?;
+        Ok(Dmx {
+            header,
+            elements: std::mem::take(&mut self.elements),
+        })
+    }
+
+    // `<!-- dmx encoding keyvalues2 1 format dmx 1 -->`
+    fn header(&mut self) -> DmxResult<Header<'text>> {
+        let invalid = self.err(DmxParseErrorKind::InvalidHeader);
+        let (contents, len) = header::split(self.rest()).ok_or_else(|| invalid.clone())?;
+        let parts: Vec<_> = contents.split_whitespace().collect();
+        let ["dmx", "encoding", encoding, encoding_version, "format", format, format_version] =
+            parts[..]
+        else {
+            return Err(invalid);
+        };
+        if !matches!(encoding, "keyvalues2" | "keyvalues2_flat") {
+            return Err(invalid);
+        }
+        let (Ok(encoding_version), Ok(format_version)) =
+            (encoding_version.parse(), format_version.parse())
+        else {
+            return Err(invalid);
+        };
+
+        self.pos += len;
+        Ok(Header {
+            encoding: Cow::Borrowed(encoding),
+            encoding_version,
+            format: Cow::Borrowed(format),
+            format_version,
+        })
+    }
+
+    // Parses the body of an element starting at its opening brace
+    fn element(&mut self, class: Cow<'text, str>) -> DmxResult<ElementId> {
+        let start = self.pos;
+        // Parsing inline elements recurses, so the depth is limited
+        if self.depth == Limits::DEFAULT_MAX_DEPTH {
+            return Err(self.err(DmxParseErrorKind::DepthExceeded));
+        }
+        self.depth += 1;
+        self.expect('{')?;
+        let index = self.elements.len();
+        self.elements.push(Element::new(class, ""));
+        self.element_positions.push(start);
+
+        let mut id = None;
+        loop {
+            self.skip();
+            if self.eat('}') {
+                break;
+            }
+
+            let name = self.str()?;
+            self.skip();
+            let ty_pos = self.pos;
+            let ty = self.str()?;
+            self.skip();
+
+            if name == "id" && ty == "elementid" {
+                id = Some(self.str()?);
+                continue;
+            }
+
+            let attr_index = self.elements[index].attributes.len();
+            let attr = if self.peek() == Some('{') {
+                // An inline element where the type is the element's class
+                let child = self.element(ty)?;
+                Attribute::Value(Value::Element(Some(child)))
+            } else if self.peek() == Some('[') {
+                let elem_ty = ty
+                    .strip_suffix("_array")
+                    .and_then(AttributeType::from_name)
+                    .ok_or_else(|| self.err_at(ty_pos, DmxParseErrorKind::UnknownType))?;
+                self.array(elem_ty, index, attr_index)?
+            } else {
+                let ty = AttributeType::from_name(&ty)
+                    .ok_or_else(|| self.err_at(ty_pos, DmxParseErrorKind::UnknownType))?;
+                let value_pos = self.pos;
+                let s = self.str()?;
+                if ty == AttributeType::Element {
+                    self.refs.push(PendingRef {
+                        element: index,
+                        attr: attr_index,
+                        index: None,
+                        id: s,
+                        pos: value_pos,
+                    });
+                    Attribute::Value(Value::Element(None))
+                } else {
+                    let value = parse_scalar(ty, s).ok_or_else(|| {
+                        self.err_at(value_pos, DmxParseErrorKind::InvalidValue(ty))
+                    })?;
+                    Attribute::Value(value)
+                }
+            };
+            self.elements[index].attributes.push((name, attr));
+        }
+
+        let id = id.ok_or_else(|| self.err_at(start, DmxParseErrorKind::MissingId))?;
+        self.elements[index].id = id;
+        self.depth -= 1;
+        Ok(ElementId(index))
+    }
+
+    fn array(
+        &mut self,
+        ty: AttributeType,
+        element: usize,
+        attr: usize,
+    ) -> DmxResult<Attribute<'text>> {
+        self.expect('[')?;
+        let mut values = Vec::new();
+        loop {
+            self.skip();
+            if self.eat(']') {
+                break;
+            }
+
+            let value_pos = self.pos;
+            let s = self.str()?;
+            let value = if ty == AttributeType::Element {
+                self.skip();
+                if self.peek() == Some('{') {
+                    Value::Element(Some(self.element(s)?))
+                } else if s == "element" {
+                    let id_pos = self.pos;
+                    let id = self.str()?;
+                    self.refs.push(PendingRef {
+                        element,
+                        attr,
+                        index: Some(values.len()),
+                        id,
+                        pos: id_pos,
+                    });
+                    Value::Element(None)
+                } else {
+                    return Err(self.unexpected());
+                }
+            } else {
+                parse_scalar(ty, s)
+                    .ok_or_else(|| self.err_at(value_pos, DmxParseErrorKind::InvalidValue(ty)))?
+            };
+            values.push(value);
+
+            self.skip();
+            if !self.eat(',') && self.peek() != Some(']') {
+                return Err(self.unexpected());
+            }
+        }
+
+        Ok(Attribute::Array(ty, values))
+    }
+
+    fn resolve_refs(&mut self) -> DmxResult<()> {
+        let mut ids = HashMap::new();
+        for (i, element) in self.elements.iter().enumerate() {
+            if ids.insert(element.id.clone(), i).is_some() {
+                return Err(self.err_at(self.element_positions[i], DmxParseErrorKind::DuplicateId));
+            }
+        }
+
+        for pending in std::mem::take(&mut self.refs) {
+            // An empty id is a null reference
+            let target = if pending.id.is_empty() {
+                None
+            } else {
+                let target = ids.get(&pending.id).ok_or_else(|| {
+                    self.err_at(pending.pos, DmxParseErrorKind::UnresolvedReference)
+                })?;
+                Some(ElementId(*target))
+            };
+
+            let attr = &mut self.elements[pending.element].attributes[pending.attr].1;
+            let value = match (attr, pending.index) {
+                (Attribute::Value(value), None) => value,
+                (Attribute::Array(_, values), Some(i)) => &mut values[i],
+                _ => unreachable!("Pending references match their attribute"),
+            };
+            *value = Value::Element(target);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+use std::{
+    borrow::Cow,
+    fmt::{self, Write},
+    iter, slice,
+};
+
+use super::{Attribute, Dmx, ElementId, Header, Value};
+
+fn multiple_char(c: char, amount: usize) -> String {
+    std::iter::repeat(c).take(amount).collect()
+}
+
+fn write_str(writer: &mut impl Write, s: &str) -> fmt::Result {
+    writer.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '\n' => writer.write_str(r"\n"),
+            '\r' => writer.write_str(r"\r"),
+            '\t' => writer.write_str(r"\t"),
+            '\"' => writer.write_str(r#"\""#),
+            '\\' => writer.write_str(r"\\"),
+            reg => writer.write_char(reg),
+        }?
+    }
+    writer.write_char('"')
+}
+
+fn join(values: &[impl fmt::Display]) -> String {
+    values
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl fmt::Display for Header<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "<!-- dmx encoding {} {} format {} {} -->",
+            self.encoding, self.encoding_version, self.format, self.format_version
+        )
+    }
+}
+
+/// Renders the document as `keyvalues2` text
+///
+/// Elements that are referenced exactly once are written inline within the element that
+/// references them (unless the header uses the `keyvalues2_flat` encoding) while the root and all
+/// other elements are written at the top-level and referenced by id
+impl fmt::Display for Dmx<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.header)?;
+
+        let mut renderer = Renderer::new(self);
+        let top_level = (0..self.elements.len()).filter(|&i| renderer.top_level[i]);
+        // Anything left over is only reachable through a cycle of inline elements
+        let order: Vec<_> = top_level.chain(0..self.elements.len()).collect();
+        for i in order {
+            if !renderer.written[i] {
+                if i != 0 {
+                    f.write_char('\n')?;
+                }
+                renderer.write_element(f, ElementId(i))?;
+                f.write_char('\n')?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Dmx<'_> {
+    pub fn render(&self, writer: &mut impl Write) -> crate::error::Result<()> {
+        write!(writer, "{self}").map_err(Into::into)
+    }
+}
+
+// An element that's partway through being written
+struct Frame<'a, 'text> {
+    num_indents: usize,
+    attrs: slice::Iter<'a, (Cow<'text, str>, Attribute<'text>)>,
+    // The rest of the array attribute that's being written
+    array: Option<iter::Enumerate<slice::Iter<'a, Value<'text>>>>,
+}
+
+struct Renderer<'a, 'text> {
+    dmx: &'a Dmx<'text>,
+    top_level: Vec<bool>,
+    written: Vec<bool>,
+}
+
+impl<'a, 'text> Renderer<'a, 'text> {
+    fn new(dmx: &'a Dmx<'text>) -> Self {
+        let len = dmx.elements.len();
+        let mut ref_counts = vec![0_usize; len];
+        for element in &dmx.elements {
+            for (_, attr) in element.attributes() {
+                let values = match attr {
+                    Attribute::Value(value) => std::slice::from_ref(value),
+                    Attribute::Array(_, values) => values,
+                };
+                for value in values {
+                    if let Value::Element(Some(id)) = value {
+                        ref_counts[id.0] += 1;
+                    }
+                }
+            }
+        }
+
+        let flat = dmx.header.encoding == "keyvalues2_flat";
+        let top_level = ref_counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| flat || i == 0 || count != 1)
+            .collect();
+
+        Self {
+            dmx,
+            top_level,
+            written: vec![false; len],
+        }
+    }
+
+    fn should_inline(&self, id: ElementId) -> bool {
+        !self.top_level[id.0] && !self.written[id.0]
+    }
+
+    fn write_element(&mut self, writer: &mut impl Write, id: ElementId) -> fmt::Result {
+        let mut stack = vec![self.open_element(writer, id, 0)?];
+        loop {
+            let Some(frame) = stack.last_mut() else {
+                return Ok(());
+            };
+            let num_indents = frame.num_indents;
+            let indent = multiple_char('\t', num_indents);
+
+            if let Some(values) = &mut frame.array {
+                let Some((i, value)) = values.next() else {
+                    write!(writer, "\n{indent}\t]\n")?;
+                    frame.array = None;
+                    continue;
+                };
+                if i != 0 {
+                    writer.write_char(',')?;
+                }
+                write!(writer, "\n{indent}\t\t")?;
+                match value {
+                    Value::Element(Some(child)) if self.should_inline(*child) => {
+                        let child = self.open_element(writer, *child, num_indents + 2)?;
+                        stack.push(child);
+                    }
+                    Value::Element(_) => {
+                        writer.write_str("\"element\" ")?;
+                        self.write_value(writer, value)?;
+                    }
+                    _ => self.write_value(writer, value)?,
+                }
+                continue;
+            }
+
+            let Some((name, attr)) = frame.attrs.next() else {
+                write!(writer, "{indent}}}")?;
+                stack.pop();
+                // Inline elements within an array are followed by the array's next value instead
+                if stack.last().map_or(false, |parent| parent.array.is_none()) {
+                    writer.write_char('\n')?;
+                }
+                continue;
+            };
+            write!(writer, "{indent}\t")?;
+            write_str(writer, name)?;
+            writer.write_char(' ')?;
+            match attr {
+                Attribute::Value(Value::Element(Some(child))) if self.should_inline(*child) => {
+                    let child = self.open_element(writer, *child, num_indents + 1)?;
+                    stack.push(child);
+                }
+                Attribute::Value(value) => {
+                    write_str(writer, value.ty().name())?;
+                    writer.write_char(' ')?;
+                    self.write_value(writer, value)?;
+                    writer.write_char('\n')?;
+                }
+                Attribute::Array(ty, values) => {
+                    write!(writer, "\"{}_array\"\n{indent}\t[", ty.name())?;
+                    frame.array = Some(values.iter().enumerate());
+                }
+            }
+        }
+    }
+
+    // Writes everything up to the element's attributes which are left to the caller
+    fn open_element(
+        &mut self,
+        writer: &mut impl Write,
+        id: ElementId,
+        num_indents: usize,
+    ) -> Result<Frame<'a, 'text>, fmt::Error> {
+        self.written[id.0] = true;
+        let element = &self.dmx[id];
+        let indent = multiple_char('\t', num_indents);
+
+        write_str(writer, &element.class)?;
+        write!(writer, "\n{indent}{{\n{indent}\t\"id\" \"elementid\" ")?;
+        write_str(writer, &element.id)?;
+        writer.write_char('\n')?;
+
+        Ok(Frame {
+            num_indents,
+            attrs: element.attributes.iter(),
+            array: None,
+        })
+    }
+
+    fn write_value(&self, writer: &mut impl Write, value: &Value<'_>) -> fmt::Result {
+        let s = match value {
+            Value::Element(None) => String::new(),
+            Value::Element(Some(id)) => return write_str(writer, &self.dmx[*id].id),
+            Value::Int(i) => i.to_string(),
+            Value::Float(f) | Value::Time(f) => f.to_string(),
+            Value::Bool(b) => u8::from(*b).to_string(),
+            Value::Str(s) => return write_str(writer, s),
+            Value::Binary(bytes) => bytes.iter().map(|b| format!("{b:02X}")).collect(),
+            Value::Color(rgba) => join(rgba),
+            Value::Vector2(v) => join(v),
+            Value::Vector3(v) | Value::QAngle(v) => join(v),
+            Value::Vector4(v) | Value::Quaternion(v) => join(v),
+            Value::Matrix(m) => join(m),
+            Value::UInt64(u) => u.to_string(),
+            Value::UInt8(u) => u.to_string(),
+        };
+
+        write_str(writer, &s)
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    error::{ParseError, ParseErrorKind, Result},
+    span::LineIndex,
+};
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16_LE_BOM: &[u8] = b"\xFF\xFE";
+const UTF16_BE_BOM: &[u8] = b"\xFE\xFF";
+
+/// The encoding that a document's bytes were detected as when parsed with
+/// [`Parser::parse_bytes()`][crate::Parser::parse_bytes]
+///
+/// Documents parsed from a `&str` are always [`Encoding::default()`], which is UTF-8 without a
+/// byte order mark
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// UTF-8 starting with a byte order mark
+    Utf8Bom,
+    /// Little-endian UTF-16. Valve's localization files are commonly in this encoding
+    Utf16Le { bom: bool },
+    /// Big-endian UTF-16
+    Utf16Be { bom: bool },
+}
+
+impl Encoding {
+    /// Detects the encoding of `bytes`
+    ///
+    /// A byte order mark always wins. Without one, text starting with a null byte next to a
+    /// non-null one is taken as UTF-16 (KeyValues text pretty much always starts with ASCII), and
+    /// everything else is UTF-8
+    ///
+    /// ```
+    /// use keyvalues_parser::Encoding;
+    /// assert_eq!(Encoding::detect(b"\xFF\xFEK\0e\0y\0"), Encoding::Utf16Le { bom: true });
+    /// assert_eq!(Encoding::detect(b"\0K\0e\0y"), Encoding::Utf16Be { bom: false });
+    /// assert_eq!(Encoding::detect(b"Key"), Encoding::Utf8);
+    /// ```
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(UTF8_BOM) {
+            Self::Utf8Bom
+        } else if bytes.starts_with(UTF16_LE_BOM) {
+            Self::Utf16Le { bom: true }
+        } else if bytes.starts_with(UTF16_BE_BOM) {
+            Self::Utf16Be { bom: true }
+        } else {
+            match bytes {
+                [first, 0, ..] if *first != 0 => Self::Utf16Le { bom: false },
+                [0, second, ..] if *second != 0 => Self::Utf16Be { bom: false },
+                _ => Self::Utf8,
+            }
+        }
+    }
+
+    pub(crate) fn bom(self) -> &'static [u8] {
+        match self {
+            Self::Utf8 | Self::Utf16Le { bom: false } | Self::Utf16Be { bom: false } => &[],
+            Self::Utf8Bom => UTF8_BOM,
+            Self::Utf16Le { bom: true } => UTF16_LE_BOM,
+            Self::Utf16Be { bom: true } => UTF16_BE_BOM,
+        }
+    }
+
+    /// Encodes `text` to bytes in this encoding, including the byte order mark if it has one
+    ///
+    /// ```
+    /// use keyvalues_parser::Encoding;
+    /// let bytes = Encoding::Utf16Le { bom: true }.encode("Key");
+    /// assert_eq!(bytes, b"\xFF\xFEK\0e\0y\0");
+    /// ```
+    pub fn encode(self, text: &str) -> Vec<u8> {
+        let mut bytes = self.bom().to_vec();
+        self.encode_into(text, &mut bytes);
+        bytes
+    }
+
+    // Leaves off the byte order mark, so text can be encoded a piece at a time
+    pub(crate) fn encode_into(self, text: &str, bytes: &mut Vec<u8>) {
+        match self {
+            Self::Utf8 | Self::Utf8Bom => bytes.extend_from_slice(text.as_bytes()),
+            Self::Utf16Le { .. } => bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes)),
+            Self::Utf16Be { .. } => bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes)),
+        }
+    }
+}
+
+/// Decodes `bytes` in their detected encoding with the byte order mark stripped
+///
+/// Invalid sequences get replaced with `U+FFFD` when `lossy` and are an error otherwise
+pub(crate) fn decode(bytes: &[u8], lossy: bool) -> Result<(String, Encoding)> {
+    let encoding = Encoding::detect(bytes);
+    let bytes = &bytes[encoding.bom().len()..];
+    let (text, invalid_at) = match encoding {
+        Encoding::Utf8 | Encoding::Utf8Bom => match std::str::from_utf8(bytes) {
+            Ok(text) => (text.to_owned(), None),
+            Err(err) => (
+                String::from_utf8_lossy(bytes).into_owned(),
+                Some(err.valid_up_to()),
+            ),
+        },
+        Encoding::Utf16Le { .. } => decode_utf16(bytes, u16::from_le_bytes),
+        Encoding::Utf16Be { .. } => decode_utf16(bytes, u16::from_be_bytes),
+    };
+
+    match invalid_at {
+        Some(offset) if !lossy => {
+            let lines = LineIndex::new(&text);
+            Err(ParseError::new(&text, &lines, ParseErrorKind::InvalidEncoding, offset).into())
+        }
+        _ => Ok((text, encoding)),
+    }
+}
+
+// Decodes lossily while tracking the offset in the decoded text of the first invalid sequence
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> (String, Option<usize>) {
+    let chunks = bytes.chunks_exact(2);
+    let odd_byte = !chunks.remainder().is_empty();
+    let units = chunks.map(|chunk| from_bytes([chunk[0], chunk[1]]));
+
+    let mut text = String::with_capacity(bytes.len() / 2);
+    let mut invalid_at = None;
+    for c in char::decode_utf16(units) {
+        let c = c.unwrap_or_else(|_| {
+            invalid_at.get_or_insert(text.len());
+            char::REPLACEMENT_CHARACTER
+        });
+        text.push(c);
+    }
+    if odd_byte {
+        invalid_at.get_or_insert(text.len());
+        text.push(char::REPLACEMENT_CHARACTER);
+    }
+
+    (text, invalid_at)
+}
@@ -1,38 +1,626 @@
 //! All error information for parsing and rendering
 
-use std::fmt;
+use std::{fmt, io, path::PathBuf};
 
-#[doc(inline)]
-pub use crate::text::parse::{EscapedPestError, RawPestError};
+use crate::{dmx::AttributeType, span::LineIndex, Position};
 
 /// Just a type alias for `Result` with a [`Error`]
 pub type Result<T> = std::result::Result<T, Error>;
 
-// TODO: Swap out the `EscapedParseError` and `RawParseError` for an opaque `Error::Parse` variant
-// that handles displaying the error
 // TODO: should this whole thing be overhauled (future me here: yes)
 // TODO: split the `Error` into a separate parse and render error
 
 /// All possible errors when parsing or rendering VDF text
-///
-/// Currently the two variants are parse errors which currently only occurs when `pest` encounters
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Debug)]
 pub enum Error {
-    EscapedParseError(EscapedPestError),
-    RawParseError(RawPestError),
+    ParseError(ParseError),
+    BinaryParseError(BinaryParseError),
+    Kv3ParseError(Kv3ParseError),
+    Kv3ConvertError(Kv3ConvertError),
+    DmxParseError(DmxParseError),
+    StreamError(StreamError),
+    WriteError(WriteError),
+    ResolveError(ResolveError),
     RenderError(fmt::Error),
-    RawRenderError { invalid_char: char },
+    RawRenderError {
+        invalid_char: char,
+    },
+    /// A `#base` or `#include` path contained a `"` or newline, so it can't be rendered
+    InvalidDirectivePath {
+        path: String,
+    },
+    /// Reading or writing failed when rendering straight to an [`io::Write`] or a file
+    Io(io::Error),
+}
+
+/// An error encountered while parsing KeyValues text
+///
+/// Along with the [`ParseErrorKind`] and where it happened, the error keeps the lines of the text
+/// needed to render an annotated [`ParseError::snippet()`]
+///
+/// # Example
+///
+/// ```
+/// use keyvalues_parser::error::{Error, ParseErrorKind};
+///
+/// let Err(Error::ParseError(err)) = keyvalues_parser::parse("Key \"Value") else {
+///     unreachable!();
+/// };
+/// assert_eq!(err.kind(), ParseErrorKind::UnterminatedString);
+/// assert_eq!((err.line(), err.col()), (1, 5));
+/// println!("{}", err.snippet());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    kind: ParseErrorKind,
+    position: Position,
+    line_text: String,
+    // The line of the related position when it's on a different line
+    related_line_text: Option<String>,
+}
+
+impl ParseError {
+    pub(crate) fn new(
+        text: &str,
+        lines: &LineIndex<'_>,
+        kind: ParseErrorKind,
+        offset: usize,
+    ) -> Self {
+        let line_text = |position: Position| {
+            let line_start = text[..position.offset]
+                .rfind('\n')
+                .map_or(0, |newline| newline + 1);
+            let line = &text[line_start..];
+            line[..line.find('\n').unwrap_or(line.len())]
+                .trim_end_matches('\r')
+                .to_owned()
+        };
+
+        let position = lines.position(offset);
+        let related_line_text = match kind.related() {
+            Some((related, _)) if related.line != position.line => Some(line_text(related)),
+            _ => None,
+        };
+        Self {
+            kind,
+            position,
+            line_text: line_text(position),
+            related_line_text,
+        }
+    }
+
+    pub fn kind(&self) -> ParseErrorKind {
+        self.kind
+    }
+
+    /// Where in the text the error was encountered
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// The 1-indexed line where the error was encountered
+    pub fn line(&self) -> usize {
+        self.position.line
+    }
+
+    /// The 1-indexed column (in `char`s) where the error was encountered
+    pub fn col(&self) -> usize {
+        self.position.col
+    }
+
+    /// Displays the error along with an annotated snippet of the offending text
+    ///
+    /// ```text
+    /// error: Unbalanced brace, the `{` at 2:1 is never closed
+    ///  --> 3:24
+    ///   |
+    /// 2 | {
+    ///   | - unclosed `{`
+    /// 3 |     Inner { Key Value }
+    ///   |                        ^
+    /// ```
+    pub fn snippet(&self) -> Snippet<'_> {
+        Snippet(self)
+    }
 }
 
-impl From<EscapedPestError> for Error {
-    fn from(e: EscapedPestError) -> Self {
-        Self::EscapedParseError(e)
+/// An annotated snippet of the text that caused a [`ParseError`]
+///
+/// Created by [`ParseError::snippet()`]
+#[derive(Clone, Copy, Debug)]
+pub struct Snippet<'err>(&'err ParseError);
+
+impl fmt::Display for Snippet<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Tabs get expanded, so columns are measured by the displayed width of what precedes them
+        fn expand_tabs(s: &str) -> String {
+            s.replace('\t', "    ")
+        }
+        fn indent(line: &str, col: usize) -> usize {
+            line.chars()
+                .take(col - 1)
+                .map(|c| if c == '\t' { 4 } else { 1 })
+                .sum()
+        }
+
+        let err = self.0;
+        let Position { line, col, .. } = err.position;
+        let width = line.to_string().len();
+        let gutter = " ".repeat(width);
+
+        writeln!(f, "error: {}", err.kind)?;
+        writeln!(f, "{gutter}--> {line}:{col}")?;
+        writeln!(f, "{gutter} |")?;
+
+        let related = err.kind.related();
+        if let (Some((related, label)), Some(related_text)) = (related, &err.related_line_text) {
+            let pad = " ".repeat(indent(related_text, related.col));
+            writeln!(
+                f,
+                "{:>width$} | {}",
+                related.line,
+                expand_tabs(related_text)
+            )?;
+            writeln!(f, "{gutter} | {pad}- {label}")?;
+        }
+
+        writeln!(f, "{line:>width$} | {}", expand_tabs(&err.line_text))?;
+        let pad = " ".repeat(indent(&err.line_text, col));
+        match related {
+            // The related position was on the same line, so mark both
+            Some((related, _)) if err.related_line_text.is_none() => {
+                let related_pad = " ".repeat(indent(&err.line_text, related.col));
+                let between = " ".repeat(pad.len() - related_pad.len() - 1);
+                write!(f, "{gutter} | {related_pad}-{between}^")
+            }
+            _ => write!(f, "{gutter} | {pad}^"),
+        }
     }
 }
 
-impl From<RawPestError> for Error {
-    fn from(e: RawPestError) -> Self {
-        Self::RawParseError(e)
+/// The different kinds of [`ParseError`]s
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A quoted string was never closed. Points to the opening `"`
+    UnterminatedString,
+    /// The text ended before an object was closed
+    UnbalancedBrace {
+        /// Where the unclosed `{` is
+        opening: Position,
+    },
+    /// An unknown escape sequence within a quoted string. Points to the `\`
+    InvalidEscape(char),
+    /// There was more text after the top-level pair
+    TrailingContent,
+    /// A key wasn't followed by a value
+    MissingValue,
+    /// Expected the key of a pair, but found a brace or the end of the text
+    MissingKey,
+    /// The bytes weren't valid in their detected [`Encoding`][crate::Encoding]. Points to the
+    /// first invalid sequence
+    InvalidEncoding,
+    /// A key was used more than once within the same object while parsing with
+    /// [`DuplicateKeys::Error`][crate::DuplicateKeys::Error]. Points to the later key
+    DuplicateKey {
+        /// Where the key was first used
+        first: Position,
+    },
+    /// The text went over one of the parser's [`Limits`][crate::Limits]. Parsing stops here even
+    /// when parsing leniently
+    LimitExceeded {
+        limit: Limit,
+        /// The configured maximum
+        max: usize,
+    },
+}
+
+/// The different [`Limits`][crate::Limits] that can be exceeded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    /// Objects were nested too deeply. Points to the opening `{`
+    Depth,
+    /// The whole input was too long. Points to the start of the text
+    InputLen,
+    /// A string was too long. Points to the start of the string
+    StringLen,
+    /// There were too many pairs. Points to the key of the first pair past the limit
+    Pairs,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Depth => "nesting depth",
+            Self::InputLen => "input length",
+            Self::StringLen => "string length",
+            Self::Pairs => "number of pairs",
+        })
+    }
+}
+
+impl ParseErrorKind {
+    // Another position that's relevant to the error along with how to label it in snippets
+    fn related(self) -> Option<(Position, &'static str)> {
+        match self {
+            Self::UnbalancedBrace { opening } => Some((opening, "unclosed `{`")),
+            Self::DuplicateKey { first } => Some((first, "first used here")),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnterminatedString => f.write_str("Unterminated string"),
+            Self::UnbalancedBrace { opening } => write!(
+                f,
+                "Unbalanced brace, the `{{` at {}:{} is never closed",
+                opening.line, opening.col
+            ),
+            Self::InvalidEscape(c) => write!(f, "Invalid escape sequence: \\{c}"),
+            Self::TrailingContent => f.write_str("Trailing content after the top-level pair"),
+            Self::MissingValue => f.write_str("Missing value after key"),
+            Self::MissingKey => f.write_str("Missing key"),
+            Self::InvalidEncoding => f.write_str("Invalid text for the detected encoding"),
+            Self::DuplicateKey { first } => write!(
+                f,
+                "Duplicate key, it was already used at {}:{}",
+                first.line, first.col
+            ),
+            Self::LimitExceeded { limit, max } => {
+                write!(f, "Exceeded the maximum {limit} of {max}")
+            }
+        }
+    }
+}
+
+/// An error encountered while parsing binary KeyValues
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BinaryParseError {
+    /// The byte offset into the input where the error was encountered
+    pub offset: usize,
+    pub kind: BinaryParseErrorKind,
+}
+
+/// The different kinds of [`BinaryParseError`]s
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryParseErrorKind {
+    /// The input ended in the middle of a pair
+    UnexpectedEof,
+    /// Encountered a type tag that doesn't correspond to any known type
+    InvalidType(u8),
+    /// A key or string value wasn't valid UTF-8
+    InvalidUtf8,
+    /// A wide string value wasn't valid UTF-16
+    InvalidUtf16,
+    /// There was more data after the top-level pair
+    TrailingBytes,
+    /// The document ended before any pair was read
+    MissingPair,
+    /// A key referenced an entry past the end of the container's key table
+    InvalidKeyIndex(u32),
+    /// The container started with an unknown magic number
+    UnsupportedMagic(u32),
+    /// A container entry's size was too small to fit its own header
+    InvalidEntrySize(u32),
+    /// Objects were nested deeper than [`Limits::max_depth`][crate::Limits::max_depth]
+    DepthExceeded,
+}
+
+impl fmt::Display for BinaryParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedEof => f.write_str("Unexpected end of input"),
+            Self::InvalidType(ty) => write!(f, "Invalid type tag: {ty:#04x}"),
+            Self::InvalidUtf8 => f.write_str("Invalid UTF-8 in string"),
+            Self::InvalidUtf16 => f.write_str("Invalid UTF-16 in wide string"),
+            Self::TrailingBytes => f.write_str("Trailing bytes after the top-level pair"),
+            Self::MissingPair => f.write_str("Missing top-level pair"),
+            Self::InvalidKeyIndex(index) => write!(f, "Invalid key table index: {index}"),
+            Self::UnsupportedMagic(magic) => write!(f, "Unsupported magic number: {magic:#010x}"),
+            Self::InvalidEntrySize(size) => write!(f, "Invalid entry size: {size}"),
+            Self::DepthExceeded => f.write_str("Objects are nested too deeply"),
+        }
+    }
+}
+
+/// An error encountered while parsing KeyValues3 text
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Kv3ParseError {
+    /// The 1-indexed line where the error was encountered
+    pub line: usize,
+    /// The 1-indexed column (in `char`s) where the error was encountered
+    pub col: usize,
+    pub kind: Kv3ParseErrorKind,
+}
+
+/// The different kinds of [`Kv3ParseError`]s
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kv3ParseErrorKind {
+    /// The `<!-- kv3 ... -->` header was malformed
+    InvalidHeader,
+    /// The input ended in the middle of a value
+    UnexpectedEof,
+    /// Encountered a character that can't start or continue the current item
+    UnexpectedChar(char),
+    /// A number literal was out of range or malformed
+    InvalidNumber,
+    /// An unknown escape sequence within a string
+    InvalidEscape(char),
+    /// A binary blob contained something other than pairs of hex digits
+    InvalidBinary,
+    /// There was more input after the root value
+    TrailingInput,
+    /// Objects, arrays, or flagged values were nested deeper than
+    /// [`Limits::DEFAULT_MAX_DEPTH`][crate::Limits::DEFAULT_MAX_DEPTH]
+    DepthExceeded,
+}
+
+impl fmt::Display for Kv3ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidHeader => f.write_str("Invalid kv3 header"),
+            Self::UnexpectedEof => f.write_str("Unexpected end of input"),
+            Self::UnexpectedChar(c) => write!(f, "Unexpected character: {c:?}"),
+            Self::InvalidNumber => f.write_str("Invalid number"),
+            Self::InvalidEscape(c) => write!(f, "Invalid escape sequence: \\{c}"),
+            Self::InvalidBinary => f.write_str("Invalid binary blob"),
+            Self::TrailingInput => f.write_str("Trailing input after the root value"),
+            Self::DepthExceeded => f.write_str("Values are nested too deeply"),
+        }
+    }
+}
+
+/// An error encountered while converting KeyValues3 to KeyValues
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Kv3ConvertError {
+    /// The path to the offending value e.g. `root.items[2]`
+    pub path: String,
+    pub kind: Kv3ConvertErrorKind,
+}
+
+/// The different kinds of [`Kv3ConvertError`]s
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kv3ConvertErrorKind {
+    /// KeyValues documents are a single pair, so the root has to be an object with one pair
+    RootNotSinglePair,
+    /// The type of value has no KeyValues equivalent
+    UnsupportedType(&'static str),
+    /// A float can't be represented as an `f32` without losing precision
+    LossyFloat,
+}
+
+impl fmt::Display for Kv3ConvertErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RootNotSinglePair => f.write_str("Root must be an object with exactly one pair"),
+            Self::UnsupportedType(ty) => write!(f, "Type has no KeyValues equivalent: {ty}"),
+            Self::LossyFloat => f.write_str("Float can't be represented as an `f32`"),
+        }
+    }
+}
+
+/// An error encountered while parsing DMX text
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DmxParseError {
+    /// The 1-indexed line where the error was encountered
+    pub line: usize,
+    /// The 1-indexed column (in `char`s) where the error was encountered
+    pub col: usize,
+    pub kind: DmxParseErrorKind,
+}
+
+/// The different kinds of [`DmxParseError`]s
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DmxParseErrorKind {
+    /// The `<!-- dmx ... -->` header was malformed or used a non-text encoding
+    InvalidHeader,
+    /// The input ended in the middle of an element
+    UnexpectedEof,
+    /// Encountered a character that can't start or continue the current item
+    UnexpectedChar(char),
+    /// An unknown escape sequence within a string
+    InvalidEscape(char),
+    /// An attribute's type isn't a known type (or array type)
+    UnknownType,
+    /// An attribute's value couldn't be parsed as its type
+    InvalidValue(AttributeType),
+    /// An element didn't have an `"id" "elementid"` attribute
+    MissingId,
+    /// Multiple elements had the same id
+    DuplicateId,
+    /// A reference pointed to an id that no element has
+    UnresolvedReference,
+    /// Inline elements were nested deeper than
+    /// [`Limits::DEFAULT_MAX_DEPTH`][crate::Limits::DEFAULT_MAX_DEPTH]
+    DepthExceeded,
+}
+
+impl fmt::Display for DmxParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidHeader => f.write_str("Invalid dmx header"),
+            Self::UnexpectedEof => f.write_str("Unexpected end of input"),
+            Self::UnexpectedChar(c) => write!(f, "Unexpected character: {c:?}"),
+            Self::InvalidEscape(c) => write!(f, "Invalid escape sequence: \\{c}"),
+            Self::UnknownType => f.write_str("Unknown attribute type"),
+            Self::InvalidValue(ty) => write!(f, "Invalid value for type: {}", ty.name()),
+            Self::MissingId => f.write_str("Element is missing an id"),
+            Self::DuplicateId => f.write_str("Element id is already used"),
+            Self::UnresolvedReference => f.write_str("Reference to an unknown element id"),
+            Self::DepthExceeded => f.write_str("Elements are nested too deeply"),
+        }
+    }
+}
+
+/// An error encountered while reading a stream of KeyValues text
+#[derive(Debug)]
+pub struct StreamError {
+    /// Where in the stream the error was encountered
+    pub position: Position,
+    pub kind: StreamErrorKind,
+}
+
+/// The different kinds of [`StreamError`]s
+#[derive(Debug)]
+pub enum StreamErrorKind {
+    /// Reading from the underlying reader failed
+    Io(io::Error),
+    /// A string wasn't valid UTF-8
+    InvalidUtf8,
+    /// The text was invalid
+    Parse(ParseErrorKind),
+}
+
+impl fmt::Display for StreamErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Failed reading input: {e}"),
+            Self::InvalidUtf8 => f.write_str("Invalid UTF-8 in string"),
+            Self::Parse(kind) => write!(f, "{kind}"),
+        }
+    }
+}
+
+/// An error encountered while writing a stream of KeyValues text
+#[derive(Debug)]
+pub struct WriteError {
+    /// How many objects were open when the error was encountered
+    pub depth: usize,
+    pub kind: WriteErrorKind,
+}
+
+/// The different kinds of [`WriteError`]s
+#[derive(Debug)]
+pub enum WriteErrorKind {
+    /// Writing to the underlying writer failed
+    Io(io::Error),
+    /// An object was ended without a matching start
+    UnbalancedEndObj,
+    /// The writer was finished while objects were still open
+    UnclosedObj,
+    /// A directive was written after the top-level pair
+    MisplacedDirective,
+    /// A directive's path contained a `"` or newline which can't be written
+    InvalidDirectivePath,
+    /// A pair was written after the top-level pair was already complete
+    MultipleTopLevelPairs,
+    /// The writer was finished without writing a top-level pair
+    MissingTopLevelPair,
+}
+
+impl fmt::Display for WriteErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "Failed writing output: {e}"),
+            Self::UnbalancedEndObj => f.write_str("Ended an object that was never started"),
+            Self::UnclosedObj => f.write_str("Finished with unclosed objects"),
+            Self::MisplacedDirective => {
+                f.write_str("Directives must come before the top-level pair")
+            }
+            Self::InvalidDirectivePath => {
+                f.write_str("Directive path can't contain a quote or newline")
+            }
+            Self::MultipleTopLevelPairs => f.write_str("Only one top-level pair is allowed"),
+            Self::MissingTopLevelPair => f.write_str("Finished without a top-level pair"),
+        }
+    }
+}
+
+/// An error encountered while resolving `#base` and `#include` directives
+#[derive(Debug)]
+pub struct ResolveError {
+    /// The path (relative to the loader's root) of the file that caused the error
+    pub path: PathBuf,
+    pub kind: ResolveErrorKind,
+}
+
+/// The different kinds of [`ResolveError`]s
+#[derive(Debug)]
+pub enum ResolveErrorKind {
+    /// The loader failed to load the file
+    Load(io::Error),
+    /// The file was parsed while it was already being resolved. Holds the chain of files that
+    /// make up the cycle starting and ending with the same file
+    Cycle(Vec<PathBuf>),
+    /// The path is absolute or points outside of the loader's root
+    EscapesRoot,
+    /// The file failed to parse
+    Parse(Box<Error>),
+}
+
+impl fmt::Display for ResolveErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Load(e) => write!(f, "Failed loading file: {e}"),
+            Self::Cycle(cycle) => {
+                f.write_str("Cyclic directives: ")?;
+                for (i, path) in cycle.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(" -> ")?;
+                    }
+                    write!(f, "{}", path.display())?;
+                }
+                Ok(())
+            }
+            Self::EscapesRoot => f.write_str("Path escapes the root directory"),
+            Self::Parse(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Self::ParseError(e)
+    }
+}
+
+impl From<BinaryParseError> for Error {
+    fn from(e: BinaryParseError) -> Self {
+        Self::BinaryParseError(e)
+    }
+}
+
+impl From<Kv3ParseError> for Error {
+    fn from(e: Kv3ParseError) -> Self {
+        Self::Kv3ParseError(e)
+    }
+}
+
+impl From<Kv3ConvertError> for Error {
+    fn from(e: Kv3ConvertError) -> Self {
+        Self::Kv3ConvertError(e)
+    }
+}
+
+impl From<DmxParseError> for Error {
+    fn from(e: DmxParseError) -> Self {
+        Self::DmxParseError(e)
+    }
+}
+
+impl From<StreamError> for Error {
+    fn from(e: StreamError) -> Self {
+        Self::StreamError(e)
+    }
+}
+
+impl From<WriteError> for Error {
+    fn from(e: WriteError) -> Self {
+        Self::WriteError(e)
+    }
+}
+
+impl From<ResolveError> for Error {
+    fn from(e: ResolveError) -> Self {
+        Self::ResolveError(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
     }
 }
 
@@ -45,15 +633,73 @@ impl From<std::fmt::Error> for Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::EscapedParseError(e) => write!(f, "Failed parsing input Error: {e}"),
-            Self::RawParseError(e) => write!(f, "Failed parsing input Error: {e}"),
+            Self::ParseError(e) => write!(
+                f,
+                "Failed parsing input at {}:{}: {}",
+                e.line(),
+                e.col(),
+                e.kind()
+            ),
+            Self::BinaryParseError(BinaryParseError { offset, kind }) => {
+                write!(f, "Failed parsing binary input at offset {offset}: {kind}")
+            }
+            Self::Kv3ParseError(Kv3ParseError { line, col, kind }) => {
+                write!(f, "Failed parsing kv3 input at {line}:{col}: {kind}")
+            }
+            Self::Kv3ConvertError(Kv3ConvertError { path, kind }) => {
+                write!(f, "Failed converting kv3 at `{path}`: {kind}")
+            }
+            Self::DmxParseError(DmxParseError { line, col, kind }) => {
+                write!(f, "Failed parsing dmx input at {line}:{col}: {kind}")
+            }
+            Self::StreamError(StreamError { position, kind }) => write!(
+                f,
+                "Failed reading input at {}:{}: {kind}",
+                position.line, position.col
+            ),
+            Self::WriteError(WriteError { depth, kind }) => {
+                write!(f, "Failed writing output at depth {depth}: {kind}")
+            }
+            Self::ResolveError(ResolveError { path, kind }) => {
+                write!(f, "Failed resolving `{}`: {kind}", path.display())
+            }
             Self::RenderError(e) => write!(f, "Failed rendering input Error: {e}"),
             Self::RawRenderError { invalid_char } => write!(
                 f,
                 "Encountered invalid character in raw string: {invalid_char:?}"
             ),
+            Self::InvalidDirectivePath { path } => {
+                write!(
+                    f,
+                    "Directive path can't contain a quote or newline: {path:?}"
+                )
+            }
+            Self::Io(e) => write!(f, "Encountered I/O error: {e}"),
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e)
+            | Self::StreamError(StreamError {
+                kind: StreamErrorKind::Io(e),
+                ..
+            })
+            | Self::WriteError(WriteError {
+                kind: WriteErrorKind::Io(e),
+                ..
+            })
+            | Self::ResolveError(ResolveError {
+                kind: ResolveErrorKind::Load(e),
+                ..
+            }) => Some(e),
+            Self::ResolveError(ResolveError {
+                kind: ResolveErrorKind::Parse(e),
+                ..
+            }) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
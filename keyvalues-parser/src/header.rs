@@ -0,0 +1,10 @@
+// The `<!-- ... -->` comment that starts both KeyValues3 and DMX documents
+
+// Returns the contents of the header at the start of `text` along with the length of the whole
+// header including its delimiters
+pub(crate) fn split(text: &str) -> Option<(&str, usize)> {
+    let contents = text.strip_prefix("<!--")?;
+    // The end is searched for after the start, so that `<!-->` isn't taken as a whole comment
+    let end = contents.find("-->")?;
+    Some((&contents[..end], "<!--".len() + end + "-->".len()))
+}
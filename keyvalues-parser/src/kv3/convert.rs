@@ -0,0 +1,128 @@
+use super::{Kv3, Obj, Value};
+use crate::{
+    error::{Error, Kv3ConvertError, Kv3ConvertErrorKind},
+    TypedValue,
+};
+
+type ConvertResult<T> = std::result::Result<T, Kv3ConvertError>;
+
+/// Converts the [`Vdf`][crate::Vdf] to a document with a root object containing its single pair
+///
+/// Strings and objects (including duplicate keys in their original order) carry over as-is while
+/// typed leaves become the closest KeyValues3 type. Pointers become unsigned ints, wide strings
+/// become strings, and colors become an array of their components. Conditionals are dropped
+impl<'text> From<crate::Vdf<'text>> for Kv3<'text> {
+    fn from(vdf: crate::Vdf<'text>) -> Self {
+        let mut root = Obj::new();
+        root.push(vdf.key, vdf.value.into());
+        Self::new(Value::Obj(root))
+    }
+}
+
+impl<'text> From<crate::Value<'text>> for Value<'text> {
+    fn from(value: crate::Value<'text>) -> Self {
+        match value {
+            crate::Value::Str(s) => Self::Str(s),
+            crate::Value::Obj(obj) => Self::Obj(
+                obj.into_vdfs()
+                    .map(|vdf| (vdf.key, vdf.value.into()))
+                    .collect(),
+            ),
+            crate::Value::Typed(typed) => match typed {
+                TypedValue::Int(i) => Self::Int(i.into()),
+                TypedValue::Float(f) => Self::Float(f.into()),
+                TypedValue::Ptr(p) => Self::UInt(p.into()),
+                TypedValue::WideStr(s) => Self::Str(s),
+                TypedValue::Color(rgba) => {
+                    Self::Array(rgba.iter().map(|&c| Self::Int(c.into())).collect())
+                }
+                TypedValue::UInt64(u) => Self::UInt(u),
+                TypedValue::Int64(i) => Self::Int(i),
+            },
+        }
+    }
+}
+
+/// Converts a document whose root object has a single pair
+///
+/// Strings and objects carry over as-is while numbers become [`TypedValue`]s. Ints that fit in an
+/// [`i32`] become [`TypedValue::Int`] with larger ones using the 64-bit variants, and floats have
+/// to be representable as an [`f32`]. Anything else (nulls, bools, arrays, binary blobs, and
+/// flagged values) has no KeyValues equivalent and is an error
+///
+/// ```
+/// use keyvalues_parser::{kv3::Kv3, TypedValue, Value, Vdf};
+///
+/// let kv3 = Kv3::parse(r#"{ Config = { name = "example" count = 2 } }"#)?;
+/// let vdf = Vdf::try_from(kv3)?;
+/// let config = vdf.value.get_obj().unwrap();
+/// assert_eq!(config.get("name").unwrap()[0].get_str(), Some("example"));
+/// assert_eq!(config.get("count").unwrap()[0], Value::Typed(TypedValue::Int(2)));
+/// # Ok::<(), keyvalues_parser::error::Error>(())
+/// ```
+impl<'text> TryFrom<Kv3<'text>> for crate::Vdf<'text> {
+    type Error = Error;
+
+    fn try_from(kv3: Kv3<'text>) -> Result<Self, Self::Error> {
+        let root_err = || Kv3ConvertError {
+            path: String::new(),
+            kind: Kv3ConvertErrorKind::RootNotSinglePair,
+        };
+        let Value::Obj(root) = kv3.root else {
+            return Err(root_err().into());
+        };
+        let mut pairs = root.into_iter();
+        let (Some((key, value)), None) = (pairs.next(), pairs.next()) else {
+            return Err(root_err().into());
+        };
+
+        let mut path = key.to_string();
+        let value = into_kv1(value, &mut path)?;
+        Ok(Self::new(key, value))
+    }
+}
+
+fn into_kv1<'text>(value: Value<'text>, path: &mut String) -> ConvertResult<crate::Value<'text>> {
+    let unsupported = |ty, path: &str| Kv3ConvertError {
+        path: path.to_owned(),
+        kind: Kv3ConvertErrorKind::UnsupportedType(ty),
+    };
+
+    let value = match value {
+        Value::Str(s) => crate::Value::Str(s),
+        Value::Int(i) => match i32::try_from(i) {
+            Ok(i) => crate::Value::Typed(TypedValue::Int(i)),
+            Err(_) => crate::Value::Typed(TypedValue::Int64(i)),
+        },
+        Value::UInt(u) => crate::Value::Typed(TypedValue::UInt64(u)),
+        Value::Float(f) => {
+            let narrowed = f as f32;
+            if f64::from(narrowed) != f && !f.is_nan() {
+                return Err(Kv3ConvertError {
+                    path: path.clone(),
+                    kind: Kv3ConvertErrorKind::LossyFloat,
+                });
+            }
+            crate::Value::Typed(TypedValue::Float(narrowed))
+        }
+        Value::Obj(obj) => {
+            let mut kv1 = crate::Obj::new();
+            for (key, value) in obj {
+                let path_len = path.len();
+                path.push('.');
+                path.push_str(&key);
+                let value = into_kv1(value, path)?;
+                path.truncate(path_len);
+                kv1.push(key, value, None);
+            }
+            crate::Value::Obj(kv1)
+        }
+        Value::Null => return Err(unsupported("null", path)),
+        Value::Bool(_) => return Err(unsupported("bool", path)),
+        Value::Binary(_) => return Err(unsupported("binary", path)),
+        Value::Array(_) => return Err(unsupported("array", path)),
+        Value::Flagged(..) => return Err(unsupported("flagged value", path)),
+    };
+
+    Ok(value)
+}
@@ -0,0 +1,274 @@
+//! KeyValues3 text support
+//!
+//! Source 2 games replaced KeyValues with KeyValues3 which has a richer set of types (bools,
+//! numbers, arrays, binary blobs, etc.) and starts with a header that describes the encoding and
+//! format of the document
+//!
+//! ```text
+//! <!-- kv3 encoding:text:version{e21c7f3c-8a33-41c5-9977-a76d3a32aa0d} format:generic:version{7412167c-06e9-4698-aff2-e63eb59037e7} -->
+//! {
+//!     name = "Example"
+//!     enabled = true
+//!     scale = 1.5
+//!     tags = [ "a", "b" ]
+//!     model = resource:"models/example.vmdl"
+//! }
+//! ```
+//!
+//! Documents are parsed to a [`Kv3`] which can be rendered back to text. Converting to and from a
+//! KeyValues [`Vdf`][crate::Vdf] is supported for data that fits in both formats. See
+//! [`Kv3::from()`] and [`Vdf::try_from()`][crate::Vdf::try_from]
+
+use std::{borrow::Cow, slice, vec};
+
+mod convert;
+mod parse;
+mod render;
+
+pub use parse::parse;
+
+/// A parsed KeyValues3 document
+///
+/// ```
+/// use keyvalues_parser::kv3::{self, Value};
+///
+/// let text = r#"
+/// <!-- kv3 encoding:text:version{e21c7f3c-8a33-41c5-9977-a76d3a32aa0d} format:generic:version{7412167c-06e9-4698-aff2-e63eb59037e7} -->
+/// {
+///     count = 3
+///     names = [ "a", "b", "c" ]
+/// }
+/// "#;
+/// let kv3 = kv3::parse(text)?;
+/// let root = kv3.root.get_obj().unwrap();
+/// assert_eq!(root.get("count"), Some(&Value::Int(3)));
+/// assert_eq!(root.get("names").unwrap().get_array().unwrap().len(), 3);
+/// # Ok::<(), keyvalues_parser::error::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Kv3<'text> {
+    pub header: Header<'text>,
+    /// The root value. This is almost always an object
+    pub root: Value<'text>,
+}
+
+impl<'text> Kv3<'text> {
+    /// Creates a document with the default [`Header`]
+    pub fn new(root: Value<'text>) -> Self {
+        Self {
+            header: Header::default(),
+            root,
+        }
+    }
+
+    /// Attempts to parse KeyValues3 text
+    ///
+    /// This is the same as [`kv3::parse()`][parse]
+    pub fn parse(text: &'text str) -> crate::error::Result<Self> {
+        parse(text)
+    }
+
+    /// Converts the document to one that owns all of its data
+    pub fn into_owned(self) -> Kv3<'static> {
+        Kv3 {
+            header: self.header.into_owned(),
+            root: self.root.into_owned(),
+        }
+    }
+}
+
+/// The header comment that starts a document
+///
+/// `<!-- kv3 encoding:text:version{...} format:generic:version{...} -->`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Header<'text> {
+    pub encoding: Format<'text>,
+    pub format: Format<'text>,
+}
+
+impl Header<'_> {
+    pub fn into_owned(self) -> Header<'static> {
+        Header {
+            encoding: self.encoding.into_owned(),
+            format: self.format.into_owned(),
+        }
+    }
+}
+
+/// Uses the `text` encoding and `generic` format
+impl Default for Header<'_> {
+    fn default() -> Self {
+        Self {
+            encoding: Format {
+                name: Cow::Borrowed("text"),
+                version: Cow::Borrowed("e21c7f3c-8a33-41c5-9977-a76d3a32aa0d"),
+            },
+            format: Format {
+                name: Cow::Borrowed("generic"),
+                version: Cow::Borrowed("7412167c-06e9-4698-aff2-e63eb59037e7"),
+            },
+        }
+    }
+}
+
+/// A named and versioned entry within the [`Header`] e.g. `format:generic:version{...}`
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Format<'text> {
+    pub name: Cow<'text, str>,
+    /// The GUID from within the braces
+    pub version: Cow<'text, str>,
+}
+
+impl Format<'_> {
+    pub fn into_owned(self) -> Format<'static> {
+        Format {
+            name: Cow::Owned(self.name.into_owned()),
+            version: Cow::Owned(self.version.into_owned()),
+        }
+    }
+}
+
+/// A KeyValues3 value
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value<'text> {
+    Null,
+    Bool(bool),
+    Int(i64),
+    /// Only used for integers that are too large to fit in an [`i64`]
+    UInt(u64),
+    Float(f64),
+    Str(Cow<'text, str>),
+    /// A binary blob written as hex bytes e.g. `#[ 00 ff ]`
+    Binary(Vec<u8>),
+    Array(Vec<Value<'text>>),
+    Obj(Obj<'text>),
+    /// A value with a flag that hints at how it's used e.g. `resource:"models/example.vmdl"`
+    Flagged(Cow<'text, str>, Box<Value<'text>>),
+}
+
+impl<'text> Value<'text> {
+    pub fn get_str(&self) -> Option<&str> {
+        if let Self::Str(s) = self {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_array(&self) -> Option<&[Value<'text>]> {
+        if let Self::Array(array) = self {
+            Some(array)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_obj(&self) -> Option<&Obj<'text>> {
+        if let Self::Obj(obj) = self {
+            Some(obj)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut_obj(&mut self) -> Option<&mut Obj<'text>> {
+        if let Self::Obj(obj) = self {
+            Some(obj)
+        } else {
+            None
+        }
+    }
+
+    /// Converts the value to one that owns all of its data
+    pub fn into_owned(self) -> Value<'static> {
+        match self {
+            Self::Null => Value::Null,
+            Self::Bool(b) => Value::Bool(b),
+            Self::Int(i) => Value::Int(i),
+            Self::UInt(u) => Value::UInt(u),
+            Self::Float(f) => Value::Float(f),
+            Self::Str(s) => Value::Str(Cow::Owned(s.into_owned())),
+            Self::Binary(bytes) => Value::Binary(bytes),
+            Self::Array(array) => Value::Array(array.into_iter().map(Value::into_owned).collect()),
+            Self::Obj(obj) => Value::Obj(obj.into_owned()),
+            Self::Flagged(flag, value) => {
+                Value::Flagged(Cow::Owned(flag.into_owned()), Box::new(value.into_owned()))
+            }
+        }
+    }
+}
+
+/// An object of key-value pairs that keeps the order that the pairs were written in
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Obj<'text> {
+    pairs: Vec<(Cow<'text, str>, Value<'text>)>,
+}
+
+impl<'text> Obj<'text> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Gets the value for the first pair with a matching key
+    pub fn get(&self, key: &str) -> Option<&Value<'text>> {
+        self.pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// Gets the value for the first pair with a matching key
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value<'text>> {
+        self.pairs
+            .iter_mut()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// Appends a pair to the end of the object
+    pub fn push(&mut self, key: impl Into<Cow<'text, str>>, value: Value<'text>) {
+        self.pairs.push((key.into(), value));
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, (Cow<'text, str>, Value<'text>)> {
+        self.pairs.iter()
+    }
+
+    pub fn into_owned(self) -> Obj<'static> {
+        self.pairs
+            .into_iter()
+            .map(|(key, value)| (Cow::Owned(key.into_owned()), value.into_owned()))
+            .collect()
+    }
+}
+
+impl<'text> FromIterator<(Cow<'text, str>, Value<'text>)> for Obj<'text> {
+    fn from_iter<T: IntoIterator<Item = (Cow<'text, str>, Value<'text>)>>(iter: T) -> Self {
+        Self {
+            pairs: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<'text> IntoIterator for Obj<'text> {
+    type Item = (Cow<'text, str>, Value<'text>);
+    type IntoIter = vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pairs.into_iter()
+    }
+}
+
+impl<'a, 'text> IntoIterator for &'a Obj<'text> {
+    type Item = &'a (Cow<'text, str>, Value<'text>);
+    type IntoIter = slice::Iter<'a, (Cow<'text, str>, Value<'text>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
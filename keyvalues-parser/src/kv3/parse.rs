@@ -0,0 +1,360 @@
+use std::borrow::Cow;
+
+use super::{Format, Header, Kv3, Obj, Value};
+use crate::{
+    error::{Kv3ParseError, Kv3ParseErrorKind, Result},
+    header, Limits,
+};
+
+type Kv3Result<T> = std::result::Result<T, Kv3ParseError>;
+
+/// Parses KeyValues3 text to a [`Kv3`]
+///
+/// The header is optional. When it's missing the default [`Header`] is used
+///
+/// Objects, arrays, and flagged values can nest up to [`Limits::DEFAULT_MAX_DEPTH`] deep
+pub fn parse(text: &str) -> Result<Kv3<'_>> {
+    let mut parser = Parser {
+        text,
+        pos: 0,
+        depth: 0,
+    };
+    let kv3 = parser.document()?;
+    Ok(kv3)
+}
+
+struct Parser<'text> {
+    text: &'text str,
+    pos: usize,
+    depth: usize,
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.'
+}
+
+impl<'text> Parser<'text> {
+    fn rest(&self) -> &'text str {
+        &self.text[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn err_at(&self, pos: usize, kind: Kv3ParseErrorKind) -> Kv3ParseError {
+        let before = &self.text[..pos];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let col = before[line_start..].chars().count() + 1;
+        Kv3ParseError { line, col, kind }
+    }
+
+    fn err(&self, kind: Kv3ParseErrorKind) -> Kv3ParseError {
+        self.err_at(self.pos, kind)
+    }
+
+    // Errors for whatever is at the current position
+    fn unexpected(&self) -> Kv3ParseError {
+        match self.peek() {
+            Some(c) => self.err(Kv3ParseErrorKind::UnexpectedChar(c)),
+            None => self.err(Kv3ParseErrorKind::UnexpectedEof),
+        }
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if self.rest().starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Kv3Result<()> {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'text str {
+        let rest = self.rest();
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    // Skips whitespace and comments
+    fn skip(&mut self) -> Kv3Result<()> {
+        loop {
+            self.take_while(char::is_whitespace);
+            if self.eat("//") {
+                self.take_while(|c| c != '\n');
+            } else if self.rest().starts_with("/*") {
+                let start = self.pos;
+                match self.rest().find("*/") {
+                    Some(end) => self.pos += end + 2,
+                    None => return Err(self.err_at(start, Kv3ParseErrorKind::UnexpectedEof)),
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn document(&mut self) -> Kv3Result<Kv3<'text>> {
+        self.skip()?;
+        let header = if self.rest().starts_with("<!--") {
+            self.header()?
+        } else {
+            Header::default()
+        };
+
+        self.skip()?;
+        let root = self.value()?;
+        self.skip()?;
+        if self.pos != self.text.len() {
+            return Err(self.err(Kv3ParseErrorKind::TrailingInput));
+        }
+
+        Ok(Kv3 { header, root })
+    }
+
+    // `<!-- kv3 encoding:text:version{...} format:generic:version{...} -->`
+    fn header(&mut self) -> Kv3Result<Header<'text>> {
+        let start = self.pos;
+        let invalid = || self.err_at(start, Kv3ParseErrorKind::InvalidHeader);
+        let (contents, len) = header::split(self.rest()).ok_or_else(invalid)?;
+        let mut parts = contents.split_whitespace();
+
+        if parts.next() != Some("kv3") {
+            return Err(invalid());
+        }
+        let mut encoding = None;
+        let mut format = None;
+        for part in parts {
+            let (kind, format_str) = part.split_once(':').ok_or_else(invalid)?;
+            let (name, version) = format_str.split_once(':').ok_or_else(invalid)?;
+            let version = version
+                .strip_prefix("version{")
+                .and_then(|version| version.strip_suffix('}'))
+                .ok_or_else(invalid)?;
+            let parsed = Format {
+                name: Cow::Borrowed(name),
+                version: Cow::Borrowed(version),
+            };
+            match kind {
+                "encoding" => encoding = Some(parsed),
+                "format" => format = Some(parsed),
+                _ => return Err(invalid()),
+            }
+        }
+
+        let header = Header {
+            encoding: encoding.ok_or_else(invalid)?,
+            format: format.ok_or_else(invalid)?,
+        };
+        self.pos += len;
+        Ok(header)
+    }
+
+    // Parsing nested values recurses, so the depth is limited
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Kv3Result<T>) -> Kv3Result<T> {
+        if self.depth == Limits::DEFAULT_MAX_DEPTH {
+            return Err(self.err(Kv3ParseErrorKind::DepthExceeded));
+        }
+        self.depth += 1;
+        let nested = f(self);
+        self.depth -= 1;
+        nested
+    }
+
+    fn value(&mut self) -> Kv3Result<Value<'text>> {
+        match self.peek() {
+            Some('{') => self.nested(Self::obj).map(Value::Obj),
+            Some('[') => self.nested(Self::array).map(Value::Array),
+            Some('"') => self.str().map(Value::Str),
+            Some('#') => self.binary().map(Value::Binary),
+            Some(c) if c == '-' || c == '+' || c.is_ascii_digit() => self.number(),
+            Some(c) if is_ident_char(c) => {
+                let start = self.pos;
+                let ident = self.take_while(is_ident_char);
+                match ident {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "null" => Ok(Value::Null),
+                    flag => {
+                        if self.peek() != Some(':') {
+                            self.pos = start;
+                            return Err(self.unexpected());
+                        }
+                        self.pos += 1;
+                        let value = self.nested(Self::value)?;
+                        Ok(Value::Flagged(Cow::Borrowed(flag), Box::new(value)))
+                    }
+                }
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn obj(&mut self) -> Kv3Result<Obj<'text>> {
+        self.expect('{')?;
+        let mut obj = Obj::new();
+        loop {
+            self.skip()?;
+            if self.eat("}") {
+                return Ok(obj);
+            }
+
+            let key = match self.peek() {
+                Some('"') => self.str()?,
+                Some(c) if is_ident_char(c) => Cow::Borrowed(self.take_while(is_ident_char)),
+                _ => return Err(self.unexpected()),
+            };
+            self.skip()?;
+            self.expect('=')?;
+            self.skip()?;
+            let value = self.value()?;
+            obj.push(key, value);
+
+            // Commas between pairs are optional
+            self.skip()?;
+            self.eat(",");
+        }
+    }
+
+    fn array(&mut self) -> Kv3Result<Vec<Value<'text>>> {
+        self.expect('[')?;
+        let mut array = Vec::new();
+        loop {
+            self.skip()?;
+            if self.eat("]") {
+                return Ok(array);
+            }
+
+            array.push(self.value()?);
+            self.skip()?;
+            // Trailing commas are allowed
+            if !self.eat(",") && self.peek() != Some(']') {
+                return Err(self.unexpected());
+            }
+        }
+    }
+
+    fn str(&mut self) -> Kv3Result<Cow<'text, str>> {
+        if self.eat(r#"""""#) {
+            return self.multi_line_str();
+        }
+
+        self.expect('"')?;
+        let start = self.pos;
+        let mut owned: Option<String> = None;
+        loop {
+            let c = self
+                .peek()
+                .ok_or_else(|| self.err(Kv3ParseErrorKind::UnexpectedEof))?;
+            match c {
+                '"' => {
+                    let s = match owned {
+                        Some(s) => Cow::Owned(s),
+                        None => Cow::Borrowed(&self.text[start..self.pos]),
+                    };
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                '\\' => {
+                    let s = owned.get_or_insert_with(|| self.text[start..self.pos].to_owned());
+                    self.pos += 1;
+                    let escaped = self.rest().chars().next();
+                    let unescaped = match escaped {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some(other) => {
+                            return Err(self.err(Kv3ParseErrorKind::InvalidEscape(other)))
+                        }
+                        None => return Err(self.err(Kv3ParseErrorKind::UnexpectedEof)),
+                    };
+                    s.push(unescaped);
+                    self.pos += 1;
+                }
+                c => {
+                    if let Some(s) = &mut owned {
+                        s.push(c);
+                    }
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    // The contents are everything between the newline after the opening quotes and the newline
+    // before the closing quotes
+    fn multi_line_str(&mut self) -> Kv3Result<Cow<'text, str>> {
+        let start = self.pos - 3;
+        if !self.eat("\r\n") && !self.eat("\n") {
+            return Err(self.unexpected());
+        }
+
+        let rest = self.rest();
+        let end = rest
+            .find("\n\"\"\"")
+            .ok_or_else(|| self.err_at(start, Kv3ParseErrorKind::UnexpectedEof))?;
+        let contents = &rest[..end];
+        let contents = contents.strip_suffix('\r').unwrap_or(contents);
+        self.pos += end + "\n\"\"\"".len();
+        Ok(Cow::Borrowed(contents))
+    }
+
+    // `#[ 00 01 ff ]`
+    fn binary(&mut self) -> Kv3Result<Vec<u8>> {
+        self.expect('#')?;
+        self.expect('[')?;
+        let mut bytes = Vec::new();
+        loop {
+            self.skip()?;
+            if self.eat("]") {
+                return Ok(bytes);
+            }
+
+            let start = self.pos;
+            let hex = self.take_while(|c| c.is_ascii_hexdigit());
+            if hex.is_empty() {
+                return Err(self.unexpected());
+            }
+            if hex.len() % 2 != 0 {
+                return Err(self.err_at(start, Kv3ParseErrorKind::InvalidBinary));
+            }
+            for pair in hex.as_bytes().chunks(2) {
+                let pair = std::str::from_utf8(pair).expect("Hex digits are ASCII");
+                bytes.push(u8::from_str_radix(pair, 16).expect("Validated hex digits"));
+            }
+        }
+    }
+
+    fn number(&mut self) -> Kv3Result<Value<'text>> {
+        let start = self.pos;
+        let literal =
+            self.take_while(|c| c.is_ascii_alphanumeric() || c == '-' || c == '+' || c == '.');
+        let invalid = || self.err_at(start, Kv3ParseErrorKind::InvalidNumber);
+
+        if literal.contains(['.', 'e', 'E']) {
+            literal.parse().map(Value::Float).map_err(|_| invalid())
+        } else if let Ok(i) = literal.parse() {
+            Ok(Value::Int(i))
+        } else {
+            literal
+                .strip_prefix('+')
+                .unwrap_or(literal)
+                .parse()
+                .map(Value::UInt)
+                .map_err(|_| invalid())
+        }
+    }
+}
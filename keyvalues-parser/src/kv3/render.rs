@@ -0,0 +1,225 @@
+use std::{
+    borrow::Cow,
+    fmt::{self, Write},
+    slice,
+};
+
+use super::{Format, Header, Kv3, Value};
+
+fn multiple_char(c: char, amount: usize) -> String {
+    std::iter::repeat(c).take(amount).collect()
+}
+
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        && !matches!(s, "true" | "false" | "null")
+}
+
+// Multi-line strings can't represent contents that contain their own terminator or a leading or
+// trailing carriage return
+fn fits_multi_line(s: &str) -> bool {
+    s.contains('\n') && !s.contains(r#"""""#) && !s.starts_with('\r') && !s.ends_with('\r')
+}
+
+fn write_str(writer: &mut impl Write, s: &str) -> fmt::Result {
+    writer.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '\n' => writer.write_str(r"\n"),
+            '\r' => writer.write_str(r"\r"),
+            '\t' => writer.write_str(r"\t"),
+            '\"' => writer.write_str(r#"\""#),
+            '\\' => writer.write_str(r"\\"),
+            reg => writer.write_char(reg),
+        }?
+    }
+    writer.write_char('"')
+}
+
+fn write_key(writer: &mut impl Write, key: &str) -> fmt::Result {
+    if is_ident(key) {
+        writer.write_str(key)
+    } else {
+        write_str(writer, key)
+    }
+}
+
+impl fmt::Display for Format<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:version{{{}}}", self.name, self.version)
+    }
+}
+
+impl fmt::Display for Header<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "<!-- kv3 encoding:{} format:{} -->",
+            self.encoding, self.format
+        )
+    }
+}
+
+impl fmt::Display for Kv3<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.header)?;
+        self.root.write_indented(f, 0)?;
+        f.write_char('\n')
+    }
+}
+
+impl Kv3<'_> {
+    pub fn render(&self, writer: &mut impl Write) -> crate::error::Result<()> {
+        write!(writer, "{self}").map_err(Into::into)
+    }
+}
+
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_indented(f, 0)
+    }
+}
+
+// An object or multi-line array that has been opened along with whatever is left to write in it
+enum Block<'a, 'text> {
+    Obj(slice::Iter<'a, (Cow<'text, str>, Value<'text>)>),
+    Array(slice::Iter<'a, Value<'text>>),
+}
+
+impl Block<'_, '_> {
+    // What follows each of the values within the block
+    fn value_end(&self) -> &'static str {
+        match self {
+            Self::Obj(_) => "\n",
+            Self::Array(_) => ",\n",
+        }
+    }
+}
+
+impl<'text> Value<'text> {
+    // Flags don't change how a value gets laid out
+    fn unflagged(&self) -> &Self {
+        let mut value = self;
+        while let Self::Flagged(_, inner) = value {
+            value = inner;
+        }
+        value
+    }
+
+    // Values that get written across multiple lines start on their own line
+    fn is_block(&self) -> bool {
+        match self.unflagged() {
+            Self::Obj(_) => true,
+            Self::Array(array) => !array.iter().all(Value::is_inline),
+            _ => false,
+        }
+    }
+
+    // Whether the value can be written in an array on a single line
+    fn is_inline(&self) -> bool {
+        match self.unflagged() {
+            Self::Obj(_) | Self::Array(_) => false,
+            Self::Str(s) => !fits_multi_line(s),
+            _ => true,
+        }
+    }
+
+    fn write_indented(&self, writer: &mut impl Write, num_indents: usize) -> fmt::Result {
+        let mut stack: Vec<_> = self.write_start(writer)?.into_iter().collect();
+        loop {
+            let indent = multiple_char('\t', (num_indents + stack.len()).saturating_sub(1));
+            let Some(block) = stack.last_mut() else {
+                return Ok(());
+            };
+            let next = match block {
+                Block::Obj(pairs) => pairs.next().map(|(key, value)| (Some(key), value)),
+                Block::Array(values) => values.next().map(|value| (None, value)),
+            };
+
+            let Some((key, value)) = next else {
+                let closing = if let Block::Obj(_) = block { '}' } else { ']' };
+                write!(writer, "{indent}{closing}")?;
+                stack.pop();
+                if let Some(parent) = stack.last() {
+                    writer.write_str(parent.value_end())?;
+                }
+                continue;
+            };
+
+            write!(writer, "{indent}\t")?;
+            if let Some(key) = key {
+                write_key(writer, key)?;
+                writer.write_str(" =")?;
+                if value.is_block() {
+                    write!(writer, "\n{indent}\t")?;
+                } else {
+                    writer.write_char(' ')?;
+                }
+            }
+            match value.write_start(writer)? {
+                Some(inner) => stack.push(inner),
+                None => writer.write_str(block.value_end())?,
+            }
+        }
+    }
+
+    // Writes everything up to the contents of an object or multi-line array which gets returned
+    // instead to be filled in by the caller
+    fn write_start<'a>(
+        &'a self,
+        writer: &mut impl Write,
+    ) -> Result<Option<Block<'a, 'text>>, fmt::Error> {
+        let mut value = self;
+        while let Self::Flagged(flag, inner) = value {
+            write!(writer, "{flag}:")?;
+            value = inner;
+        }
+
+        match value {
+            Self::Null => writer.write_str("null")?,
+            Self::Bool(b) => write!(writer, "{b}")?,
+            Self::Int(i) => write!(writer, "{i}")?,
+            Self::UInt(u) => write!(writer, "{u}")?,
+            // Debug formatting always includes a decimal point or exponent, so it reads back as a
+            // float
+            Self::Float(float) => write!(writer, "{float:?}")?,
+            Self::Str(s) if fits_multi_line(s) => write!(writer, "\"\"\"\n{s}\n\"\"\"")?,
+            Self::Str(s) => write_str(writer, s)?,
+            Self::Binary(bytes) => {
+                writer.write_str("#[")?;
+                for byte in bytes {
+                    write!(writer, " {byte:02x}")?;
+                }
+                writer.write_str(" ]")?;
+            }
+            Self::Array(array) if array.is_empty() => writer.write_str("[]")?,
+            Self::Array(array) if !value.is_block() => {
+                writer.write_str("[ ")?;
+                for (i, value) in array.iter().enumerate() {
+                    if i != 0 {
+                        writer.write_str(", ")?;
+                    }
+                    // Inline values never open a block
+                    value.write_start(writer)?;
+                }
+                writer.write_str(" ]")?;
+            }
+            Self::Array(array) => {
+                writer.write_str("[\n")?;
+                return Ok(Some(Block::Array(array.iter())));
+            }
+            Self::Obj(obj) if obj.is_empty() => writer.write_str("{}")?,
+            Self::Obj(obj) => {
+                writer.write_str("{\n")?;
+                return Ok(Some(Block::Obj(obj.iter())));
+            }
+            Self::Flagged(..) => unreachable!("Flags were already written"),
+        }
+
+        Ok(None)
+    }
+}
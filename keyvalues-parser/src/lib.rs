@@ -6,18 +6,35 @@
 
 use std::{
     borrow::Cow,
-    collections::{btree_map::IntoIter, BTreeMap},
-    ops::{Deref, DerefMut},
+    collections::BTreeMap,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    vec,
 };
 
+pub mod binary;
+mod conditional;
+pub mod cst;
+pub mod dmx;
+mod encoding;
 pub mod error;
+mod header;
+pub mod kv3;
+pub mod resolve;
 #[cfg(feature = "serde")]
 #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
 mod serde;
+mod span;
+pub mod stream;
 pub mod text;
+mod typed;
 
-/// `pest` re-exported for your convenience :)
-pub use pest;
+pub use conditional::Conditional;
+pub use encoding::Encoding;
+pub use span::{DocumentSpans, PairSpan, Position, RootSpans, Span};
+pub use text::parse::Recovered;
+pub use typed::TypedValue;
 
 /// Parse a KeyValues document to a loosely typed representation
 ///
@@ -30,6 +47,11 @@ pub fn parse<'text>(text: &'text str) -> error::Result<PartialVdf<'text>> {
 #[derive(Clone, Debug, Default)]
 pub struct Parser {
     literal_special_chars: bool,
+    spans: bool,
+    lossy: bool,
+    case_insensitive_keys: bool,
+    duplicate_keys: DuplicateKeys,
+    limits: Limits,
 }
 
 impl Parser {
@@ -40,10 +62,20 @@ impl Parser {
     /// | Toggle | Description |
     /// | :---: | :--- |
     /// | [`Parser::literal_special_chars()`] | Whether to interpret `\` in strings as the start of an escaped special character, or a literal `\` |
+    /// | [`Parser::spans()`] | Whether to record where each key and value came from in the text |
+    /// | [`Parser::lossy()`] | Whether [`Parser::parse_bytes()`] replaces invalid text or errors on it |
+    /// | [`Parser::case_insensitive_keys()`] | Whether keys that only differ in ASCII case are merged |
+    /// | [`Parser::duplicate_keys()`] | How keys that appear more than once within an object are handled |
+    /// | [`Parser::limits()`] | The [`Limits`] on the size and shape of the text |
     pub const fn new() -> Self {
         // same as Default, but const 😏
         Self {
             literal_special_chars: false,
+            spans: false,
+            lossy: false,
+            case_insensitive_keys: false,
+            duplicate_keys: DuplicateKeys::KeepAll,
+            limits: Limits::new(),
         }
     }
 
@@ -58,6 +90,105 @@ impl Parser {
         self
     }
 
+    /// Toggle recording the [`Span`]s of the parsed nodes
+    ///
+    /// By default (`false`) no spans are recorded. When `true` the spans of the top-level pair and
+    /// directives are stored in [`PartialVdf::spans`] while the spans of the pairs within objects
+    /// can be looked up with [`Obj::span()`]
+    ///
+    /// ```
+    /// use keyvalues_parser::Parser;
+    /// let text = "Config\n{\n    LibraryPath \"/mnt/games\"\n}";
+    /// let vdf = Parser::new().spans(true).parse(text)?;
+    ///
+    /// let span = vdf.value.get_obj().unwrap().span("LibraryPath", 0).unwrap();
+    /// assert_eq!(&text[span.value.range()], "\"/mnt/games\"");
+    /// assert_eq!((span.value.start.line, span.value.start.col), (3, 17));
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub const fn spans(mut self, yes: bool) -> Self {
+        self.spans = yes;
+        self
+    }
+
+    /// Toggle how [`Parser::parse_bytes()`] handles text that's invalid in its encoding
+    ///
+    /// By default (`false`) invalid text is an error. When `true` each invalid sequence is
+    /// replaced with `U+FFFD` instead, like [`String::from_utf8_lossy()`]. Handy for files like
+    /// `loginusers.vdf` where persona names can contain just about anything
+    pub const fn lossy(mut self, yes: bool) -> Self {
+        self.lossy = yes;
+        self
+    }
+
+    /// Toggle merging keys that only differ in ASCII case
+    ///
+    /// By default (`false`) keys are case-sensitive like the [`BTreeMap`] that [`Obj`] derefs to.
+    /// When `true` keys within an object are matched ignoring ASCII case like Valve's own
+    /// implementation does, so all of their values end up under the spelling that came first in
+    /// the text. That's also the spelling that gets rendered
+    ///
+    /// ```
+    /// use keyvalues_parser::Parser;
+    /// let text = r#"AppState { InstallDir "Team Fortress 2" installdir "tf2" }"#;
+    /// let vdf = Parser::new().case_insensitive_keys(true).parse(text)?;
+    /// let obj = vdf.value.get_obj().unwrap();
+    /// assert_eq!(obj.keys().collect::<Vec<_>>(), ["InstallDir"]);
+    /// assert_eq!(obj["InstallDir"].len(), 2);
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub const fn case_insensitive_keys(mut self, yes: bool) -> Self {
+        self.case_insensitive_keys = yes;
+        self
+    }
+
+    /// Select how keys that appear more than once within the same object are handled
+    ///
+    /// By default ([`DuplicateKeys::KeepAll`]) every value is kept. Keys are compared after
+    /// [`Parser::case_insensitive_keys()`] is applied
+    ///
+    /// ```
+    /// use keyvalues_parser::{DuplicateKeys, Parser};
+    /// let text = r#"Config { Volume "80" Volume "20" }"#;
+    /// let vdf = Parser::new().duplicate_keys(DuplicateKeys::LastWins).parse(text)?;
+    /// let obj = vdf.value.get_obj().unwrap();
+    /// assert_eq!(obj["Volume"].len(), 1);
+    /// assert_eq!(obj["Volume"][0].get_str(), Some("20"));
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub const fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    /// Set the [`Limits`] on the size and shape of the text
+    ///
+    /// Going over a limit fails with a
+    /// [`ParseErrorKind::LimitExceeded`][error::ParseErrorKind::LimitExceeded] error. By default
+    /// only the nesting depth is limited (see [`Limits::new()`]) which keeps deeply nested text from
+    /// overflowing the stack. Tighten the rest when parsing untrusted text
+    ///
+    /// ```
+    /// use keyvalues_parser::{
+    ///     error::{Error, Limit, ParseErrorKind},
+    ///     Limits, Parser,
+    /// };
+    /// let limits = Limits {
+    ///     max_depth: Some(2),
+    ///     ..Limits::new()
+    /// };
+    /// let Err(Error::ParseError(err)) = Parser::new().limits(limits).parse("A { B { C { } } }") else {
+    ///     unreachable!();
+    /// };
+    /// let limit = Limit::Depth;
+    /// assert_eq!(err.kind(), ParseErrorKind::LimitExceeded { limit, max: 2 });
+    /// assert_eq!(err.col(), 11);
+    /// ```
+    pub const fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
     /// Parse a KeyValues document to a loosely typed representation
     ///
     /// # Example
@@ -71,12 +202,380 @@ impl Parser {
     /// assert_eq!(vdf.value.unwrap_str(), r"C:\You\Later");
     /// ```
     pub fn parse<'text>(&self, vdf: &'text str) -> error::Result<PartialVdf<'text>> {
-        if self.literal_special_chars {
-            #[expect(deprecated)] // deprecated for thee, but not for me!
-            text::parse::raw_parse(vdf)
-        } else {
-            #[expect(deprecated)] // deprecated for thee, but not for me!
-            text::parse::escaped_parse(vdf)
+        text::parse::parse(vdf, self)
+    }
+
+    /// Parse a KeyValues document from raw bytes
+    ///
+    /// The text can be UTF-8 or UTF-16 (like Valve's localization files) with or without a byte
+    /// order mark. The detected [`Encoding`] is stored in [`PartialVdf::encoding`] so that
+    /// [`PartialVdf::render_bytes()`] can write the document back out the same way. Any recorded
+    /// spans point into the decoded text with the byte order mark stripped
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use keyvalues_parser::{Encoding, Parser};
+    /// let bytes: Vec<u8> = b"\xFF\xFE"
+    ///     .iter()
+    ///     .copied()
+    ///     .chain("Tokens { Hello Bonjour }".encode_utf16().flat_map(u16::to_le_bytes))
+    ///     .collect();
+    /// let vdf = Parser::new().parse_bytes(&bytes)?;
+    /// assert_eq!(vdf.encoding, Encoding::Utf16Le { bom: true });
+    /// assert_eq!(vdf.value.get_obj().unwrap()["Hello"][0].get_str(), Some("Bonjour"));
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub fn parse_bytes(&self, bytes: &[u8]) -> error::Result<PartialVdf<'static>> {
+        // Checked upfront to avoid decoding something huge
+        if let Some(err) = text::parse::check_input_len(bytes.len(), self) {
+            return Err(err.into());
+        }
+        let (text, encoding) = encoding::decode(bytes, self.lossy)?;
+        let vdf = self.parse(&text)?.into_owned();
+        Ok(PartialVdf { encoding, ..vdf })
+    }
+
+    /// Parse a KeyValues document that has any number of top-level pairs
+    ///
+    /// Plenty of files like Hammer's `.vmf`s, soundscripts, and `particles_manifest.txt` have
+    /// several pairs at the top-level instead of a single one. They all get collected into the
+    /// [`RootVdf::root`] object, and an empty document is just an empty object
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use keyvalues_parser::Parser;
+    /// let text = r#"
+    /// versioninfo { editorversion 400 }
+    /// world { id 1 }
+    /// entity { id 2 }
+    /// entity { id 3 }
+    /// "#;
+    /// let vdf = Parser::new().parse_root(text)?;
+    /// assert_eq!(vdf.root.keys().collect::<Vec<_>>(), ["entity", "versioninfo", "world"]);
+    /// assert_eq!(vdf.root["entity"].len(), 2);
+    /// assert!(Parser::new().parse_root("// Nothing here yet")?.root.is_empty());
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub fn parse_root<'text>(&self, vdf: &'text str) -> error::Result<RootVdf<'text>> {
+        text::parse::parse_root(vdf, self)
+    }
+
+    /// Parse a KeyValues document while recovering from errors instead of stopping at the first
+    ///
+    /// Problems are skipped over at the nearest string, pair, or brace boundary, so you get back as
+    /// much of the document as possible along with every error that was encountered. Objects that
+    /// are never closed (like from a truncated file) keep all of the pairs before the end of the
+    /// text, and pairs after a stray closing brace get recovered into the top-level object
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use keyvalues_parser::{error::ParseErrorKind, Parser};
+    /// let text = r#"Config
+    /// {
+    ///     LibraryPath "/mnt/games"
+    /// }
+    /// }
+    ///     Volume "80"
+    /// "#;
+    /// let recovered = Parser::new().parse_lenient(text);
+    /// let vdf = recovered.vdf.unwrap();
+    /// let obj = vdf.value.get_obj().unwrap();
+    /// assert_eq!(obj["Volume"][0].get_str(), Some("80"));
+    /// assert_eq!(recovered.errors.len(), 1);
+    /// assert_eq!(recovered.errors[0].kind(), ParseErrorKind::TrailingContent);
+    /// ```
+    pub fn parse_lenient<'text>(&self, vdf: &'text str) -> Recovered<'text> {
+        text::parse::parse_lenient(vdf, self)
+    }
+
+    /// Parse a KeyValues document to a lossless [`cst::Document`] that can be edited while
+    /// preserving its formatting
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use keyvalues_parser::Parser;
+    /// let text = r#"InstallDir "C:\\Games" // Where Steam put the game"#;
+    /// let doc = Parser::new().parse_cst(text)?;
+    /// assert_eq!(doc.pair().value().get_str(), Some(r"C:\Games"));
+    /// assert_eq!(doc.to_string(), text);
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub fn parse_cst<'text>(&self, vdf: &'text str) -> error::Result<cst::Document<'text>> {
+        Ok(cst::parse::parse(vdf, self)?)
+    }
+}
+
+/// Limits on the size and shape of the text that a [`Parser`] accepts
+///
+/// Each limit is disabled with `None`. These are checked as the text is parsed, so parsing
+/// stops as soon as a limit is exceeded instead of after doing all of the work
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Limits {
+    /// The maximum number of nested objects. The top-level pair's object is at a depth of 1
+    ///
+    /// This also limits how deeply a conditional can nest where each `!`, `(`, `&&`, and `||`
+    /// adds a level
+    pub max_depth: Option<usize>,
+    /// The maximum length of the text in bytes
+    pub max_input_len: Option<usize>,
+    /// The maximum length of a single key or value in bytes as it appears in the text
+    pub max_string_len: Option<usize>,
+    /// The maximum number of pairs within the whole document including the top-level pair
+    pub max_pairs: Option<usize>,
+}
+
+impl Limits {
+    /// The default nesting depth limit which is far deeper than anything seen in real files
+    pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+    /// The default limits
+    ///
+    /// Only the depth is limited to [`Limits::DEFAULT_MAX_DEPTH`], since parsing is recursive
+    /// along with dropping, cloning, comparing, and calling `into_owned()` on the parsed values
+    pub const fn new() -> Self {
+        Self {
+            max_depth: Some(Self::DEFAULT_MAX_DEPTH),
+            max_input_len: None,
+            max_string_len: None,
+            max_pairs: None,
+        }
+    }
+
+    /// No limits at all, so this should only be used with trusted text
+    ///
+    /// Deeply nested text can overflow the stack while parsing. Rendering never recurses, but
+    /// dropping, cloning, comparing, and calling `into_owned()` on a [`Vdf`] all do, so the
+    /// nesting still has to stay bounded to use the result
+    pub const fn none() -> Self {
+        Self {
+            max_depth: None,
+            max_input_len: None,
+            max_string_len: None,
+            max_pairs: None,
+        }
+    }
+
+    // The error for when `value` goes over the max for `limit`
+    pub(crate) fn check(&self, limit: error::Limit, value: usize) -> Option<error::ParseErrorKind> {
+        let max = match limit {
+            error::Limit::Depth => self.max_depth,
+            error::Limit::InputLen => self.max_input_len,
+            error::Limit::StringLen => self.max_string_len,
+            error::Limit::Pairs => self.max_pairs,
+        };
+        max.filter(|&max| value > max)
+            .map(|max| error::ParseErrorKind::LimitExceeded { limit, max })
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// How a [`Parser`] handles a key that appears more than once within the same object
+///
+/// Selected with [`Parser::duplicate_keys()`]. Other than [`DuplicateKeys::KeepAll`] each key ends
+/// up with a single value
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DuplicateKeys {
+    /// Every value is kept in the key's `Vec<Value>`
+    #[default]
+    KeepAll,
+    /// Only the first value is kept, which matches looking a key up in Valve's implementation
+    FirstWins,
+    /// Only the last value is kept. The pair stays where the key was first used
+    LastWins,
+    /// Object values are merged recursively with any other values being replaced by the later
+    /// one. This matches Valve's `RecursiveMergeKeyValues()`
+    MergeObjs,
+    /// Each duplicate is a [`ParseErrorKind::DuplicateKey`][error::ParseErrorKind::DuplicateKey]
+    /// error pointing to both occurrences. [`Parser::parse_lenient()`] keeps the first value
+    Error,
+}
+
+/// A configurable KeyValues renderer allowing for adjusting the formatting of the text
+///
+/// Used through [`Vdf::render_with()`], [`PartialVdf::render_with()`], and
+/// [`RootVdf::render_with()`]. The default settings match the regular `Display` output
+///
+/// # Example
+///
+/// ```
+/// use keyvalues_parser::{Newline, Renderer, Vdf};
+/// let vdf = Vdf::parse(r#""Config" { "Volume" "80" "Library Path" "/mnt/games" }"#)?;
+/// let renderer = Renderer::new()
+///     .indent("    ")
+///     .quote_only_when_needed(true)
+///     .newline(Newline::CrLf);
+/// let mut text = String::new();
+/// vdf.render_with(&mut text, &renderer)?;
+/// assert_eq!(
+///     text,
+///     "Config\r\n{\r\n    Volume\t80\r\n    \"Library Path\"\t/mnt/games\r\n}\r\n",
+/// );
+/// # Ok::<(), keyvalues_parser::error::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Renderer {
+    literal_special_chars: bool,
+    indent: Cow<'static, str>,
+    separator: Separator,
+    quote_only_when_needed: bool,
+    newline: Newline,
+    minified: bool,
+}
+
+impl Renderer {
+    /// Constructs a default renderer
+    ///
+    /// Currently this consists of:
+    ///
+    /// | Toggle | Default | Description |
+    /// | :---: | :---: | :--- |
+    /// | [`Renderer::literal_special_chars()`] | `false` | Whether special characters in strings are written as-is instead of escaped |
+    /// | [`Renderer::indent()`] | `"\t"` | The string used for each level of indentation |
+    /// | [`Renderer::separator()`] | [`Separator::Str`] of `"\t"` | What goes between a key and its value |
+    /// | [`Renderer::quote_only_when_needed()`] | `false` | Whether strings that can go without quotes are left unquoted |
+    /// | [`Renderer::newline()`] | [`Newline::Lf`] | How lines are ended |
+    /// | [`Renderer::minified()`] | `false` | Whether everything is written on a single line |
+    pub const fn new() -> Self {
+        Self {
+            literal_special_chars: false,
+            indent: Cow::Borrowed("\t"),
+            separator: Separator::Str(Cow::Borrowed("\t")),
+            quote_only_when_needed: false,
+            newline: Newline::Lf,
+            minified: false,
+        }
+    }
+
+    /// Toggle escaping special characters in strings
+    ///
+    /// By default (`false`) special characters are escaped (e.g. `"` -> `\"`, `\` -> `\\`). When
+    /// `true` strings are written as-is to be read back with
+    /// [`Parser::literal_special_chars()`], and rendering fails with an
+    /// [`Error::RawRenderError`][error::Error::RawRenderError] for any string containing a `"`
+    pub const fn literal_special_chars(mut self, yes: bool) -> Self {
+        self.literal_special_chars = yes;
+        self
+    }
+
+    /// Set the string used for each level of indentation
+    ///
+    /// Defaults to a single tab
+    pub fn indent(mut self, indent: impl Into<Cow<'static, str>>) -> Self {
+        self.indent = indent.into();
+        self
+    }
+
+    /// Set what goes between a key and its value
+    ///
+    /// Defaults to a single tab. Steam itself writes two tabs
+    ///
+    /// ```
+    /// use keyvalues_parser::{Renderer, Separator, Vdf};
+    /// let vdf = Vdf::parse(r#"Config { Volume 80 InstallDir "C:\\Games" }"#)?;
+    /// let renderer = Renderer::new().separator(Separator::Aligned);
+    /// let mut text = String::new();
+    /// vdf.render_with(&mut text, &renderer)?;
+    /// assert_eq!(
+    ///     text,
+    ///     "\"Config\"\n{\n\t\"Volume\"     \"80\"\n\t\"InstallDir\" \"C:\\\\Games\"\n}\n",
+    /// );
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub fn separator(mut self, separator: Separator) -> Self {
+        self.separator = separator;
+        self
+    }
+
+    /// Toggle leaving strings unquoted when they can be
+    ///
+    /// By default (`false`) every key and value is quoted. When `true` strings are only quoted when
+    /// they would be read back differently otherwise, like when they're empty or contain
+    /// whitespace, braces, or characters that need escaping
+    pub const fn quote_only_when_needed(mut self, yes: bool) -> Self {
+        self.quote_only_when_needed = yes;
+        self
+    }
+
+    /// Set how lines are ended
+    ///
+    /// Defaults to [`Newline::Lf`]
+    pub const fn newline(mut self, newline: Newline) -> Self {
+        self.newline = newline;
+        self
+    }
+
+    /// Toggle writing everything on a single line
+    ///
+    /// By default (`false`) each pair gets its own indented line. When `true` the indent,
+    /// separator, and newline settings are ignored and tokens are separated by single spaces
+    /// instead
+    ///
+    /// ```
+    /// use keyvalues_parser::{Renderer, Vdf};
+    /// let vdf = Vdf::parse("Config { Volume 80 Audio { Muted 1 } }")?;
+    /// let renderer = Renderer::new().minified(true).quote_only_when_needed(true);
+    /// let mut text = String::new();
+    /// vdf.render_with(&mut text, &renderer)?;
+    /// assert_eq!(text, "Config { Volume 80 Audio { Muted 1 } }");
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub const fn minified(mut self, yes: bool) -> Self {
+        self.minified = yes;
+        self
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What a [`Renderer`] writes between a key and its value
+///
+/// Selected with [`Renderer::separator()`]. Only pairs with string values have a separator since
+/// objects always start on the next line
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Separator {
+    /// The same string between every key and its value
+    Str(Cow<'static, str>),
+    /// The keys within each object are padded with spaces so that their values line up
+    Aligned,
+}
+
+impl Default for Separator {
+    fn default() -> Self {
+        Self::Str(Cow::Borrowed("\t"))
+    }
+}
+
+/// How a [`Renderer`] ends each line
+///
+/// Selected with [`Renderer::newline()`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Newline {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n` as expected by plenty of Windows tools
+    CrLf,
+}
+
+impl Newline {
+    /// The line ending itself
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
         }
     }
 }
@@ -122,9 +621,7 @@ pub type Key<'text> = Cow<'text, str>;
 ///     .value
 ///     .get_mut_obj()
 ///     .unwrap()
-///     .get_mut("Inner Key")
-///     .unwrap()
-///     .pop();
+///     .remove_value("Inner Key", 1);
 ///
 /// // Render: prints
 /// // "Outer Key"
@@ -138,6 +635,8 @@ pub type Key<'text> = Cow<'text, str>;
 pub struct Vdf<'text> {
     pub key: Key<'text>,
     pub value: Value<'text>,
+    /// An optional conditional tag for the pair e.g. `[$WIN32]`
+    pub conditional: Option<Conditional<'text>>,
 }
 
 impl<'text> Vdf<'text> {
@@ -153,7 +652,60 @@ impl<'text> Vdf<'text> {
     /// println!("{}", vdf);
     /// ```
     pub fn new(key: Key<'text>, value: Value<'text>) -> Self {
-        Self { key, value }
+        Self {
+            key,
+            value,
+            conditional: None,
+        }
+    }
+
+    /// Converts the [`Vdf`] to one that owns all of its data
+    pub fn into_owned(self) -> Vdf<'static> {
+        Vdf {
+            key: Cow::Owned(self.key.into_owned()),
+            value: self.value.into_owned(),
+            conditional: self.conditional.map(Conditional::into_owned),
+        }
+    }
+
+    /// Removes all pairs whose conditional doesn't hold for the `defined` symbols
+    ///
+    /// Pairs that are kept have their conditional cleared since it has been resolved. `None` is
+    /// returned if the top-level pair itself is excluded
+    ///
+    /// ```
+    /// let vdf_text = r#"
+    /// "Panel"
+    /// {
+    ///     "wide"  "100"   [$WIN32]
+    ///     "wide"  "120"   [!$WIN32]
+    ///     "Extra" [$X360]
+    ///     {
+    ///     }
+    /// }
+    /// "#;
+    /// let vdf = keyvalues_parser::Vdf::parse(vdf_text)?;
+    /// let linux = vdf.resolve_conditionals(&["LINUX", "POSIX"]).unwrap();
+    /// let panel = linux.value.unwrap_obj();
+    /// assert_eq!(panel.get("wide").unwrap()[0].get_str(), Some("120"));
+    /// assert_eq!(panel.get("wide").unwrap().len(), 1);
+    /// assert!(panel.get("Extra").is_none());
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub fn resolve_conditionals(self, defined: &[&str]) -> Option<Self> {
+        let Self {
+            key,
+            mut value,
+            conditional,
+        } = self;
+        if conditional.map_or(true, |cond| cond.evaluate(defined)) {
+            if let Value::Obj(obj) = &mut value {
+                obj.resolve_conditionals(defined);
+            }
+            Some(Self::new(key, value))
+        } else {
+            None
+        }
     }
 }
 
@@ -162,6 +714,7 @@ impl<'text> From<PartialVdf<'text>> for Vdf<'text> {
         Self {
             key: partial.key,
             value: partial.value,
+            conditional: partial.conditional,
         }
     }
 }
@@ -171,15 +724,125 @@ impl<'text> From<PartialVdf<'text>> for Vdf<'text> {
 pub struct PartialVdf<'text> {
     pub key: Key<'text>,
     pub value: Value<'text>,
+    pub conditional: Option<Conditional<'text>>,
+    pub bases: Vec<Cow<'text, str>>,
+    pub includes: Vec<Include<'text>>,
+    /// Where the top-level nodes came from when parsed with [`Parser::spans()`]
+    pub spans: Option<DocumentSpans>,
+    /// The encoding of the text's bytes when parsed with [`Parser::parse_bytes()`]
+    pub encoding: Encoding,
+}
+
+impl PartialVdf<'_> {
+    /// Converts the [`PartialVdf`] to one that owns all of its data
+    pub fn into_owned(self) -> PartialVdf<'static> {
+        PartialVdf {
+            key: Cow::Owned(self.key.into_owned()),
+            value: self.value.into_owned(),
+            conditional: self.conditional.map(Conditional::into_owned),
+            bases: self
+                .bases
+                .into_iter()
+                .map(|base| Cow::Owned(base.into_owned()))
+                .collect(),
+            includes: self
+                .includes
+                .into_iter()
+                .map(|include| Include {
+                    path: Cow::Owned(include.path.into_owned()),
+                    position: include.position,
+                })
+                .collect(),
+            spans: self.spans,
+            encoding: self.encoding,
+        }
+    }
+}
+
+/// A document with any number of top-level pairs
+///
+/// Created with [`Parser::parse_root()`] and rendered back through its `Display` implementation
+/// or [`RootVdf::render()`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RootVdf<'text> {
     pub bases: Vec<Cow<'text, str>>,
+    pub includes: Vec<Include<'text>>,
+    /// All of the top-level pairs
+    pub root: Obj<'text>,
+    /// Where the directives came from when parsed with [`Parser::spans()`]
+    pub spans: Option<RootSpans>,
+}
+
+impl<'text> From<PartialVdf<'text>> for RootVdf<'text> {
+    fn from(partial: PartialVdf<'text>) -> Self {
+        let mut root = Obj::new();
+        if let Some(spans) = &partial.spans {
+            root.push_span(partial.key.clone(), spans.pair);
+        }
+        root.push(partial.key, partial.value, partial.conditional);
+        Self {
+            bases: partial.bases,
+            includes: partial.includes,
+            root,
+            spans: partial.spans.map(|spans| RootSpans {
+                bases: spans.bases,
+                includes: spans.includes,
+            }),
+        }
+    }
+}
+
+/// An `#include` directive from the start of a document
+///
+/// Unlike `#base` which provides fallback values that are merged in underneath the document, an
+/// included file's pairs are appended to the document
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Include<'text> {
+    pub path: Cow<'text, str>,
+    /// The index of the directive among all of the `#base` and `#include` directives. This is used
+    /// to render the directives back out in their original order
+    pub position: usize,
 }
 
 // TODO: why is this type alias a thing if it's not private but the usage of it inside `Obj` is?
 type ObjInner<'text> = BTreeMap<Key<'text>, Vec<Value<'text>>>;
 type ObjInnerPair<'text> = (Key<'text>, Vec<Value<'text>>);
 
-#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Obj<'text>(pub ObjInner<'text>);
+type Conditionals<'text> = BTreeMap<Key<'text>, Vec<Option<Conditional<'text>>>>;
+type Spans<'text> = BTreeMap<Key<'text>, Vec<PairSpan>>;
+pub(crate) type ObjPair<'text> = (
+    Key<'text>,
+    Value<'text>,
+    Option<Conditional<'text>>,
+    Option<PairSpan>,
+);
+
+/// An object of key-value pairs
+///
+/// This derefs to the inner [`BTreeMap`] which maps each [`Key`] to its [`Value`]s for reading.
+/// Any [`Conditional`]s on the pairs are tracked alongside the map by the key and the value's
+/// index within that key's values, so adding or removing values goes through methods like
+/// [`Obj::push()`], [`Obj::insert()`], [`Obj::remove()`], and [`Obj::remove_value()`] that keep
+/// everything in sync. Existing values can be changed in place with [`Obj::get_mut()`]
+///
+/// The order that pairs were added in is also tracked, so that the original document order of
+/// every pair (including duplicate keys that are interleaved with other keys) is kept when
+/// rendering or iterating with [`Obj::ordered_pairs()`] and [`Obj::into_vdfs()`]. The order isn't
+/// considered when comparing objects
+///
+/// Objects parsed with [`Parser::spans()`] also track the [`PairSpan`] of each pair in the same way
+/// as conditionals. Spans aren't considered when comparing objects either
+///
+/// An object can be built from an existing map with [`Obj::from()`] or by collecting `(Key,
+/// Vec<Value>)` pairs
+#[derive(Clone, Default)]
+pub struct Obj<'text> {
+    inner: ObjInner<'text>,
+    conditionals: Conditionals<'text>,
+    // Each occurrence of a key refers to the next value for that key
+    order: Vec<Key<'text>>,
+    spans: Spans<'text>,
+}
 
 impl<'text> Obj<'text> {
     /// Creates an empty object value
@@ -225,7 +888,405 @@ impl<'text> Obj<'text> {
     /// println!("{:#?}", inner);
     /// ```
     pub fn into_inner(self) -> ObjInner<'text> {
-        self.0
+        self.inner
+    }
+
+    /// Converts the object to one that owns all of its data
+    pub fn into_owned(self) -> Obj<'static> {
+        let owned_key = |key: Key<'_>| -> Key<'static> { Cow::Owned(key.into_owned()) };
+        Obj {
+            inner: self
+                .inner
+                .into_iter()
+                .map(|(key, values)| {
+                    let values = values.into_iter().map(Value::into_owned).collect();
+                    (owned_key(key), values)
+                })
+                .collect(),
+            conditionals: self
+                .conditionals
+                .into_iter()
+                .map(|(key, conds)| {
+                    let conds = conds
+                        .into_iter()
+                        .map(|cond| cond.map(Conditional::into_owned))
+                        .collect();
+                    (owned_key(key), conds)
+                })
+                .collect(),
+            order: self.order.into_iter().map(owned_key).collect(),
+            spans: self
+                .spans
+                .into_iter()
+                .map(|(key, spans)| (owned_key(key), spans))
+                .collect(),
+        }
+    }
+
+    /// Returns the values for `key` mutably
+    ///
+    /// The values can be changed in place, but adding or removing values has to go through the
+    /// object so that conditionals, spans, and the order stay in sync
+    ///
+    /// ```
+    /// # use keyvalues_parser::Value;
+    /// # use std::borrow::Cow;
+    /// let mut vdf = keyvalues_parser::parse(r#"AppState { InstallDir "Team Fortress 2" }"#)?;
+    /// let obj = vdf.value.get_mut_obj().unwrap();
+    /// obj.get_mut("InstallDir").unwrap()[0] = Value::Str(Cow::from("tf2"));
+    /// assert_eq!(obj["InstallDir"][0].get_str(), Some("tf2"));
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut [Value<'text>]> {
+        self.inner.get_mut(key).map(Vec::as_mut_slice)
+    }
+
+    /// Returns an iterator over each key along with its values that can be changed in place
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Key<'text>, &mut [Value<'text>])> {
+        self.inner
+            .iter_mut()
+            .map(|(key, values)| (key, values.as_mut_slice()))
+    }
+
+    /// Replaces all of the values for `key` returning the old ones if there were any
+    ///
+    /// The old values are removed along with their conditionals and spans, and the new values come
+    /// after all of the other pairs in the order
+    pub fn insert(
+        &mut self,
+        key: Key<'text>,
+        values: Vec<Value<'text>>,
+    ) -> Option<Vec<Value<'text>>> {
+        let old = self.remove(&key);
+        self.order
+            .extend(std::iter::repeat(key.clone()).take(values.len()));
+        self.inner.insert(key, values);
+        old
+    }
+
+    /// Removes all of the values for `key` returning them if there were any
+    pub fn remove(&mut self, key: &str) -> Option<Vec<Value<'text>>> {
+        let values = self.inner.remove(key)?;
+        self.conditionals.remove(key);
+        self.spans.remove(key);
+        self.order.retain(|k| k != key);
+        Some(values)
+    }
+
+    /// Removes the `index`th value of `key` returning it if there was one
+    ///
+    /// The conditionals and spans of any later values for `key` shift down to match. A key that's
+    /// left without any values is removed entirely
+    ///
+    /// ```
+    /// let text = r#"Outer { a "1" [$WIN32] a "2" }"#;
+    /// let mut obj = keyvalues_parser::parse(text)?.value.unwrap_obj();
+    /// assert_eq!(obj.remove_value("a", 0).unwrap().get_str(), Some("1"));
+    /// assert_eq!(obj["a"][0].get_str(), Some("2"));
+    /// assert_eq!(obj.conditional("a", 0), None);
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub fn remove_value(&mut self, key: &str, index: usize) -> Option<Value<'text>> {
+        let values = self.inner.get_mut(key)?;
+        if index >= values.len() {
+            return None;
+        }
+        let value = values.remove(index);
+        if values.is_empty() {
+            self.inner.remove(key);
+        }
+
+        if let Some(conds) = self.conditionals.get_mut(key) {
+            if index < conds.len() {
+                conds.remove(index);
+            }
+            if conds.iter().all(Option::is_none) {
+                self.conditionals.remove(key);
+            }
+        }
+        if let Some(spans) = self.spans.get_mut(key) {
+            if index < spans.len() {
+                spans.remove(index);
+            }
+            if spans.is_empty() {
+                self.spans.remove(key);
+            }
+        }
+        let pos = self
+            .order
+            .iter()
+            .enumerate()
+            .filter(|(_, k)| *k == key)
+            .nth(index)
+            .map(|(pos, _)| pos);
+        if let Some(pos) = pos {
+            self.order.remove(pos);
+        }
+
+        Some(value)
+    }
+
+    /// Returns the key in the object that matches `key` ignoring ASCII case
+    ///
+    /// An exact match is preferred when there are multiple keys that only differ in case,
+    /// otherwise it's the first one in the map's order. The returned key can be used to look up
+    /// things like [`Obj::conditional()`] and [`Obj::span()`] with the stored spelling
+    pub fn key_ci(&self, key: &str) -> Option<&Key<'text>> {
+        match self.inner.get_key_value(key) {
+            Some((key, _)) => Some(key),
+            None => self.inner.keys().find(|k| k.eq_ignore_ascii_case(key)),
+        }
+    }
+
+    /// Returns the values for `key` matching it ignoring ASCII case
+    ///
+    /// Which key gets picked when multiple match is the same as [`Obj::key_ci()`]
+    ///
+    /// ```
+    /// let vdf = keyvalues_parser::parse(r#"AppState { InstallDir "Team Fortress 2" }"#)?;
+    /// let obj = vdf.value.unwrap_obj();
+    /// assert_eq!(obj.get("installdir"), None);
+    /// assert_eq!(obj.get_ci("installdir").unwrap()[0].get_str(), Some("Team Fortress 2"));
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub fn get_ci(&self, key: &str) -> Option<&Vec<Value<'text>>> {
+        self.inner.get(self.key_ci(key)?)
+    }
+
+    /// Returns the values for `key` mutably matching it ignoring ASCII case
+    ///
+    /// Which key gets picked when multiple match is the same as [`Obj::key_ci()`]
+    pub fn get_mut_ci(&mut self, key: &str) -> Option<&mut [Value<'text>]> {
+        let key = self.key_ci(key)?.clone();
+        self.get_mut(&key)
+    }
+
+    /// Appends a value like [`Obj::push()`] matching `key` ignoring ASCII case
+    ///
+    /// An existing key keeps its spelling while a new one uses `key`'s
+    ///
+    /// ```
+    /// # use keyvalues_parser::Value;
+    /// # use std::borrow::Cow;
+    /// let mut vdf = keyvalues_parser::parse(r#"AppState { InstallDir "Team Fortress 2" }"#)?;
+    /// let obj = vdf.value.get_mut_obj().unwrap();
+    /// obj.push_ci(Cow::from("INSTALLDIR"), Value::Str(Cow::from("tf2")), None);
+    /// assert_eq!(obj.keys().collect::<Vec<_>>(), ["InstallDir"]);
+    /// assert_eq!(obj["InstallDir"].len(), 2);
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub fn push_ci(
+        &mut self,
+        key: Key<'text>,
+        value: Value<'text>,
+        conditional: Option<Conditional<'text>>,
+    ) {
+        let key = self.key_ci(&key).cloned().unwrap_or(key);
+        self.push(key, value, conditional);
+    }
+
+    /// Returns the [`Conditional`] for the `index`th value of `key` if there is one
+    ///
+    /// ```
+    /// # use keyvalues_parser::Conditional;
+    /// let vdf = keyvalues_parser::parse(r#"Outer { Inner "Windows" [$WIN32] Inner "Other" }"#)?;
+    /// let obj = vdf.value.unwrap_obj();
+    /// assert_eq!(obj.conditional("Inner", 0), Some(&Conditional::symbol("WIN32")));
+    /// assert_eq!(obj.conditional("Inner", 1), None);
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub fn conditional(&self, key: &str, index: usize) -> Option<&Conditional<'text>> {
+        self.conditionals.get(key)?.get(index)?.as_ref()
+    }
+
+    /// Returns where the `index`th value of `key` came from if the object was parsed with
+    /// [`Parser::spans()`]
+    pub fn span(&self, key: &str, index: usize) -> Option<&PairSpan> {
+        self.spans.get(key)?.get(index)
+    }
+
+    // The parsers push a span for each pair right after pushing the pair
+    pub(crate) fn push_span(&mut self, key: Key<'text>, span: PairSpan) {
+        self.spans.entry(key).or_default().push(span);
+    }
+
+    // Replaces the first value of `key` along with its conditional and span while keeping its
+    // place in the order
+    pub(crate) fn replace_first(
+        &mut self,
+        key: Key<'text>,
+        value: Value<'text>,
+        conditional: Option<Conditional<'text>>,
+        span: Option<PairSpan>,
+    ) {
+        if let Some(first) = self
+            .inner
+            .get_mut(&key)
+            .and_then(|values| values.first_mut())
+        {
+            *first = value;
+        }
+        if let (Some(first), Some(span)) = (
+            self.spans.get_mut(&key).and_then(|spans| spans.first_mut()),
+            span,
+        ) {
+            *first = span;
+        }
+        self.set_conditional(key, 0, conditional);
+    }
+
+    // Breaks the object back down into its pairs in order
+    pub(crate) fn into_pairs(self) -> Vec<ObjPair<'text>> {
+        let indices: Vec<_> = self
+            .ordered_pairs()
+            .into_iter()
+            .map(|(key, index, _)| (key.clone(), index))
+            .collect();
+        let Self {
+            inner,
+            mut conditionals,
+            spans,
+            ..
+        } = self;
+        let mut values: BTreeMap<_, Vec<_>> = inner
+            .into_iter()
+            .map(|(key, values)| (key, values.into_iter().map(Some).collect()))
+            .collect();
+
+        indices
+            .into_iter()
+            .map(|(key, index)| {
+                let value = values.get_mut(&key).unwrap()[index].take().unwrap();
+                let cond = conditionals
+                    .get_mut(&key)
+                    .and_then(|conds| conds.get_mut(index)?.take());
+                let span = spans.get(&key).and_then(|spans| spans.get(index)).copied();
+                (key, value, cond, span)
+            })
+            .collect()
+    }
+
+    /// Sets (or clears with `None`) the [`Conditional`] for the `index`th value of `key`
+    ///
+    /// Returns the previous conditional if there was one
+    pub fn set_conditional(
+        &mut self,
+        key: Key<'text>,
+        index: usize,
+        conditional: Option<Conditional<'text>>,
+    ) -> Option<Conditional<'text>> {
+        match conditional {
+            Some(cond) => {
+                let conds = self.conditionals.entry(key).or_default();
+                if conds.len() <= index {
+                    conds.resize(index + 1, None);
+                }
+                conds[index].replace(cond)
+            }
+            None => {
+                let conds = self.conditionals.get_mut(&key)?;
+                let prev = conds.get_mut(index)?.take();
+                if conds.iter().all(Option::is_none) {
+                    self.conditionals.remove(&key);
+                }
+                prev
+            }
+        }
+    }
+
+    /// Appends a value for `key` along with its (optional) [`Conditional`]
+    pub fn push(
+        &mut self,
+        key: Key<'text>,
+        value: Value<'text>,
+        conditional: Option<Conditional<'text>>,
+    ) {
+        self.order.push(key.clone());
+        match conditional {
+            Some(cond) => {
+                let values = self.inner.entry(key.clone()).or_default();
+                values.push(value);
+                let index = values.len() - 1;
+                self.set_conditional(key, index, Some(cond));
+            }
+            None => self.inner.entry(key).or_default().push(value),
+        }
+    }
+
+    /// Returns all of the pairs in the order they were added along with each value's index
+    /// within its key's values
+    ///
+    /// ```
+    /// let vdf = keyvalues_parser::parse(r#"SearchPaths { Game hl2 Mod mod Game base }"#)?;
+    /// let obj = vdf.value.unwrap_obj();
+    /// let pairs: Vec<_> = obj
+    ///     .ordered_pairs()
+    ///     .into_iter()
+    ///     .map(|(key, index, value)| (key.as_ref(), index, value.get_str().unwrap()))
+    ///     .collect();
+    /// assert_eq!(pairs, [("Game", 0, "hl2"), ("Mod", 0, "mod"), ("Game", 1, "base")]);
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub fn ordered_pairs(&self) -> Vec<(&Key<'text>, usize, &Value<'text>)> {
+        let mut seen: BTreeMap<&str, usize> = BTreeMap::new();
+        self.order
+            .iter()
+            .map(|key| {
+                let index = seen.entry(key).or_default();
+                let (key, values) = self
+                    .inner
+                    .get_key_value(key)
+                    .expect("The order only holds keys in the map");
+                *index += 1;
+                (key, *index - 1, &values[*index - 1])
+            })
+            .collect()
+    }
+
+    /// Removes all pairs whose conditional doesn't hold for the `defined` symbols
+    ///
+    /// This applies recursively to all nested objects and clears the conditionals of all remaining
+    /// pairs. Keys that are left without any values are removed entirely
+    pub fn resolve_conditionals(&mut self, defined: &[&str]) {
+        let conditionals = std::mem::take(&mut self.conditionals);
+        let is_kept = |key: &str, index: usize| match conditionals.get(key).map(|c| c.get(index)) {
+            Some(Some(Some(cond))) => cond.evaluate(defined),
+            _ => true,
+        };
+
+        // Drop the order entries for removed pairs first while the indices still line up
+        let mut seen: BTreeMap<Key<'text>, usize> = BTreeMap::new();
+        self.order.retain(|key| {
+            let index = seen.entry(key.clone()).or_default();
+            *index += 1;
+            is_kept(key, *index - 1)
+        });
+
+        self.spans.retain(|key, spans| {
+            let mut index = 0;
+            spans.retain(|_| {
+                index += 1;
+                is_kept(key, index - 1)
+            });
+            !spans.is_empty()
+        });
+
+        self.inner.retain(|key, values| {
+            let mut index = 0;
+            values.retain(|_| {
+                index += 1;
+                is_kept(key, index - 1)
+            });
+            !values.is_empty()
+        });
+
+        for values in self.inner.values_mut() {
+            for value in values {
+                if let Value::Obj(obj) = value {
+                    obj.resolve_conditionals(defined);
+                }
+            }
+        }
     }
 
     /// Creates an iterator that returns the [`Vdf`]s that compose the object
@@ -233,7 +1294,8 @@ impl<'text> Obj<'text> {
     /// This is notably different compared to just iterating over the `BTreeMap`s items because it
     /// will emit a [`Vdf`] for each key-value pair while the actual items are key-values pairs.
     /// This means that empty values will not emit a [`Vdf`] at all, and a pair that has multiple
-    /// entries in values will emit a [`Vdf`] for each pairing
+    /// entries in values will emit a [`Vdf`] for each pairing. The [`Vdf`]s are emitted in the same
+    /// order as [`Obj::ordered_pairs()`]
     ///
     /// ```
     /// # use keyvalues_parser::{Obj, Value, Vdf};
@@ -252,14 +1314,8 @@ impl<'text> Obj<'text> {
     /// assert_eq!(
     ///     vdfs,
     ///     [
-    ///         Vdf {
-    ///             key: Cow::from("multiple values"),
-    ///             value: Value::Str(Cow::from("first"))
-    ///         },
-    ///         Vdf {
-    ///             key: Cow::from("multiple values"),
-    ///             value: Value::Str(Cow::from("second"))
-    ///         },
+    ///         Vdf::new(Cow::from("multiple values"), Value::Str(Cow::from("first"))),
+    ///         Vdf::new(Cow::from("multiple values"), Value::Str(Cow::from("second"))),
     ///     ]
     /// );
     /// ```
@@ -275,21 +1331,72 @@ impl<'text> FromIterator<ObjInnerPair<'text>> for Obj<'text> {
             inner.insert(key, values);
         }
 
-        Self(inner)
+        Self::from(inner)
     }
 }
 
-impl<'text> Deref for Obj<'text> {
-    type Target = ObjInner<'text>;
+// Conditionals are rare, so only include them when there are any to keep things succinct
+impl fmt::Debug for Obj<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tuple = f.debug_tuple("Obj");
+        tuple.field(&self.inner);
+        if !self.conditionals.is_empty() {
+            tuple.field(&self.conditionals);
+        }
+        tuple.finish()
+    }
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+// Without any other order to go off of the pairs are in the map's order
+impl<'text> From<ObjInner<'text>> for Obj<'text> {
+    fn from(inner: ObjInner<'text>) -> Self {
+        let order = inner
+            .iter()
+            .flat_map(|(key, values)| std::iter::repeat(key.clone()).take(values.len()))
+            .collect();
+        Self {
+            inner,
+            conditionals: BTreeMap::new(),
+            order,
+            spans: BTreeMap::new(),
+        }
+    }
+}
+
+impl PartialEq for Obj<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner && self.conditionals == other.conditionals
+    }
+}
+
+impl Eq for Obj<'_> {}
+
+impl PartialOrd for Obj<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-impl DerefMut for Obj<'_> {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+impl Ord for Obj<'_> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.inner
+            .cmp(&other.inner)
+            .then_with(|| self.conditionals.cmp(&other.conditionals))
+    }
+}
+
+impl Hash for Obj<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.hash(state);
+        self.conditionals.hash(state);
+    }
+}
+
+impl<'text> Deref for Obj<'text> {
+    type Target = ObjInner<'text>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
     }
 }
 
@@ -297,16 +1404,23 @@ impl DerefMut for Obj<'_> {
 ///
 /// Typically created by calling [`Obj::into_vdfs`] on an existing object
 pub struct IntoVdfs<'text> {
-    // TODO: can this just store an iterator for the values instead of `.collect()`ing
-    current_entry: Option<ObjInnerPair<'text>>,
-    it: IntoIter<Key<'text>, Vec<Value<'text>>>,
+    it: vec::IntoIter<Vdf<'text>>,
 }
 
 impl<'text> IntoVdfs<'text> {
     fn new(obj: Obj<'text>) -> Self {
+        let vdfs: Vec<_> = obj
+            .into_pairs()
+            .into_iter()
+            .map(|(key, value, conditional, _)| Vdf {
+                key,
+                value,
+                conditional,
+            })
+            .collect();
+
         Self {
-            current_entry: None,
-            it: obj.into_inner().into_iter(),
+            it: vdfs.into_iter(),
         }
     }
 }
@@ -315,23 +1429,7 @@ impl<'text> Iterator for IntoVdfs<'text> {
     type Item = Vdf<'text>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        // Iteration will pop the first pair off `current_entry` if it's set and then falls back to
-        // reading in a new `current_entry` from `it`. If `it` is exhausted then we're done
-        loop {
-            match self.current_entry.take() {
-                // There is a pair to return
-                Some((key, mut values)) if !values.is_empty() => {
-                    let value = values.pop().expect("values isn't empty");
-                    self.current_entry = Some((key.clone(), values));
-                    return Some(Vdf::new(key, value));
-                }
-                _ => {
-                    let (key, values) = self.it.next()?;
-                    // Store the next entry. Flip the values so that `pop`ing returns correct order
-                    self.current_entry = Some((key, values.into_iter().rev().collect()));
-                }
-            }
-        }
+        self.it.next()
     }
 }
 
@@ -340,18 +1438,21 @@ impl<'text> Iterator for IntoVdfs<'text> {
 ///
 /// VDF is composed of [`Key`]s and their respective [`Value`]s where this represents the latter. A
 /// value is either going to be a `Str(Cow<str>)`, or an `Obj(Obj)` that contains a list of keys
-/// and values.
+/// and values. Binary KeyValues can also contain `Typed(TypedValue)` leaves that keep track of
+/// their wire type (ints, floats, colors, etc.)
 ///
 /// ```
-/// # use keyvalues_parser::{Obj, Value};
+/// # use keyvalues_parser::{Obj, TypedValue, Value};
 /// # use std::borrow::Cow;
 /// let value_str = Value::Str(Cow::from("some text"));
 /// let value_obj = Value::Obj(Obj::new());
+/// let value_int = Value::Typed(TypedValue::Int(42));
 /// ```
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Value<'text> {
     Str(Cow<'text, str>),
     Obj(Obj<'text>),
+    Typed(TypedValue<'text>),
 }
 
 impl<'text> Value<'text> {
@@ -380,6 +1481,18 @@ impl<'text> Value<'text> {
         self.get_obj().is_some()
     }
 
+    /// Returns if the current value is the `Typed` variant
+    ///
+    /// ```
+    /// use keyvalues_parser::{TypedValue, Value};
+    ///
+    /// let value_typed = Value::Typed(TypedValue::Float(1.5));
+    /// assert!(value_typed.is_typed());
+    /// ```
+    pub fn is_typed(&self) -> bool {
+        self.get_typed().is_some()
+    }
+
     /// Gets the inner `&str` if this is a `Value::Str`
     ///
     /// ```
@@ -417,6 +1530,24 @@ impl<'text> Value<'text> {
         }
     }
 
+    /// Gets the inner `&TypedValue` if this value is a `Value::Typed`
+    ///
+    /// ```
+    /// # use keyvalues_parser::{TypedValue, Value};
+    /// let value = Value::Typed(TypedValue::UInt64(76561197960287930));
+    ///
+    /// if let Some(TypedValue::UInt64(steam_id)) = value.get_typed() {
+    ///     println!("steam id: {}", steam_id);
+    /// }
+    /// ```
+    pub fn get_typed(&self) -> Option<&TypedValue<'text>> {
+        if let Self::Typed(typed) = self {
+            Some(typed)
+        } else {
+            None
+        }
+    }
+
     /// Gets the inner `&mut str` if this is a `Value::Str`
     ///
     /// ```
@@ -462,11 +1593,20 @@ impl<'text> Value<'text> {
         }
     }
 
+    /// Gets the inner `&mut TypedValue` if this is a `Value::Typed`
+    pub fn get_mut_typed(&mut self) -> Option<&mut TypedValue<'text>> {
+        if let Self::Typed(typed) = self {
+            Some(typed)
+        } else {
+            None
+        }
+    }
+
     /// Unwraps the `Cow<str>` from the `Value::Str`
     ///
     /// # Panics
     ///
-    /// If the variant was `Value::Obj`
+    /// If the variant was not `Value::Str`
     ///
     /// # Examples
     ///
@@ -485,14 +1625,14 @@ impl<'text> Value<'text> {
     /// value.unwrap_str(); // <-- panics
     /// ```
     pub fn unwrap_str(self) -> Cow<'text, str> {
-        self.expect_str("Called `unwrap_str` on a `Value::Obj` variant")
+        self.expect_str("Called `unwrap_str` on a non-`Value::Str` variant")
     }
 
     /// Unwraps the [`Obj`] from the `Value::Obj`
     ///
     /// # Panics
     ///
-    /// If the variant was `Value::Str`
+    /// If the variant was not `Value::Obj`
     ///
     /// # Examples
     ///
@@ -511,7 +1651,7 @@ impl<'text> Value<'text> {
     /// value.unwrap_obj(); // <-- panics
     /// ```
     pub fn unwrap_obj(self) -> Obj<'text> {
-        self.expect_obj("Called `unwrap_obj` on a `Value::Str` variant")
+        self.expect_obj("Called `unwrap_obj` on a non-`Value::Obj` variant")
     }
 
     /// Refer to [Value::unwrap_str]. Same situation, but with a custom message
@@ -531,4 +1671,13 @@ impl<'text> Value<'text> {
             panic!("{}", msg)
         }
     }
+
+    /// Converts the value to one that owns all of its data
+    pub fn into_owned(self) -> Value<'static> {
+        match self {
+            Self::Str(s) => Value::Str(Cow::Owned(s.into_owned())),
+            Self::Obj(obj) => Value::Obj(obj.into_owned()),
+            Self::Typed(typed) => Value::Typed(typed.into_owned()),
+        }
+    }
 }
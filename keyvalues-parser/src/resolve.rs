@@ -0,0 +1,303 @@
+//! Resolving `#base` and `#include` directives
+//!
+//! Documents can pull in other files with `#base` and `#include` directives. A [`Resolver`]
+//! recursively loads the referenced files through a [`Loader`] and merges everything into a
+//! single [`Vdf`] following Valve's semantics
+//!
+//! - `#include`d files have their pairs appended to the document
+//! - `#base` files only fill in keys that are missing from the document. Objects that exist in
+//!   both are merged recursively
+//!
+//! The top-level keys of included and base files are ignored, so only their pairs are merged in.
+//! Paths within directives are relative to the file that contains them and can't escape the
+//! loader's root
+//!
+//! ```no_run
+//! use keyvalues_parser::resolve::Resolver;
+//!
+//! let resolved = Resolver::from_root("tf/custom/my_hud").resolve("resource/ui/hudlayout.res")?;
+//! let source = resolved.source(&[("HudHealth", 0), ("xpos", 0)]).unwrap();
+//! println!("`HudHealth.xpos` came from {}", source.display());
+//! # Ok::<(), keyvalues_parser::error::Error>(())
+//! ```
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{Error, ResolveError, ResolveErrorKind, Result},
+    Obj, Parser, PartialVdf, Value, Vdf,
+};
+
+/// Loads the contents of files that are referenced while resolving
+pub trait Loader {
+    /// Loads the file at `path` which is relative to the loader's root
+    ///
+    /// The path is already normalized, so it never contains `.` or `..` components
+    fn load(&mut self, path: &Path) -> io::Result<String>;
+}
+
+/// Loads files from the filesystem relative to a root directory
+#[derive(Clone, Debug)]
+pub struct FsLoader {
+    root: PathBuf,
+}
+
+impl FsLoader {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl Loader for FsLoader {
+    fn load(&mut self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(self.root.join(path))
+    }
+}
+
+// A path of `(key, index)`s where the index picks the value for keys with multiple values
+type KeyPath = Vec<(String, usize)>;
+// Maps each pair to the index of the file that it came from
+type Sources = BTreeMap<KeyPath, usize>;
+
+/// A fully resolved document along with where each of its pairs came from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resolved {
+    pub vdf: Vdf<'static>,
+    files: Vec<PathBuf>,
+    sources: Sources,
+}
+
+impl Resolved {
+    /// Every file that was loaded with the initial file first
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Returns the file that the pair at `path` came from
+    ///
+    /// The path is made of `(key, index)`s starting from the top-level object where the index
+    /// picks which of the key's values to follow. The top-level key itself always comes from the
+    /// initial file
+    pub fn source(&self, path: &[(&str, usize)]) -> Option<&Path> {
+        let path: KeyPath = path
+            .iter()
+            .map(|&(key, index)| (key.to_owned(), index))
+            .collect();
+        let file = self.sources.get(&path)?;
+        Some(&self.files[*file])
+    }
+}
+
+/// Resolves `#base` and `#include` directives using a [`Loader`]
+#[derive(Clone, Debug)]
+pub struct Resolver<L> {
+    loader: L,
+    parser: Parser,
+}
+
+impl Resolver<FsLoader> {
+    /// Creates a resolver that loads files from the filesystem relative to `root`
+    pub fn from_root(root: impl Into<PathBuf>) -> Self {
+        Self::new(FsLoader::new(root))
+    }
+}
+
+impl<L: Loader> Resolver<L> {
+    pub fn new(loader: L) -> Self {
+        Self {
+            loader,
+            parser: Parser::new(),
+        }
+    }
+
+    /// Sets the [`Parser`] that's used for every file
+    pub fn parser(mut self, parser: Parser) -> Self {
+        self.parser = parser;
+        self
+    }
+
+    /// Loads the file at `path` (relative to the loader's root) and resolves all of its directives
+    pub fn resolve(&mut self, path: impl AsRef<Path>) -> Result<Resolved> {
+        let path = path.as_ref();
+        let path = normalize(Path::new(""), &path.to_string_lossy())?;
+        let mut files = Vec::new();
+        let (vdf, sources) = self.resolve_file(path, &mut Vec::new(), &mut files)?;
+        Ok(Resolved {
+            vdf,
+            files,
+            sources,
+        })
+    }
+
+    fn resolve_file(
+        &mut self,
+        path: PathBuf,
+        stack: &mut Vec<PathBuf>,
+        files: &mut Vec<PathBuf>,
+    ) -> Result<(Vdf<'static>, Sources)> {
+        if let Some(start) = stack.iter().position(|p| *p == path) {
+            let mut cycle = stack[start..].to_vec();
+            cycle.push(path.clone());
+            return Err(resolve_err(path, ResolveErrorKind::Cycle(cycle)));
+        }
+
+        let text = self
+            .loader
+            .load(&path)
+            .map_err(|e| resolve_err(path.clone(), ResolveErrorKind::Load(e)))?;
+        let PartialVdf {
+            key,
+            value,
+            conditional,
+            bases,
+            includes,
+            ..
+        } = self
+            .parser
+            .parse(&text)
+            .map_err(|e| resolve_err(path.clone(), ResolveErrorKind::Parse(Box::new(e))))?;
+        let mut vdf = Vdf {
+            key,
+            value,
+            conditional,
+        }
+        .into_owned();
+
+        let file = files.len();
+        files.push(path.clone());
+        let mut sources = Sources::new();
+        record_sources(&vdf.value, &mut Vec::new(), file, &mut sources);
+
+        let dir = path.parent().unwrap_or(Path::new("")).to_owned();
+        stack.push(path);
+        for include in includes {
+            let include_path = normalize(&dir, &include.path)?;
+            let (included, included_sources) = self.resolve_file(include_path, stack, files)?;
+            if let (Value::Obj(obj), Value::Obj(included)) = (&mut vdf.value, included.value) {
+                append(obj, &included, &mut sources, included_sources);
+            }
+        }
+        for base in bases {
+            let base_path = normalize(&dir, &base)?;
+            let (base, base_sources) = self.resolve_file(base_path, stack, files)?;
+            if let (Value::Obj(obj), Value::Obj(base)) = (&mut vdf.value, base.value) {
+                let mut added = BTreeSet::new();
+                merge_base(obj, &base, &mut Vec::new(), &mut added);
+                copy_added_sources(&added, base_sources, &mut sources);
+            }
+        }
+        stack.pop();
+
+        Ok((vdf, sources))
+    }
+}
+
+fn resolve_err(path: PathBuf, kind: ResolveErrorKind) -> Error {
+    ResolveError { path, kind }.into()
+}
+
+// Joins `relative` onto `dir` while resolving `.` and `..` components. Both `/` and `\` are
+// treated as separators since Valve's files use either
+fn normalize(dir: &Path, relative: &str) -> Result<PathBuf> {
+    let escapes = || resolve_err(dir.join(relative), ResolveErrorKind::EscapesRoot);
+    if relative.starts_with(['/', '\\']) || relative.contains(':') {
+        return Err(escapes());
+    }
+
+    let mut components: Vec<_> = dir.iter().map(ToOwned::to_owned).collect();
+    for part in relative.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                components.pop().ok_or_else(escapes)?;
+            }
+            part => components.push(part.into()),
+        }
+    }
+
+    Ok(components.iter().collect())
+}
+
+fn record_sources(value: &Value<'_>, prefix: &mut KeyPath, file: usize, sources: &mut Sources) {
+    if let Value::Obj(obj) = value {
+        for (key, index, value) in obj.ordered_pairs() {
+            prefix.push((key.to_string(), index));
+            sources.insert(prefix.clone(), file);
+            record_sources(value, prefix, file, sources);
+            prefix.pop();
+        }
+    }
+}
+
+// Appends all of the pairs from `included` to the end of `obj`
+fn append(
+    obj: &mut Obj<'static>,
+    included: &Obj<'static>,
+    sources: &mut Sources,
+    included_sources: Sources,
+) {
+    // The appended values come after any existing values for the same key
+    let mut new_indices = BTreeMap::new();
+    for (key, index, value) in included.ordered_pairs() {
+        let new_index = obj.get(key).map_or(0, Vec::len);
+        new_indices.insert((key.to_string(), index), new_index);
+        let conditional = included.conditional(key, index).cloned();
+        obj.push(key.clone(), value.clone(), conditional);
+    }
+
+    for (mut path, file) in included_sources {
+        if let Some(new_index) = new_indices.get(&path[0]) {
+            path[0].1 = *new_index;
+            sources.insert(path, file);
+        }
+    }
+}
+
+// Fills in keys from `base` that are missing in `obj` recording the `(prefix, key)` for each
+// added key. Since only missing keys get added the indices of the added values match between
+// the two objects
+fn merge_base(
+    obj: &mut Obj<'static>,
+    base: &Obj<'static>,
+    prefix: &mut KeyPath,
+    added: &mut BTreeSet<(KeyPath, String)>,
+) {
+    // Missing keys are found upfront, since a key is no longer missing once its first value is added
+    let missing: BTreeSet<_> = base.keys().filter(|key| !obj.contains_key(*key)).collect();
+    for (key, index, value) in base.ordered_pairs() {
+        if missing.contains(key) {
+            added.insert((prefix.clone(), key.to_string()));
+            let conditional = base.conditional(key, index).cloned();
+            obj.push(key.clone(), value.clone(), conditional);
+            continue;
+        }
+
+        // Objects that exist in both are merged (following the first value for the key)
+        let existing = obj.get_mut(key).and_then(|values| values.first_mut());
+        if let (Some(Value::Obj(existing)), Value::Obj(base_obj), 0) = (existing, value, index) {
+            prefix.push((key.to_string(), 0));
+            merge_base(existing, base_obj, prefix, added);
+            prefix.pop();
+        }
+    }
+}
+
+fn copy_added_sources(
+    added: &BTreeSet<(KeyPath, String)>,
+    base_sources: Sources,
+    sources: &mut Sources,
+) {
+    for (path, file) in base_sources {
+        let was_added = (0..path.len()).any(|len| {
+            // Cloning isn't ideal, but the paths are short
+            added.contains(&(path[..len].to_vec(), path[len].0.clone()))
+        });
+        if was_added {
+            sources.insert(path, file);
+        }
+    }
+}
@@ -40,7 +40,7 @@ impl<'a> Visitor<'a> for ValueVisitor {
         while let Some((key, value)) = visitor.next_entry()? {
             obj.insert(key, value);
         }
-        Ok(Value::Obj(crate::Obj(obj)))
+        Ok(Value::Obj(Obj::from(obj)))
     }
 }
 
@@ -70,7 +70,7 @@ impl<'a> Visitor<'a> for ObjVisitor {
         while let Some((key, value)) = visitor.next_entry()? {
             obj.insert(key, value);
         }
-        Ok(Obj(obj))
+        Ok(Obj::from(obj))
     }
 }
 
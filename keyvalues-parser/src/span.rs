@@ -0,0 +1,105 @@
+use std::ops::Range;
+
+/// A location within the parsed text
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Position {
+    /// The byte offset into the text
+    pub offset: usize,
+    /// The 1-indexed line
+    pub line: usize,
+    /// The 1-indexed column (in `char`s)
+    pub col: usize,
+}
+
+/// The region of the parsed text that a node came from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Span {
+    pub start: Position,
+    /// The position just past the end of the node
+    pub end: Position,
+}
+
+impl Span {
+    /// The byte range of the span, which can be used to slice the parsed text
+    pub fn range(&self) -> Range<usize> {
+        self.start.offset..self.end.offset
+    }
+}
+
+/// The spans of a key-value pair
+///
+/// Strings include their surrounding quotes and objects span from their opening `{` to their
+/// closing `}`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PairSpan {
+    pub key: Span,
+    pub value: Span,
+}
+
+/// The spans of everything at the top-level of a document
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DocumentSpans {
+    pub pair: PairSpan,
+    /// The span of each `#base` directive (in the same order as [`PartialVdf::bases`][bases])
+    ///
+    /// [bases]: crate::PartialVdf::bases
+    pub bases: Vec<Span>,
+    /// The span of each `#include` directive (in the same order as
+    /// [`PartialVdf::includes`][includes])
+    ///
+    /// [includes]: crate::PartialVdf::includes
+    pub includes: Vec<Span>,
+}
+
+/// The spans of the directives of a document parsed with [`Parser::parse_root()`][parse_root]
+///
+/// The spans of the top-level pairs are stored in the [`RootVdf::root`][root] object
+///
+/// [parse_root]: crate::Parser::parse_root
+/// [root]: crate::RootVdf::root
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RootSpans {
+    /// The span of each `#base` directive (in the same order as [`RootVdf::bases`][bases])
+    ///
+    /// [bases]: crate::RootVdf::bases
+    pub bases: Vec<Span>,
+    /// The span of each `#include` directive (in the same order as
+    /// [`RootVdf::includes`][includes])
+    ///
+    /// [includes]: crate::RootVdf::includes
+    pub includes: Vec<Span>,
+}
+
+// Maps byte offsets to lines and columns
+pub(crate) struct LineIndex<'text> {
+    text: &'text str,
+    line_starts: Vec<usize>,
+}
+
+impl<'text> LineIndex<'text> {
+    pub(crate) fn new(text: &'text str) -> Self {
+        let newlines = text.match_indices('\n').map(|(i, _)| i + 1);
+        let line_starts = std::iter::once(0).chain(newlines).collect();
+        Self { text, line_starts }
+    }
+
+    pub(crate) fn position(&self, offset: usize) -> Position {
+        let line = match self.line_starts.binary_search(&offset) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+        let line_start = self.line_starts[line];
+        Position {
+            offset,
+            line: line + 1,
+            col: self.text[line_start..offset].chars().count() + 1,
+        }
+    }
+
+    pub(crate) fn span(&self, start: usize, end: usize) -> Span {
+        Span {
+            start: self.position(start),
+            end: self.position(end),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+//! Streaming KeyValues text support
+//!
+//! Parsing normally needs the whole text in memory and builds the whole [`Vdf`][crate::Vdf] tree
+//! from it. For large documents the [`Reader`] instead pulls [`Event`]s out of any
+//! [`io::BufRead`][std::io::BufRead] while only ever buffering the token that it's currently
+//! reading. Going the other way, the [`Writer`] writes a document straight to any
+//! [`io::Write`][std::io::Write] without building the tree first
+//!
+//! # Example
+//!
+//! ```
+//! use keyvalues_parser::stream::{Event, Reader};
+//!
+//! let text = r#"
+//! "AppState"
+//! {
+//!     "appid" "440"
+//!     "UserConfig" { "language" "english" }
+//! }
+//! "#;
+//! let mut keys = Vec::new();
+//! for event in Reader::new(text.as_bytes()) {
+//!     if let Event::Key(key) = event? {
+//!         keys.push(key);
+//!     }
+//! }
+//! assert_eq!(keys, ["AppState", "appid", "UserConfig", "language"]);
+//! # Ok::<(), keyvalues_parser::error::Error>(())
+//! ```
+
+mod reader;
+mod writer;
+
+pub use reader::{Event, Reader};
+pub use writer::Writer;
@@ -0,0 +1,553 @@
+use std::{
+    borrow::Cow,
+    io::{self, BufRead, BufReader, Read},
+};
+
+use crate::{
+    error::{Limit, ParseErrorKind, StreamError, StreamErrorKind},
+    text::lex::{self, CondError, Input, RawStr},
+    Conditional, Limits, Position,
+};
+
+type Result<T> = std::result::Result<T, StreamError>;
+
+/// A single piece of a document pulled from a [`Reader`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// The path of a `#base` directive
+    Base(String),
+    /// The path of an `#include` directive
+    Include(String),
+    /// The key of a pair. Always followed by its value as either an [`Event::Str`] or an
+    /// [`Event::ObjBegin`]
+    Key(String),
+    /// A string value
+    Str(String),
+    /// The start of an object value. The object's pairs follow until the matching
+    /// [`Event::ObjEnd`]
+    ObjBegin,
+    /// The end of an object value
+    ObjEnd,
+    /// The conditional of the current pair
+    ///
+    /// This comes in the same place as in the text, so it's either right before the pair's
+    /// [`Event::ObjBegin`] or right after its value
+    Conditional(Conditional<'static>),
+}
+
+/// A pull-parser that reads [`Event`]s from KeyValues text
+///
+/// Only the token that's currently being read gets buffered (along with a small amount of
+/// lookahead for conditionals and directives), so memory use only depends on the longest string
+/// instead of how large the document is. Set [`Limits::max_string_len`] with [`Reader::limits()`]
+/// to bound that too when reading untrusted text
+///
+/// The events are validated the same way as [`Parser::parse()`][crate::Parser::parse], so the
+/// reader stops with an error at the first problem it encounters
+///
+/// # Example
+///
+/// ```
+/// use keyvalues_parser::stream::{Event, Reader};
+///
+/// let mut reader = Reader::new(&b"Outer { Inner Value }"[..]);
+/// assert_eq!(reader.next_event()?, Some(Event::Key("Outer".into())));
+/// assert_eq!(reader.next_event()?, Some(Event::ObjBegin));
+/// assert_eq!(reader.next_event()?, Some(Event::Key("Inner".into())));
+/// assert_eq!(reader.next_event()?, Some(Event::Str("Value".into())));
+/// assert_eq!(reader.next_event()?, Some(Event::ObjEnd));
+/// assert_eq!(reader.next_event()?, None);
+/// # Ok::<(), keyvalues_parser::error::Error>(())
+/// ```
+pub struct Reader<R> {
+    source: Source<R>,
+    literal_special_chars: bool,
+    pairs: usize,
+    state: State,
+    // The position of each `{` that hasn't been closed yet and whether its pair had a leading
+    // conditional
+    open: Vec<(Position, bool)>,
+    queued: Option<Event>,
+}
+
+#[derive(Clone, Copy)]
+enum State {
+    Directives,
+    Key,
+    Value,
+    AfterValue,
+    Trailing,
+    Done,
+}
+
+impl<R: Read> Reader<BufReader<R>> {
+    /// Creates a reader that buffers reads from `reader`
+    pub fn from_read(reader: R) -> Self {
+        Self::new(BufReader::new(reader))
+    }
+}
+
+impl<R: BufRead> Reader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            source: Source {
+                reader,
+                pushback: Vec::new(),
+                position: Position {
+                    offset: 0,
+                    line: 1,
+                    col: 1,
+                },
+                recording: None,
+                limits: Limits::new(),
+            },
+            literal_special_chars: false,
+            pairs: 0,
+            state: State::Directives,
+            open: Vec::new(),
+            queued: None,
+        }
+    }
+
+    /// Whether to interpret `\` in strings as the start of an escaped special character, or a
+    /// literal `\`. Same as [`Parser::literal_special_chars()`][crate::Parser::literal_special_chars]
+    pub fn literal_special_chars(mut self, yes: bool) -> Self {
+        self.literal_special_chars = yes;
+        self
+    }
+
+    /// The [`Limits`] on the size and shape of the text. Same as
+    /// [`Parser::limits()`][crate::Parser::limits]
+    ///
+    /// ```
+    /// use keyvalues_parser::{
+    ///     error::{Error, Limit, ParseErrorKind, StreamErrorKind},
+    ///     stream::Reader,
+    ///     Limits,
+    /// };
+    /// let limits = Limits {
+    ///     max_string_len: Some(8),
+    ///     ..Limits::new()
+    /// };
+    /// let mut reader = Reader::new(&b"Key \"A really long value\""[..]).limits(limits);
+    /// reader.next_event()?;
+    /// let Err(Error::StreamError(err)) = reader.next_event() else {
+    ///     unreachable!();
+    /// };
+    /// let limit = Limit::StringLen;
+    /// let kind = ParseErrorKind::LimitExceeded { limit, max: 8 };
+    /// assert!(matches!(err.kind, StreamErrorKind::Parse(k) if k == kind));
+    /// # Ok::<(), keyvalues_parser::error::Error>(())
+    /// ```
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.source.limits = limits;
+        self
+    }
+
+    /// Where the reader currently is in the text
+    pub fn position(&self) -> Position {
+        self.source.position
+    }
+
+    /// Reads the next event, returning `None` once the document is over
+    ///
+    /// After an error is returned the reader is done and only returns `None`
+    pub fn next_event(&mut self) -> crate::error::Result<Option<Event>> {
+        if let Some(event) = self.queued.take() {
+            return Ok(Some(event));
+        }
+
+        self.advance().map_err(|err| {
+            self.state = State::Done;
+            err.into()
+        })
+    }
+
+    fn advance(&mut self) -> Result<Option<Event>> {
+        loop {
+            match self.state {
+                State::Directives => {
+                    self.skip()?;
+                    if let Some(event) = self.try_directive()? {
+                        return Ok(Some(event));
+                    }
+                    self.state = State::Key;
+                }
+                State::Key => {
+                    self.skip()?;
+                    let position = self.source.position;
+                    return match self.source.peek()? {
+                        None => match self.open.last() {
+                            Some(&(opening, _)) => Err(parse_error(
+                                ParseErrorKind::UnbalancedBrace { opening },
+                                position,
+                            )),
+                            None => Err(parse_error(ParseErrorKind::MissingKey, position)),
+                        },
+                        Some(b'}') if !self.open.is_empty() => {
+                            self.source.bump()?;
+                            let (_, leading_cond) = self.open.pop().unwrap();
+                            // A trailing conditional is only possible without a leading one
+                            self.state = if leading_cond {
+                                self.after_pair()
+                            } else {
+                                State::AfterValue
+                            };
+                            Ok(Some(Event::ObjEnd))
+                        }
+                        Some(b'{' | b'}') => Err(parse_error(ParseErrorKind::MissingKey, position)),
+                        Some(_) => {
+                            self.pairs += 1;
+                            self.source
+                                .check_limit(Limit::Pairs, self.pairs, position)?;
+                            let key = self.string()?;
+                            self.state = State::Value;
+                            Ok(Some(Event::Key(key)))
+                        }
+                    };
+                }
+                State::Value => {
+                    self.skip()?;
+
+                    // A conditional between the key and value is only valid when the value is an
+                    // object
+                    if self.source.peek()? == Some(b'[') {
+                        self.source.mark();
+                        if let Some(cond) = self.try_conditional()? {
+                            self.skip()?;
+                            if self.source.peek()? == Some(b'{') {
+                                self.source.commit();
+                                self.open_obj(true)?;
+                                self.queued = Some(Event::ObjBegin);
+                                return Ok(Some(Event::Conditional(cond)));
+                            }
+                        }
+                        self.source.reset();
+                    }
+
+                    let position = self.source.position;
+                    return match self.source.peek()? {
+                        None | Some(b'}') => {
+                            Err(parse_error(ParseErrorKind::MissingValue, position))
+                        }
+                        Some(b'{') => {
+                            self.open_obj(false)?;
+                            Ok(Some(Event::ObjBegin))
+                        }
+                        Some(_) => {
+                            let s = self.string()?;
+                            self.state = State::AfterValue;
+                            Ok(Some(Event::Str(s)))
+                        }
+                    };
+                }
+                State::AfterValue => {
+                    // Skipping here is fine even without a conditional since whatever comes next
+                    // would skip it anyways
+                    self.skip()?;
+                    self.state = self.after_pair();
+                    if self.source.peek()? == Some(b'[') {
+                        self.source.mark();
+                        match self.try_conditional()? {
+                            Some(cond) => {
+                                self.source.commit();
+                                return Ok(Some(Event::Conditional(cond)));
+                            }
+                            None => self.source.reset(),
+                        }
+                    }
+                }
+                State::Trailing => {
+                    self.skip()?;
+                    self.source.eat(b'\0')?;
+                    self.skip()?;
+                    if self.source.peek()?.is_some() {
+                        let position = self.source.position;
+                        return Err(parse_error(ParseErrorKind::TrailingContent, position));
+                    }
+                    self.state = State::Done;
+                }
+                State::Done => return Ok(None),
+            }
+        }
+    }
+
+    fn after_pair(&self) -> State {
+        if self.open.is_empty() {
+            State::Trailing
+        } else {
+            State::Key
+        }
+    }
+
+    fn open_obj(&mut self, leading_cond: bool) -> Result<()> {
+        let position = self.source.position;
+        self.source
+            .check_limit(Limit::Depth, self.open.len() + 1, position)?;
+        self.open.push((position, leading_cond));
+        self.source.bump()?;
+        self.state = State::Key;
+        Ok(())
+    }
+
+    fn skip(&mut self) -> Result<()> {
+        lex::skip(&mut self.source)
+    }
+
+    // A directive that fails to parse is actually the key of the top-level pair
+    fn try_directive(&mut self) -> Result<Option<Event>> {
+        if self.source.peek()? != Some(b'#') {
+            return Ok(None);
+        }
+
+        self.source.mark();
+        let Some(is_base) = lex::directive_keyword(&mut self.source)? else {
+            self.source.reset();
+            return Ok(None);
+        };
+        self.skip()?;
+        let start = self.source.position;
+        let Some((path, _)) = lex::directive_path(&mut self.source)? else {
+            self.source.reset();
+            return Ok(None);
+        };
+        self.source.commit();
+
+        let path = into_string(path, start)?;
+        Ok(Some(if is_base {
+            Event::Base(path)
+        } else {
+            Event::Include(path)
+        }))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let start = self.source.position;
+        let inner = match lex::string(&mut self.source, self.literal_special_chars)? {
+            RawStr::Unquoted(s) => return into_string(s, start),
+            RawStr::Quoted(inner) => into_string(inner, start)?,
+            RawStr::Unterminated(_) => {
+                return Err(parse_error(ParseErrorKind::UnterminatedString, start))
+            }
+        };
+        if self.literal_special_chars {
+            return Ok(inner);
+        }
+
+        let mut invalid = None;
+        let unescaped = match lex::unescape(&inner, |other, offset| {
+            invalid.get_or_insert((other, offset));
+        }) {
+            Cow::Owned(unescaped) => Some(unescaped),
+            Cow::Borrowed(_) => None,
+        };
+        match invalid {
+            Some((other, offset)) => {
+                // Walk from the opening quote up to the escape
+                let mut position = start;
+                for &b in b"\"".iter().chain(&inner.as_bytes()[..offset]) {
+                    advance(&mut position, b);
+                }
+                let kind = ParseErrorKind::InvalidEscape(other);
+                Err(parse_error(kind, position))
+            }
+            None => Ok(unescaped.unwrap_or(inner)),
+        }
+    }
+
+    // Any partial conditional gets reset by the caller, so nothing needs to be given back here
+    fn try_conditional(&mut self) -> Result<Option<Conditional<'static>>> {
+        let start = self.source.position;
+        let max_depth = self.source.limits.max_depth;
+        lex::conditional(&mut self.source, max_depth).map_err(|err| match err {
+            CondError::Input(err) => err,
+            CondError::TooDeep { max } => {
+                let kind = ParseErrorKind::LimitExceeded {
+                    limit: Limit::Depth,
+                    max,
+                };
+                parse_error(kind, start)
+            }
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = crate::error::Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
+
+// Bytes from the reader with the ability to give back what was read since the last `mark()`
+struct Source<R> {
+    reader: R,
+    // Bytes that were given back, stored in reverse
+    pushback: Vec<u8>,
+    position: Position,
+    recording: Option<(Position, Vec<u8>)>,
+    limits: Limits,
+}
+
+impl<R: BufRead> Source<R> {
+    fn peek(&mut self) -> Result<Option<u8>> {
+        if let Some(&b) = self.pushback.last() {
+            return Ok(Some(b));
+        }
+        let next = loop {
+            match self.reader.fill_buf() {
+                Ok(buf) => break buf.first().copied(),
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    return Err(StreamError {
+                        position: self.position,
+                        kind: StreamErrorKind::Io(err),
+                    })
+                }
+            }
+        };
+
+        // Like the regular parser this points at the start of the text
+        let len = self.position.offset + 1;
+        match self
+            .limits
+            .check(Limit::InputLen, len)
+            .filter(|_| next.is_some())
+        {
+            Some(kind) => {
+                let start = Position {
+                    offset: 0,
+                    line: 1,
+                    col: 1,
+                };
+                Err(parse_error(kind, start))
+            }
+            None => Ok(next),
+        }
+    }
+
+    fn bump(&mut self) -> Result<Option<u8>> {
+        let b = match self.pushback.pop() {
+            Some(b) => b,
+            None => match self.peek()? {
+                Some(b) => {
+                    self.reader.consume(1);
+                    b
+                }
+                None => return Ok(None),
+            },
+        };
+
+        advance(&mut self.position, b);
+        if let Some((_, recorded)) = &mut self.recording {
+            recorded.push(b);
+        }
+
+        Ok(Some(b))
+    }
+
+    // Gives back a single byte that was just bumped
+    fn unbump(&mut self, b: u8, before: Position) {
+        self.pushback.push(b);
+        self.position = before;
+        if let Some((_, recorded)) = &mut self.recording {
+            recorded.pop();
+        }
+    }
+
+    fn mark(&mut self) {
+        debug_assert!(self.recording.is_none());
+        self.recording = Some((self.position, Vec::new()));
+    }
+
+    fn commit(&mut self) {
+        self.recording = None;
+    }
+
+    fn reset(&mut self) {
+        if let Some((position, recorded)) = self.recording.take() {
+            self.position = position;
+            self.pushback.extend(recorded.into_iter().rev());
+        }
+    }
+
+    fn check_limit(&self, limit: Limit, value: usize, position: Position) -> Result<()> {
+        match self.limits.check(limit, value) {
+            Some(kind) => Err(parse_error(kind, position)),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<R: BufRead> Input<'static> for Source<R> {
+    type Error = StreamError;
+    type Pos = Position;
+    type Str = Vec<u8>;
+
+    fn pos(&self) -> Position {
+        self.position
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>> {
+        Source::peek(self)
+    }
+
+    fn peek_second(&mut self) -> Result<Option<u8>> {
+        let before = self.position;
+        let Some(first) = self.bump()? else {
+            return Ok(None);
+        };
+        let second = Source::peek(self)?;
+        self.unbump(first, before);
+        Ok(second)
+    }
+
+    fn bump(&mut self) -> Result<()> {
+        Source::bump(self)?;
+        Ok(())
+    }
+
+    // The token is only buffered up to the string length limit
+    fn take_while(&mut self, start: Position, mut pred: impl FnMut(u8) -> bool) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        while let Some(b) = Source::peek(self)?.filter(|&b| pred(b)) {
+            buf.push(b);
+            self.check_limit(Limit::StringLen, buf.len(), start)?;
+            Source::bump(self)?;
+        }
+        Ok(buf)
+    }
+
+    fn ascii(s: Vec<u8>) -> Cow<'static, str> {
+        Cow::Owned(s.into_iter().map(char::from).collect())
+    }
+}
+
+fn parse_error(kind: ParseErrorKind, position: Position) -> StreamError {
+    StreamError {
+        position,
+        kind: StreamErrorKind::Parse(kind),
+    }
+}
+
+fn into_string(bytes: Vec<u8>, start: Position) -> Result<String> {
+    String::from_utf8(bytes).map_err(|_| StreamError {
+        position: start,
+        kind: StreamErrorKind::InvalidUtf8,
+    })
+}
+
+fn advance(position: &mut Position, b: u8) {
+    position.offset += 1;
+    if b == b'\n' {
+        position.line += 1;
+        position.col = 1;
+    } else if !is_continuation_byte(b) {
+        position.col += 1;
+    }
+}
+
+fn is_continuation_byte(b: u8) -> bool {
+    b & 0xC0 == 0x80
+}
@@ -0,0 +1,226 @@
+use std::{
+    fmt::{self, Write as _},
+    io::{self, Write},
+};
+
+use crate::{
+    error::{Error, Result, WriteError, WriteErrorKind},
+    text::{
+        lex,
+        render_::{find_invalid_raw_char, multiple_char, write_str, RenderType},
+    },
+};
+
+/// A writer that streams KeyValues text straight to an [`io::Write`]
+///
+/// The output is rendered exactly like [`Vdf::render()`][crate::Vdf::render] would render the
+/// same document, but without ever having to build the whole tree in memory. Calls are checked
+/// against the structure of a document, so misuse (like ending an object that was never started)
+/// returns an error instead of writing malformed text
+///
+/// # Example
+///
+/// ```
+/// use keyvalues_parser::stream::Writer;
+///
+/// let mut writer = Writer::new(Vec::new());
+/// writer.base("base.vdf")?;
+/// writer.begin_obj("AppState")?;
+/// writer.pair("appid", "440")?;
+/// writer.begin_obj("UserConfig")?;
+/// writer.pair("language", "english")?;
+/// writer.end_obj()?;
+/// writer.end_obj()?;
+/// let text = String::from_utf8(writer.finish()?).unwrap();
+/// let expected = "\
+/// #base \"base.vdf\"\n\
+/// \n\
+/// \"AppState\"\n\
+/// {\n\
+/// \t\"appid\"\t\"440\"\n\
+/// \t\"UserConfig\"\n\
+/// \t{\n\
+/// \t\t\"language\"\t\"english\"\n\
+/// \t}\n\
+/// }\n";
+/// assert_eq!(text, expected);
+/// # Ok::<(), keyvalues_parser::error::Error>(())
+/// ```
+pub struct Writer<W> {
+    writer: W,
+    render_type: RenderType,
+    state: State,
+    depth: usize,
+    // Reused between calls so that each call is a single write
+    buf: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Start,
+    Directives,
+    TopLevel,
+    Done,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            render_type: RenderType::Escaped,
+            state: State::Start,
+            depth: 0,
+            buf: String::new(),
+        }
+    }
+
+    /// Whether to write strings with their special characters escaped (the default) or as-is
+    ///
+    /// When `true` this matches [`Vdf::render_raw()`][crate::Vdf::render_raw], so writing a
+    /// string that contains a `"` is an error
+    pub fn literal_special_chars(mut self, yes: bool) -> Self {
+        self.render_type = if yes {
+            RenderType::Raw
+        } else {
+            RenderType::Escaped
+        };
+        self
+    }
+
+    /// Writes a `#base` directive. Directives must come before the top-level pair
+    pub fn base(&mut self, path: &str) -> Result<()> {
+        self.directive("base", path)
+    }
+
+    /// Writes an `#include` directive. Directives must come before the top-level pair
+    pub fn include(&mut self, path: &str) -> Result<()> {
+        self.directive("include", path)
+    }
+
+    /// Writes a pair with a string value
+    pub fn pair(&mut self, key: &str, value: &str) -> Result<()> {
+        self.check_raw(key)?;
+        self.check_raw(value)?;
+        let state = self.start_pair()?;
+        let (depth, render_type) = (self.depth, self.render_type);
+        self.emit(|buf| {
+            buf.push_str(&multiple_char('\t', depth));
+            write_str(buf, key, render_type)?;
+            buf.push('\t');
+            write_str(buf, value, render_type)?;
+            buf.push('\n');
+            Ok(())
+        })?;
+        self.state = if depth == 0 { State::Done } else { state };
+        Ok(())
+    }
+
+    /// Writes the key of a pair with an object value and starts the object. Everything written
+    /// until the matching [`Writer::end_obj()`] goes inside of it
+    pub fn begin_obj(&mut self, key: &str) -> Result<()> {
+        self.check_raw(key)?;
+        let state = self.start_pair()?;
+        let (depth, render_type) = (self.depth, self.render_type);
+        self.emit(|buf| {
+            let indent = multiple_char('\t', depth);
+            buf.push_str(&indent);
+            write_str(buf, key, render_type)?;
+            writeln!(buf, "\n{indent}{{")
+        })?;
+        self.state = state;
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Ends the most recently started object
+    pub fn end_obj(&mut self) -> Result<()> {
+        let Some(depth) = self.depth.checked_sub(1) else {
+            return Err(self.err(WriteErrorKind::UnbalancedEndObj));
+        };
+        self.emit(|buf| writeln!(buf, "{}}}", multiple_char('\t', depth)))?;
+        self.depth = depth;
+        if depth == 0 {
+            self.state = State::Done;
+        }
+        Ok(())
+    }
+
+    /// How many objects are currently open
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Checks that the document is complete, flushes, and returns the underlying writer
+    pub fn finish(mut self) -> Result<W> {
+        if self.depth != 0 {
+            return Err(self.err(WriteErrorKind::UnclosedObj));
+        }
+        if self.state != State::Done {
+            return Err(self.err(WriteErrorKind::MissingTopLevelPair));
+        }
+        self.writer.flush().map_err(|e| self.io_err(e))?;
+        Ok(self.writer)
+    }
+
+    fn directive(&mut self, name: &str, path: &str) -> Result<()> {
+        if !matches!(self.state, State::Start | State::Directives) {
+            return Err(self.err(WriteErrorKind::MisplacedDirective));
+        }
+        if !lex::is_writable_path(path) {
+            return Err(self.err(WriteErrorKind::InvalidDirectivePath));
+        }
+        self.emit(|buf| writeln!(buf, "#{name} \"{path}\""))?;
+        self.state = State::Directives;
+        Ok(())
+    }
+
+    // Checks that a pair can go here and writes the blank line that separates any directives
+    // from the top-level pair. Returns the state to switch to once the pair is written
+    fn start_pair(&mut self) -> Result<State> {
+        match self.state {
+            State::Done => Err(self.err(WriteErrorKind::MultipleTopLevelPairs)),
+            State::Directives => {
+                self.emit(|buf| {
+                    buf.push('\n');
+                    Ok(())
+                })?;
+                self.state = State::Start;
+                Ok(State::TopLevel)
+            }
+            State::Start | State::TopLevel => Ok(State::TopLevel),
+        }
+    }
+
+    fn check_raw(&self, s: &str) -> Result<()> {
+        match self.render_type {
+            RenderType::Raw => match find_invalid_raw_char(s) {
+                Some(invalid_char) => Err(Error::RawRenderError { invalid_char }),
+                None => Ok(()),
+            },
+            RenderType::Escaped => Ok(()),
+        }
+    }
+
+    fn emit(&mut self, f: impl FnOnce(&mut String) -> fmt::Result) -> Result<()> {
+        self.buf.clear();
+        f(&mut self.buf)?;
+        let Self { writer, buf, .. } = self;
+        writer.write_all(buf.as_bytes()).map_err(|e| self.io_err(e))
+    }
+
+    fn err(&self, kind: WriteErrorKind) -> Error {
+        WriteError {
+            depth: self.depth,
+            kind,
+        }
+        .into()
+    }
+
+    fn io_err(&self, e: io::Error) -> Error {
+        self.err(WriteErrorKind::Io(e))
+    }
+}
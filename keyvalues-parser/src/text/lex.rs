@@ -0,0 +1,479 @@
+// The byte-level grammar shared by the text parser, the CST parser, and the stream reader
+//
+// All of the syntax is ASCII, so everything gets scanned byte-by-byte. UTF-8 continuation bytes
+// never overlap with ASCII, so every position we stop at is a valid `char` boundary
+//
+// The grammar never backtracks on its own. Anything that can fail part way through (directives and
+// conditionals) leaves whatever it read consumed, and the caller resets to where it started instead
+
+use std::{borrow::Cow, convert::Infallible};
+
+use crate::Conditional;
+
+pub(crate) trait Input<'text> {
+    type Error;
+    type Pos: Copy;
+    // The bytes of a token. Either borrowed from the text or buffered from a stream
+    type Str: AsRef<[u8]>;
+
+    fn pos(&self) -> Self::Pos;
+
+    fn peek(&mut self) -> Result<Option<u8>, Self::Error>;
+
+    fn peek_second(&mut self) -> Result<Option<u8>, Self::Error>;
+
+    fn bump(&mut self) -> Result<(), Self::Error>;
+
+    fn skip_while(&mut self, mut pred: impl FnMut(u8) -> bool) -> Result<(), Self::Error> {
+        while self.peek()?.map_or(false, &mut pred) {
+            self.bump()?;
+        }
+        Ok(())
+    }
+
+    // `start` is where the token started including any opening quote, so that a token that's too
+    // long can be reported there
+    fn take_while(
+        &mut self,
+        start: Self::Pos,
+        pred: impl FnMut(u8) -> bool,
+    ) -> Result<Self::Str, Self::Error>;
+
+    // Only ever given ASCII
+    fn ascii(s: Self::Str) -> Cow<'text, str>;
+
+    fn eat(&mut self, b: u8) -> Result<bool, Self::Error> {
+        let matches = self.peek()? == Some(b);
+        if matches {
+            self.bump()?;
+        }
+        Ok(matches)
+    }
+
+    // Partial matches are left consumed
+    fn eat_all(&mut self, s: &[u8]) -> Result<bool, Self::Error> {
+        for &b in s {
+            if !self.eat(b)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+// A string as it appears in the text, so escapes are still escaped
+pub(crate) enum RawStr<S> {
+    Unquoted(S),
+    Quoted(S),
+    // Runs to the end of the text
+    Unterminated(S),
+}
+
+// Whitespace followed by any number of comments that are each followed by whitespace
+pub(crate) fn skip<'text, I: Input<'text>>(input: &mut I) -> Result<(), I::Error> {
+    loop {
+        input.skip_while(is_whitespace)?;
+        // A lone `/` is the start of an unquoted string instead
+        if input.peek()? != Some(b'/') || input.peek_second()? != Some(b'/') {
+            return Ok(());
+        }
+        input.skip_while(|b| b != b'\n')?;
+    }
+}
+
+// Returns whether the directive is a `#base` as opposed to an `#include`
+pub(crate) fn directive_keyword<'text, I: Input<'text>>(
+    input: &mut I,
+) -> Result<Option<bool>, I::Error> {
+    if !input.eat(b'#')? {
+        return Ok(None);
+    }
+    let is_base = match input.peek()? {
+        Some(b'b') => true,
+        Some(b'i') => false,
+        _ => return Ok(None),
+    };
+    let rest: &[u8] = if is_base { b"base" } else { b"include" };
+    Ok(input.eat_all(rest)?.then_some(is_base))
+}
+
+// Along with whether the path was quoted
+pub(crate) fn directive_path<'text, I: Input<'text>>(
+    input: &mut I,
+) -> Result<Option<(I::Str, bool)>, I::Error> {
+    let start = input.pos();
+    if input.eat(b'"')? {
+        // Paths are never escaped
+        let path = input.take_while(start, |b| b != b'"')?;
+        Ok(input.eat(b'"')?.then_some((path, true)))
+    } else {
+        let path = input.take_while(start, is_unquoted_byte)?;
+        Ok((!path.as_ref().is_empty()).then_some((path, false)))
+    }
+}
+
+pub(crate) fn string<'text, I: Input<'text>>(
+    input: &mut I,
+    literal_special_chars: bool,
+) -> Result<RawStr<I::Str>, I::Error> {
+    let start = input.pos();
+    if !input.eat(b'"')? {
+        return Ok(RawStr::Unquoted(input.take_while(start, is_unquoted_byte)?));
+    }
+
+    let inner = if literal_special_chars {
+        input.take_while(start, |b| b != b'"')?
+    } else {
+        // Whatever is escaped gets skipped. Validating it is left to unescaping
+        let mut escaped = false;
+        input.take_while(start, |b| {
+            if escaped {
+                escaped = false;
+            } else if b == b'\\' {
+                escaped = true;
+            } else if b == b'"' {
+                return false;
+            }
+            true
+        })?
+    };
+
+    Ok(if input.eat(b'"')? {
+        RawStr::Quoted(inner)
+    } else {
+        RawStr::Unterminated(inner)
+    })
+}
+
+// Invalid escapes are kept as-is after being passed to `invalid` along with their offset in `s`
+pub(crate) fn unescape(s: &str, mut invalid: impl FnMut(char, usize)) -> Cow<'_, str> {
+    let bytes = s.as_bytes();
+    let Some(first) = bytes.iter().position(|&b| b == b'\\') else {
+        return Cow::Borrowed(s);
+    };
+
+    // Everything between escapes gets copied over in chunks
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chunk_start = 0;
+    let mut i = first;
+    while i < bytes.len() {
+        if bytes[i] != b'\\' {
+            i += 1;
+            continue;
+        }
+
+        unescaped.push_str(&s[chunk_start..i]);
+        let escaped = match bytes.get(i + 1) {
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'\\') => '\\',
+            Some(b'"') => '"',
+            Some(_) => {
+                let other = s[i + 1..].chars().next().unwrap();
+                invalid(other, i);
+                unescaped.push('\\');
+                chunk_start = i + 1;
+                i += 1 + other.len_utf8();
+                continue;
+            }
+            // Only possible for an unterminated string which is already reported
+            None => '\\',
+        };
+        unescaped.push(escaped);
+        i += 2;
+        chunk_start = i;
+    }
+    unescaped.push_str(&s[chunk_start.min(s.len())..]);
+
+    Cow::Owned(unescaped)
+}
+
+pub(crate) enum CondError<E> {
+    Input(E),
+    // Nested deeper than the max depth, since both parsing and dropping a conditional recurse
+    TooDeep { max: usize },
+}
+
+impl<E> From<E> for CondError<E> {
+    fn from(err: E) -> Self {
+        Self::Input(err)
+    }
+}
+
+// Along with the depth of the conditional
+type CondResult<'text, E> = Result<Option<(Conditional<'text>, usize)>, CondError<E>>;
+
+pub(crate) fn conditional<'text, I: Input<'text>>(
+    input: &mut I,
+    max_depth: Option<usize>,
+) -> Result<Option<Conditional<'text>>, CondError<I::Error>> {
+    if !input.eat(b'[')? {
+        return Ok(None);
+    }
+    input.skip_while(is_cond_space)?;
+    let Some((cond, _)) = cond_or(input, 1, max_depth)? else {
+        return Ok(None);
+    };
+    input.skip_while(is_cond_space)?;
+    Ok(input.eat(b']')?.then_some(cond))
+}
+
+fn check_depth<E>(depth: usize, max_depth: Option<usize>) -> Result<usize, CondError<E>> {
+    match max_depth {
+        Some(max) if depth > max => Err(CondError::TooDeep { max }),
+        _ => Ok(depth),
+    }
+}
+
+// Both `||` and `&&` chains get folded to the left
+fn cond_or<'text, I: Input<'text>>(
+    input: &mut I,
+    nesting: usize,
+    max_depth: Option<usize>,
+) -> CondResult<'text, I::Error> {
+    let Some((mut cond, mut depth)) = cond_and(input, nesting, max_depth)? else {
+        return Ok(None);
+    };
+    loop {
+        input.skip_while(is_cond_space)?;
+        if input.peek()? != Some(b'|') {
+            return Ok(Some((cond, depth)));
+        }
+        // A lone `|` fails the whole conditional
+        if !input.eat_all(b"||")? {
+            return Ok(None);
+        }
+        input.skip_while(is_cond_space)?;
+        let Some((right, right_depth)) = cond_and(input, nesting, max_depth)? else {
+            return Ok(None);
+        };
+        depth = check_depth(depth.max(right_depth) + 1, max_depth)?;
+        cond = Conditional::Or(Box::new(cond), Box::new(right));
+    }
+}
+
+fn cond_and<'text, I: Input<'text>>(
+    input: &mut I,
+    nesting: usize,
+    max_depth: Option<usize>,
+) -> CondResult<'text, I::Error> {
+    let Some((mut cond, mut depth)) = cond_unary(input, nesting, max_depth)? else {
+        return Ok(None);
+    };
+    loop {
+        input.skip_while(is_cond_space)?;
+        if input.peek()? != Some(b'&') {
+            return Ok(Some((cond, depth)));
+        }
+        if !input.eat_all(b"&&")? {
+            return Ok(None);
+        }
+        input.skip_while(is_cond_space)?;
+        let Some((right, right_depth)) = cond_unary(input, nesting, max_depth)? else {
+            return Ok(None);
+        };
+        depth = check_depth(depth.max(right_depth) + 1, max_depth)?;
+        cond = Conditional::And(Box::new(cond), Box::new(right));
+    }
+}
+
+// `nesting` is how many `!`s and `(`s this is within, which bounds the recursion even when the
+// parentheses don't add to the depth
+fn cond_unary<'text, I: Input<'text>>(
+    input: &mut I,
+    nesting: usize,
+    max_depth: Option<usize>,
+) -> CondResult<'text, I::Error> {
+    check_depth(nesting, max_depth)?;
+    if input.eat(b'!')? {
+        input.skip_while(is_cond_space)?;
+        let Some((cond, depth)) = cond_unary(input, nesting + 1, max_depth)? else {
+            return Ok(None);
+        };
+        let depth = check_depth(depth + 1, max_depth)?;
+        Ok(Some((Conditional::Not(Box::new(cond)), depth)))
+    } else if input.eat(b'(')? {
+        input.skip_while(is_cond_space)?;
+        let cond = cond_or(input, nesting + 1, max_depth)?;
+        input.skip_while(is_cond_space)?;
+        Ok(match cond {
+            Some(cond) if input.eat(b')')? => Some(cond),
+            _ => None,
+        })
+    } else if input.eat(b'$')? {
+        let start = input.pos();
+        let symbol = input.take_while(start, |b| b.is_ascii_alphanumeric() || b == b'_')?;
+        Ok((!symbol.as_ref().is_empty()).then(|| (Conditional::Symbol(I::ascii(symbol)), 1)))
+    } else {
+        Ok(None)
+    }
+}
+
+// Text that's already in memory. Scanning it can't fail
+#[derive(Clone, Copy)]
+pub(crate) struct Cursor<'text> {
+    pub text: &'text str,
+    pub pos: usize,
+}
+
+impl<'text> Cursor<'text> {
+    pub fn new(text: &'text str) -> Self {
+        Self { text, pos: 0 }
+    }
+
+    #[inline]
+    pub fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    #[inline]
+    pub fn eat(&mut self, s: &[u8]) -> bool {
+        let matches = self.text.as_bytes()[self.pos..].starts_with(s);
+        if matches {
+            self.pos += s.len();
+        }
+        matches
+    }
+
+    #[inline]
+    pub fn eat_while(&mut self, mut pred: impl FnMut(u8) -> bool) -> &'text str {
+        let start = self.pos;
+        let rest = &self.text.as_bytes()[start..];
+        let len = rest.iter().position(|&b| !pred(b)).unwrap_or(rest.len());
+        self.pos += len;
+        &self.text[start..self.pos]
+    }
+
+    // Everything from `start` up to where the cursor is now
+    pub fn since(&self, start: usize) -> &'text str {
+        &self.text[start..self.pos]
+    }
+
+    pub fn rest(&self) -> &'text str {
+        &self.text[self.pos..]
+    }
+
+    pub fn skip(&mut self) {
+        infallible(skip(self));
+    }
+
+    // A directive that fails to parse is left unconsumed
+    pub fn directive_keyword(&mut self) -> Option<bool> {
+        let start = self.pos;
+        let is_base = infallible(directive_keyword(self));
+        if is_base.is_none() {
+            self.pos = start;
+        }
+        is_base
+    }
+
+    pub fn directive_path(&mut self) -> Option<(&'text str, bool)> {
+        infallible(directive_path(self))
+    }
+
+    pub fn string(&mut self, literal_special_chars: bool) -> RawStr<&'text str> {
+        infallible(string(self, literal_special_chars))
+    }
+
+    // A conditional that fails to parse is left unconsumed. Errors with the max depth when it's
+    // nested too deeply
+    pub fn conditional(
+        &mut self,
+        max_depth: Option<usize>,
+    ) -> Result<Option<Conditional<'text>>, usize> {
+        let start = self.pos;
+        let cond = match conditional(self, max_depth) {
+            Ok(cond) => cond,
+            Err(CondError::TooDeep { max }) => return Err(max),
+            Err(CondError::Input(never)) => match never {},
+        };
+        if cond.is_none() {
+            self.pos = start;
+        }
+        Ok(cond)
+    }
+}
+
+impl<'text> Input<'text> for Cursor<'text> {
+    type Error = Infallible;
+    type Pos = usize;
+    type Str = &'text str;
+
+    #[inline]
+    fn pos(&self) -> usize {
+        self.pos
+    }
+
+    #[inline]
+    fn peek(&mut self) -> Result<Option<u8>, Infallible> {
+        Ok(Cursor::peek(self))
+    }
+
+    #[inline]
+    fn peek_second(&mut self) -> Result<Option<u8>, Infallible> {
+        Ok(self.text.as_bytes().get(self.pos + 1).copied())
+    }
+
+    #[inline]
+    fn bump(&mut self) -> Result<(), Infallible> {
+        self.pos += 1;
+        Ok(())
+    }
+
+    #[inline]
+    fn skip_while(&mut self, pred: impl FnMut(u8) -> bool) -> Result<(), Infallible> {
+        self.eat_while(pred);
+        Ok(())
+    }
+
+    #[inline]
+    fn take_while(
+        &mut self,
+        _start: usize,
+        pred: impl FnMut(u8) -> bool,
+    ) -> Result<&'text str, Infallible> {
+        Ok(self.eat_while(pred))
+    }
+
+    fn ascii(s: &'text str) -> Cow<'text, str> {
+        Cow::Borrowed(s)
+    }
+}
+
+fn infallible<T>(res: Result<T, Infallible>) -> T {
+    match res {
+        Ok(t) => t,
+        Err(never) => match never {},
+    }
+}
+
+#[inline]
+pub(crate) fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\r' | b'\n')
+}
+
+#[inline]
+pub(crate) fn is_cond_space(b: u8) -> bool {
+    matches!(b, b' ' | b'\t')
+}
+
+#[inline]
+pub(crate) fn is_unquoted_byte(b: u8) -> bool {
+    !matches!(b, b'"' | b'{' | b'}') && !is_whitespace(b)
+}
+
+// Whether `path` can be written back out as a directive. Paths are quoted but never escaped, and
+// the directive has to stay on its own line
+pub(crate) fn is_writable_path(path: &str) -> bool {
+    !path.contains(['"', '\n', '\r'])
+}
+
+// Whether `s` can be written without quotes and still be read back the same. Unquoted strings are
+// never unescaped, and a leading `#`, `[`, or `//` would start a directive, conditional, or comment
+pub(crate) fn is_unquotable(s: &str) -> bool {
+    !s.is_empty()
+        && s.bytes().all(is_unquoted_byte)
+        && !s.starts_with(['#', '['])
+        && !s.starts_with("//")
+}
@@ -1,6 +1,7 @@
+pub(crate) mod lex;
 pub mod parse;
 #[path = "render.rs"]
-mod render_;
+pub(crate) mod render_;
 
 #[deprecated(since = "0.2.3", note = "Empty and unintentionally exposed :)")]
 pub mod render {}
@@ -0,0 +1,525 @@
+use std::{borrow::Cow, collections::BTreeMap};
+
+use crate::{
+    error::{Limit, ParseError, ParseErrorKind},
+    span::LineIndex,
+    text::lex::{self, Cursor, RawStr},
+    Conditional, DocumentSpans, DuplicateKeys, Encoding, Include, Key, Limits, Obj, ObjPair,
+    PairSpan, Parser, PartialVdf, Position, RootSpans, RootVdf, Span, Value,
+};
+
+/// The result of a lenient parse from [`Parser::parse_lenient()`][crate::Parser::parse_lenient]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recovered<'text> {
+    /// The best-effort document. This is only `None` when there was no top-level pair to recover
+    pub vdf: Option<PartialVdf<'text>>,
+    /// Every problem encountered while parsing. Empty when the text was valid
+    pub errors: Vec<ParseError>,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct Options {
+    pub literal_special_chars: bool,
+    pub spans: bool,
+    pub case_insensitive_keys: bool,
+    pub duplicate_keys: DuplicateKeys,
+    pub limits: Limits,
+    /// Stop at the first error instead of recovering
+    pub strict: bool,
+}
+
+impl Options {
+    pub fn new(parser: &Parser, strict: bool) -> Self {
+        Self {
+            literal_special_chars: parser.literal_special_chars,
+            spans: parser.spans,
+            case_insensitive_keys: parser.case_insensitive_keys,
+            duplicate_keys: parser.duplicate_keys,
+            limits: parser.limits,
+            strict,
+        }
+    }
+}
+
+pub(crate) fn parse(text: &str, opts: Options) -> Recovered<'_> {
+    let mut scanner = Scanner::new(text, opts);
+    let vdf = scanner.document();
+
+    Recovered {
+        vdf,
+        errors: scanner.errors,
+    }
+}
+
+pub(crate) fn parse_root(text: &str, opts: Options) -> (RootVdf<'_>, Vec<ParseError>) {
+    let mut scanner = Scanner::new(text, opts);
+    let vdf = scanner.root();
+    (vdf, scanner.errors)
+}
+
+// Along with the offset of the key
+type ParsedPair<'text> = (ObjPair<'text>, usize);
+type KeyOffsets<'text> = BTreeMap<Key<'text>, usize>;
+
+#[derive(Default)]
+struct Directives<'text> {
+    bases: Vec<Cow<'text, str>>,
+    includes: Vec<Include<'text>>,
+    base_spans: Vec<Span>,
+    include_spans: Vec<Span>,
+}
+
+// Instead of bailing at the first problem an error gets recorded and parsing recovers at the
+// nearest string, pair, or brace boundary. Strict parsing just stops after the first error
+struct Scanner<'text> {
+    cursor: Cursor<'text>,
+    opts: Options,
+    // Built upfront when recording spans, otherwise only once there's an error to report
+    lines: Option<LineIndex<'text>>,
+    errors: Vec<ParseError>,
+    // Where each key in the currently open objects was first used. Only tracked when duplicate
+    // keys are errors
+    key_offsets: Vec<KeyOffsets<'text>>,
+    // The offsets from the most recently closed object, which is the top-level pair's value once
+    // it's parsed
+    closed_key_offsets: KeyOffsets<'text>,
+    depth: usize,
+    pairs: usize,
+    // Set once a limit is exceeded, which stops parsing even when recovering from errors
+    aborted: bool,
+}
+
+impl<'text> Scanner<'text> {
+    fn new(text: &'text str, opts: Options) -> Self {
+        Self {
+            cursor: Cursor::new(text),
+            opts,
+            lines: opts.spans.then(|| LineIndex::new(text)),
+            errors: Vec::new(),
+            key_offsets: Vec::new(),
+            closed_key_offsets: KeyOffsets::new(),
+            depth: 0,
+            pairs: 0,
+            aborted: false,
+        }
+    }
+
+    fn lines(&mut self) -> &LineIndex<'text> {
+        let text = self.cursor.text;
+        self.lines.get_or_insert_with(|| LineIndex::new(text))
+    }
+
+    fn position(&mut self, offset: usize) -> Position {
+        self.lines().position(offset)
+    }
+
+    #[inline]
+    fn span(&mut self, start: usize) -> Option<Span> {
+        let end = self.cursor.pos;
+        self.opts.spans.then(|| self.lines().span(start, end))
+    }
+
+    fn error(&mut self, kind: ParseErrorKind, offset: usize) {
+        if self.stopped() {
+            return;
+        }
+        let text = self.cursor.text;
+        let err = ParseError::new(text, self.lines(), kind, offset);
+        self.errors.push(err);
+    }
+
+    #[inline]
+    fn stopped(&self) -> bool {
+        self.aborted || (self.opts.strict && !self.errors.is_empty())
+    }
+
+    // Returns whether `value` is over the `max` for `limit`, aborting if it is
+    fn exceeds(&mut self, limit: Limit, max: Option<usize>, value: usize, offset: usize) -> bool {
+        match max {
+            Some(max) if value > max => {
+                self.limit_exceeded(limit, max, offset);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn limit_exceeded(&mut self, limit: Limit, max: usize, offset: usize) {
+        self.error(ParseErrorKind::LimitExceeded { limit, max }, offset);
+        self.aborted = true;
+    }
+
+    fn skip(&mut self) {
+        self.cursor.skip();
+    }
+
+    fn directives(&mut self) -> Directives<'text> {
+        let mut directives = Directives::default();
+        self.skip();
+        while let Some((is_base, path, span)) = self.try_directive() {
+            if is_base {
+                directives.bases.push(path);
+                directives.base_spans.extend(span);
+            } else {
+                let position = directives.bases.len() + directives.includes.len();
+                directives.includes.push(Include { path, position });
+                directives.include_spans.extend(span);
+            }
+            self.skip();
+        }
+        directives
+    }
+
+    fn document(&mut self) -> Option<PartialVdf<'text>> {
+        let Directives {
+            bases,
+            includes,
+            base_spans,
+            include_spans,
+        } = self.directives();
+
+        // Anything that can't start a pair gets skipped over until we find one
+        let ((key, mut value, conditional, pair_span), _) = loop {
+            if self.stopped() {
+                return None;
+            }
+            match self.cursor.peek() {
+                None => {
+                    self.error(ParseErrorKind::MissingKey, self.cursor.pos);
+                    return None;
+                }
+                Some(b'}') => {
+                    self.error(ParseErrorKind::MissingKey, self.cursor.pos);
+                    self.cursor.pos += 1;
+                }
+                Some(b'{') => {
+                    self.error(ParseErrorKind::MissingKey, self.cursor.pos);
+                    self.obj();
+                }
+                Some(_) => {
+                    if let Some(pair) = self.pair() {
+                        break pair;
+                    }
+                }
+            }
+            self.skip();
+        };
+
+        self.trailing(&mut value);
+
+        Some(PartialVdf {
+            key,
+            value,
+            conditional,
+            bases,
+            includes,
+            spans: pair_span.map(|pair| DocumentSpans {
+                pair,
+                bases: base_spans,
+                includes: include_spans,
+            }),
+            encoding: Encoding::default(),
+        })
+    }
+
+    // Like an object's pairs, but without the braces and with an optional trailing null byte
+    fn root(&mut self) -> RootVdf<'text> {
+        let Directives {
+            bases,
+            includes,
+            base_spans,
+            include_spans,
+        } = self.directives();
+
+        self.key_offsets.push(KeyOffsets::new());
+        let mut root = Obj::new();
+        while !self.stopped() {
+            self.skip();
+            self.cursor.eat(b"\0");
+            self.skip();
+            match self.cursor.peek() {
+                None => break,
+                Some(b'}') => {
+                    self.error(ParseErrorKind::MissingKey, self.cursor.pos);
+                    self.cursor.pos += 1;
+                }
+                Some(b'{') => {
+                    self.error(ParseErrorKind::MissingKey, self.cursor.pos);
+                    self.obj();
+                }
+                Some(_) => {
+                    if let Some(pair) = self.pair() {
+                        self.push_pair(&mut root, pair);
+                    }
+                }
+            }
+        }
+        self.key_offsets.pop();
+
+        RootVdf {
+            bases,
+            includes,
+            root,
+            spans: self.opts.spans.then_some(RootSpans {
+                bases: base_spans,
+                includes: include_spans,
+            }),
+        }
+    }
+
+    // Stray closing braces are a common source of trailing content, so pairs that come after the
+    // top-level pair get recovered into it
+    fn trailing(&mut self, value: &mut Value<'text>) {
+        let offsets = std::mem::take(&mut self.closed_key_offsets);
+        self.key_offsets.push(offsets);
+        let mut reported = false;
+        while !self.stopped() {
+            self.skip();
+            self.cursor.eat(b"\0");
+            self.skip();
+            match self.cursor.peek() {
+                None => break,
+                Some(b'}') => {
+                    self.error(ParseErrorKind::TrailingContent, self.cursor.pos);
+                    self.cursor.pos += 1;
+                    reported = true;
+                }
+                Some(b) => {
+                    if !reported {
+                        self.error(ParseErrorKind::TrailingContent, self.cursor.pos);
+                        reported = true;
+                    }
+                    if b == b'{' {
+                        self.obj();
+                    } else if let Some(pair) = self.pair() {
+                        if let Value::Obj(obj) = value {
+                            self.push_pair(obj, pair);
+                        }
+                    }
+                }
+            }
+        }
+        self.key_offsets.pop();
+    }
+
+    // A directive that fails to parse is actually the key of the top-level pair
+    fn try_directive(&mut self) -> Option<(bool, Cow<'text, str>, Option<Span>)> {
+        let start = self.cursor.pos;
+        let is_base = self.cursor.directive_keyword()?;
+        self.skip();
+        match self.cursor.directive_path() {
+            Some((path, _)) => Some((is_base, Cow::Borrowed(path), self.span(start))),
+            None => {
+                self.cursor.pos = start;
+                None
+            }
+        }
+    }
+
+    // Returns `None` when the pair is missing its value
+    fn pair(&mut self) -> Option<ParsedPair<'text>> {
+        let key_start = self.cursor.pos;
+        self.pairs += 1;
+        if self.exceeds(
+            Limit::Pairs,
+            self.opts.limits.max_pairs,
+            self.pairs,
+            key_start,
+        ) {
+            return None;
+        }
+        let key = self.string();
+        let key_span = self.span(key_start);
+        self.skip();
+
+        // A conditional between the key and value is only valid when the value is an object
+        if self.cursor.peek() == Some(b'[') {
+            let before_cond = self.cursor.pos;
+            if let Some(cond) = self.try_conditional() {
+                self.skip();
+                if self.cursor.peek() == Some(b'{') {
+                    let value_start = self.cursor.pos;
+                    let value = Value::Obj(self.obj());
+                    let span = pair_span(key_span, self.span(value_start));
+                    return Some(((key, value, Some(cond), span), key_start));
+                }
+            }
+            self.cursor.pos = before_cond;
+        }
+
+        let value_start = self.cursor.pos;
+        let value = match self.cursor.peek() {
+            None | Some(b'}') => {
+                self.error(ParseErrorKind::MissingValue, self.cursor.pos);
+                return None;
+            }
+            Some(b'{') => Value::Obj(self.obj()),
+            Some(_) => Value::Str(self.string()),
+        };
+        let span = pair_span(key_span, self.span(value_start));
+
+        let before_cond = self.cursor.pos;
+        self.skip();
+        let cond = if self.cursor.peek() == Some(b'[') {
+            self.try_conditional()
+        } else {
+            None
+        };
+        if cond.is_none() {
+            self.cursor.pos = before_cond;
+        }
+
+        Some(((key, value, cond, span), key_start))
+    }
+
+    fn try_conditional(&mut self) -> Option<Conditional<'text>> {
+        let start = self.cursor.pos;
+        match self.cursor.conditional(self.opts.limits.max_depth) {
+            Ok(cond) => cond,
+            Err(max) => {
+                self.limit_exceeded(Limit::Depth, max, start);
+                None
+            }
+        }
+    }
+
+    fn obj(&mut self) -> Obj<'text> {
+        let opening = self.cursor.pos;
+        assert!(self.cursor.eat(b"{"), "Only called on an opening brace");
+        let max_depth = self.opts.limits.max_depth;
+        if self.exceeds(Limit::Depth, max_depth, self.depth + 1, opening) {
+            return Obj::new();
+        }
+        self.depth += 1;
+        self.key_offsets.push(KeyOffsets::new());
+        let mut obj = Obj::new();
+        while !self.stopped() {
+            self.skip();
+            match self.cursor.peek() {
+                None => {
+                    let opening = self.position(opening);
+                    self.error(ParseErrorKind::UnbalancedBrace { opening }, self.cursor.pos);
+                    break;
+                }
+                Some(b'}') => {
+                    self.cursor.pos += 1;
+                    break;
+                }
+                // The pairs within are still checked for errors, but there's no key to store
+                // them under
+                Some(b'{') => {
+                    self.error(ParseErrorKind::MissingKey, self.cursor.pos);
+                    self.obj();
+                }
+                Some(_) => {
+                    if let Some(pair) = self.pair() {
+                        self.push_pair(&mut obj, pair);
+                    }
+                }
+            }
+        }
+
+        self.closed_key_offsets = self.key_offsets.pop().unwrap_or_default();
+        self.depth -= 1;
+        obj
+    }
+
+    fn push_pair(&mut self, obj: &mut Obj<'text>, (pair, key_offset): ParsedPair<'text>) {
+        let (key, value, cond, span) = pair;
+        let key = self.fold_key(obj, key);
+        if !obj.contains_key(&key) {
+            if self.opts.duplicate_keys == DuplicateKeys::Error {
+                if let Some(offsets) = self.key_offsets.last_mut() {
+                    offsets.insert(key.clone(), key_offset);
+                }
+            }
+            push(obj, (key, value, cond, span));
+            return;
+        }
+
+        match self.opts.duplicate_keys {
+            DuplicateKeys::KeepAll => push(obj, (key, value, cond, span)),
+            DuplicateKeys::FirstWins => {}
+            DuplicateKeys::LastWins => obj.replace_first(key, value, cond, span),
+            DuplicateKeys::MergeObjs => self.merge_pair(obj, (key, value, cond, span)),
+            DuplicateKeys::Error => {
+                let first = self
+                    .key_offsets
+                    .last()
+                    .and_then(|offsets| offsets.get(&key))
+                    .copied()
+                    .unwrap_or(key_offset);
+                let first = self.position(first);
+                self.error(ParseErrorKind::DuplicateKey { first }, key_offset);
+            }
+        }
+    }
+
+    // Objects get merged recursively while anything else replaces the earlier value. The merged
+    // pair keeps the conditional and span of where the key was first used
+    fn merge_pair(&self, obj: &mut Obj<'text>, (key, value, cond, span): ObjPair<'text>) {
+        let key = self.fold_key(obj, key);
+        let existing = obj.get_mut(&key).and_then(|values| values.first_mut());
+        match (existing, value) {
+            (None, value) => push(obj, (key, value, cond, span)),
+            (Some(Value::Obj(existing)), Value::Obj(merged)) => {
+                for pair in merged.into_pairs() {
+                    self.merge_pair(existing, pair);
+                }
+            }
+            (Some(_), value) => obj.replace_first(key, value, cond, span),
+        }
+    }
+
+    // Keys that only differ in case get merged under the first spelling
+    fn fold_key(&self, obj: &Obj<'text>, key: Key<'text>) -> Key<'text> {
+        if self.opts.case_insensitive_keys {
+            obj.key_ci(&key).cloned().unwrap_or(key)
+        } else {
+            key
+        }
+    }
+
+    fn string(&mut self) -> Cow<'text, str> {
+        let start = self.cursor.pos;
+        let inner = match self.cursor.string(self.opts.literal_special_chars) {
+            RawStr::Unquoted(s) => {
+                self.check_string_len(s, start);
+                return Cow::Borrowed(s);
+            }
+            RawStr::Quoted(inner) => inner,
+            // An unterminated string would otherwise swallow the rest of the text, so it only
+            // extends to the end of its line instead
+            RawStr::Unterminated(_) => {
+                self.error(ParseErrorKind::UnterminatedString, start);
+                self.cursor.pos = start + 1;
+                self.cursor.eat_while(|b| b != b'\n').trim_end_matches('\r')
+            }
+        };
+
+        if self.check_string_len(inner, start) || self.opts.literal_special_chars {
+            Cow::Borrowed(inner)
+        } else {
+            let inner_start = start + 1;
+            lex::unescape(inner, |other, offset| {
+                self.error(ParseErrorKind::InvalidEscape(other), inner_start + offset);
+            })
+        }
+    }
+
+    fn check_string_len(&mut self, s: &str, start: usize) -> bool {
+        let max = self.opts.limits.max_string_len;
+        self.exceeds(Limit::StringLen, max, s.len(), start)
+    }
+}
+
+fn push<'text>(obj: &mut Obj<'text>, (key, value, cond, span): ObjPair<'text>) {
+    if let Some(span) = span {
+        obj.push_span(key.clone(), span);
+    }
+    obj.push(key, value, cond);
+}
+
+fn pair_span(key: Option<Span>, value: Option<Span>) -> Option<PairSpan> {
+    key.zip(value).map(|(key, value)| PairSpan { key, value })
+}
@@ -1,22 +1,32 @@
-use std::fmt::{self, Write};
+use std::{
+    borrow::Cow,
+    fmt::{self, Write},
+    fs::File,
+    io::{self, BufWriter},
+    path::Path,
+    vec,
+};
 
-use crate::{error::Error, Obj, PartialVdf, Value, Vdf};
+use crate::{
+    error::Error, text::lex, Conditional, Encoding, Include, Key, Obj, PartialVdf, Renderer,
+    RootVdf, Separator, TypedValue, Value, Vdf,
+};
 
-fn multiple_char(c: char, amount: usize) -> String {
+pub(crate) fn multiple_char(c: char, amount: usize) -> String {
     std::iter::repeat(c).take(amount).collect()
 }
 
 #[derive(Debug, Clone, Copy)]
-enum RenderType {
+pub(crate) enum RenderType {
     Escaped,
     Raw,
 }
 
-fn find_invalid_raw_char(s: &str) -> Option<char> {
+pub(crate) fn find_invalid_raw_char(s: &str) -> Option<char> {
     s.chars().find(|&c| c == '"').to_owned()
 }
 
-fn write_str(writer: &mut impl Write, s: &str, render_type: RenderType) -> fmt::Result {
+pub(crate) fn write_str(writer: &mut impl Write, s: &str, render_type: RenderType) -> fmt::Result {
     writer.write_char('"')?;
 
     match render_type {
@@ -38,72 +48,414 @@ fn write_str(writer: &mut impl Write, s: &str, render_type: RenderType) -> fmt::
     writer.write_char('"')
 }
 
-fn write_pair(
-    writer: &mut impl Write,
-    num_indents: usize,
-    key: &str,
-    value: &Value<'_>,
-    render_type: RenderType,
-) -> fmt::Result {
-    // Write the indented key
-    writer.write_str(&multiple_char('\t', num_indents))?;
-    write_str(writer, key, render_type)?;
-
-    // Followed by the value
-    if value.is_str() {
-        writer.write_char('\t')?;
-    } else {
-        writer.write_char('\n')?;
-    }
-    value.write_indented(writer, num_indents, render_type)?;
-
-    writer.write_char('\n')
-}
-
-fn write_obj(
-    writer: &mut impl Write,
-    num_indents: usize,
-    obj: &Obj<'_>,
-    render_type: RenderType,
-) -> fmt::Result {
-    for (key, values) in obj.iter() {
-        for value in values {
-            write_pair(writer, num_indents, key, value, render_type)?;
+// Escaped text only ever has a `\` as part of an escape, so anything containing one stays quoted
+fn needs_quotes(s: &str, render_type: RenderType) -> bool {
+    !lex::is_unquotable(s) || (matches!(render_type, RenderType::Escaped) && s.contains('\\'))
+}
+
+// An object that's being rendered along with the width that its keys get padded to when aligned
+struct Frame<'a, 'text> {
+    obj: &'a Obj<'text>,
+    pairs: vec::IntoIter<(&'a Key<'text>, usize, &'a Value<'text>)>,
+    key_width: usize,
+}
+
+impl Renderer {
+    fn render_type(&self) -> RenderType {
+        if self.literal_special_chars {
+            RenderType::Raw
+        } else {
+            RenderType::Escaped
+        }
+    }
+
+    fn write_str(&self, writer: &mut impl Write, s: &str) -> fmt::Result {
+        let render_type = self.render_type();
+        if self.quote_only_when_needed && !needs_quotes(s, render_type) {
+            writer.write_str(s)
+        } else {
+            write_str(writer, s, render_type)
+        }
+    }
+
+    fn str_width(&self, s: &str) -> usize {
+        let mut rendered = String::new();
+        // Writing to a `String` never fails
+        let _ = self.write_str(&mut rendered, s);
+        rendered.chars().count()
+    }
+
+    fn write_newline(&self, writer: &mut impl Write) -> fmt::Result {
+        if self.minified {
+            Ok(())
+        } else {
+            writer.write_str(self.newline.as_str())
+        }
+    }
+
+    // Starts a new line at `depth`. Minified pairs are separated by a space instead
+    fn write_line_start(&self, writer: &mut impl Write, depth: usize, first: bool) -> fmt::Result {
+        if self.minified {
+            if !first {
+                writer.write_char(' ')?;
+            }
+        } else {
+            for _ in 0..depth {
+                writer.write_str(&self.indent)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_separator(&self, writer: &mut impl Write, key: &str, key_width: usize) -> fmt::Result {
+        if self.minified {
+            return writer.write_char(' ');
+        }
+
+        match &self.separator {
+            Separator::Str(separator) => writer.write_str(separator),
+            Separator::Aligned => {
+                let padding = key_width.saturating_sub(self.str_width(key)) + 1;
+                writer.write_str(&multiple_char(' ', padding))
+            }
         }
     }
 
-    Ok(())
+    fn write_closing_brace(&self, writer: &mut impl Write, depth: usize) -> fmt::Result {
+        self.write_line_start(writer, depth, false)?;
+        writer.write_char('}')
+    }
+
+    // Writes a pair without its leading indent or trailing newline. Objects only get as far as
+    // their opening brace with the pairs within left to the caller
+    fn write_pair_start(
+        &self,
+        writer: &mut impl Write,
+        depth: usize,
+        key: &str,
+        value: &Value<'_>,
+        conditional: Option<&Conditional<'_>>,
+        key_width: usize,
+    ) -> fmt::Result {
+        self.write_str(writer, key)?;
+
+        // The conditional trails a str or leads an obj
+        match value {
+            Value::Obj(_) => {
+                if let Some(cond) = conditional {
+                    write!(writer, " {cond}")?;
+                }
+                if self.minified {
+                    writer.write_str(" {")
+                } else {
+                    self.write_newline(writer)?;
+                    self.write_line_start(writer, depth, false)?;
+                    writer.write_char('{')
+                }
+            }
+            _ => {
+                self.write_separator(writer, key, key_width)?;
+                self.write_value(writer, depth, value)?;
+                if let Some(cond) = conditional {
+                    write!(writer, " {cond}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // A whole top-level pair like `Vdf` and `PartialVdf` have
+    fn write_pair(
+        &self,
+        writer: &mut impl Write,
+        key: &str,
+        value: &Value<'_>,
+        conditional: Option<&Conditional<'_>>,
+    ) -> fmt::Result {
+        let key_width = self.str_width(key);
+        self.write_pair_start(writer, 0, key, value, conditional, key_width)?;
+        if let Value::Obj(obj) = value {
+            self.write_newline(writer)?;
+            self.write_obj(writer, 1, obj, false)?;
+            self.write_closing_brace(writer, 0)?;
+        }
+        self.write_newline(writer)
+    }
+
+    fn write_value(&self, writer: &mut impl Write, depth: usize, value: &Value<'_>) -> fmt::Result {
+        match value {
+            Value::Str(s) => self.write_str(writer, s),
+            Value::Typed(typed) => self.write_str(writer, &typed.to_string()),
+            Value::Obj(obj) => {
+                self.write_line_start(writer, depth, true)?;
+                writer.write_char('{')?;
+                self.write_newline(writer)?;
+                self.write_obj(writer, depth + 1, obj, false)?;
+                self.write_closing_brace(writer, depth)
+            }
+        }
+    }
+
+    fn frame<'a, 'text>(&self, obj: &'a Obj<'text>) -> Frame<'a, 'text> {
+        let pairs = obj.ordered_pairs();
+        let key_width = match self.separator {
+            Separator::Aligned if !self.minified => pairs
+                .iter()
+                .filter(|(_, _, value)| !value.is_obj())
+                .map(|(key, _, _)| self.str_width(key))
+                .max()
+                .unwrap_or(0),
+            _ => 0,
+        };
+        Frame {
+            obj,
+            pairs: pairs.into_iter(),
+            key_width,
+        }
+    }
+
+    // Writes each of the pairs within `obj` on their own line at `depth`, leaving the braces to the
+    // caller. Nested objects are tracked with an explicit stack instead of recursing, so rendering
+    // can't overflow the stack no matter how deeply values are nested
+    fn write_obj(
+        &self,
+        writer: &mut impl Write,
+        depth: usize,
+        obj: &Obj<'_>,
+        root: bool,
+    ) -> fmt::Result {
+        let mut stack = vec![self.frame(obj)];
+        // Only the very first top-level pair skips the space between minified pairs
+        let mut first = root;
+        loop {
+            let depth = depth + stack.len().saturating_sub(1);
+            let Some(frame) = stack.last_mut() else {
+                break;
+            };
+            let Some((key, index, value)) = frame.pairs.next() else {
+                stack.pop();
+                if !stack.is_empty() {
+                    self.write_closing_brace(writer, depth - 1)?;
+                    self.write_newline(writer)?;
+                }
+                continue;
+            };
+
+            let conditional = frame.obj.conditional(key, index);
+            let key_width = frame.key_width;
+            self.write_line_start(writer, depth, first)?;
+            first = false;
+            self.write_pair_start(writer, depth, key, value, conditional, key_width)?;
+            self.write_newline(writer)?;
+            if let Value::Obj(inner) = value {
+                stack.push(self.frame(inner));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_directives(
+        &self,
+        writer: &mut impl Write,
+        bases: &[Cow<'_, str>],
+        includes: &[Include<'_>],
+    ) -> fmt::Result {
+        // The `#include`s get slotted back in between the `#base`s at their original positions
+        let mut bases_iter = bases.iter();
+        let mut includes_iter = includes.iter().peekable();
+        for position in 0.. {
+            let (name, path) = if let Some(include) =
+                includes_iter.next_if(|include| include.position <= position)
+            {
+                ("include", &include.path)
+            } else if let Some(base) = bases_iter.next() {
+                ("base", base)
+            } else if let Some(include) = includes_iter.next() {
+                ("include", &include.path)
+            } else {
+                break;
+            };
+            write!(writer, "#{name} \"{path}\"")?;
+            if self.minified {
+                writer.write_char(' ')?;
+            } else {
+                self.write_newline(writer)?;
+            }
+        }
+
+        if !bases.is_empty() || !includes.is_empty() {
+            self.write_newline(writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Passes rendered text along to an `io::Write` in some encoding. `fmt::Error` can't carry any
+// details, so the actual `io::Error` gets stashed away instead
+struct IoWriter<W> {
+    writer: W,
+    encoding: Encoding,
+    // The byte order mark is held off until the first write, so nothing gets written when the
+    // document fails to render upfront
+    bom: &'static [u8],
+    buf: Vec<u8>,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> IoWriter<W> {
+    fn write_bytes(&mut self, s: &str) -> io::Result<()> {
+        self.writer.write_all(std::mem::take(&mut self.bom))?;
+        let bytes = match self.encoding {
+            Encoding::Utf8 | Encoding::Utf8Bom => s.as_bytes(),
+            Encoding::Utf16Le { .. } | Encoding::Utf16Be { .. } => {
+                self.buf.clear();
+                self.encoding.encode_into(s, &mut self.buf);
+                &self.buf
+            }
+        };
+        self.writer.write_all(bytes)
+    }
+}
+
+impl<W: io::Write> Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_bytes(s).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+// Renders straight to `writer` with `render` swapping the `fmt::Error` from a failed write back out
+// for the writer's actual error
+fn render_io<W: io::Write>(
+    writer: &mut W,
+    encoding: Encoding,
+    render: impl FnOnce(&mut IoWriter<&mut W>) -> crate::error::Result<()>,
+) -> crate::error::Result<()> {
+    let mut io_writer = IoWriter {
+        writer: &mut *writer,
+        encoding,
+        bom: encoding.bom(),
+        buf: Vec::new(),
+        error: None,
+    };
+    if let Err(err) = render(&mut io_writer) {
+        return Err(io_writer.error.take().map_or(err, Error::Io));
+    }
+    writer.flush().map_err(Error::Io)
+}
+
+// Rendering raw strings fails on some chars, which gets checked upfront so that an existing file
+// isn't truncated only to fail
+fn check_raw(
+    renderer: &Renderer,
+    invalid_char: impl FnOnce() -> Option<char>,
+) -> crate::error::Result<()> {
+    match renderer.literal_special_chars.then(invalid_char).flatten() {
+        Some(invalid_char) => Err(Error::RawRenderError { invalid_char }),
+        None => Ok(()),
+    }
+}
+
+// Directive paths are never escaped, so ones that would break the text are caught upfront too
+fn check_paths(bases: &[Cow<'_, str>], includes: &[Include<'_>]) -> crate::error::Result<()> {
+    let mut paths = bases
+        .iter()
+        .chain(includes.iter().map(|include| &include.path));
+    match paths.find(|path| !lex::is_writable_path(path)) {
+        Some(path) => Err(Error::InvalidDirectivePath {
+            path: path.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+// Like `render_io()`, but to a freshly created (or truncated) file. The document has to be checked
+// with `check_raw()` first
+fn render_path(
+    path: &Path,
+    encoding: Encoding,
+    render: impl FnOnce(&mut IoWriter<&mut BufWriter<File>>) -> crate::error::Result<()>,
+) -> crate::error::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    render_io(&mut writer, encoding, render)
 }
 
 impl fmt::Display for PartialVdf<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self._render(f, RenderType::Raw)
+        self._render(f, &RAW)
     }
 }
 
+// `PartialVdf`'s `Display` and `render()` have always rendered raw strings without checking them
+const RAW: Renderer = Renderer::new().literal_special_chars(true);
+
 impl PartialVdf<'_> {
     // TODO: do we really want to return a crate error here? It will always be a formatting error
     pub fn render(&self, writer: &mut impl Write) -> crate::error::Result<()> {
-        self._render(writer, RenderType::Raw).map_err(Into::into)
+        check_paths(&self.bases, &self.includes)?;
+        self._render(writer, &RAW).map_err(Into::into)
     }
 
     pub fn render_raw(&self, writer: &mut impl Write) -> crate::error::Result<()> {
-        match self.find_invalid_raw_char() {
-            Some(invalid_char) => Err(Error::RawRenderError { invalid_char }),
-            None => self._render(writer, RenderType::Raw).map_err(Into::into),
-        }
+        self.render_with(writer, &RAW)
     }
 
-    fn _render(&self, writer: &mut impl Write, render_type: RenderType) -> fmt::Result {
-        for base in &self.bases {
-            writeln!(writer, "#base \"{base}\"")?;
-        }
+    /// Renders the document with the formatting from `renderer`
+    pub fn render_with(
+        &self,
+        writer: &mut impl Write,
+        renderer: &Renderer,
+    ) -> crate::error::Result<()> {
+        check_raw(renderer, || self.find_invalid_raw_char())?;
+        check_paths(&self.bases, &self.includes)?;
+        self._render(writer, renderer).map_err(Into::into)
+    }
 
-        if !self.bases.is_empty() {
-            writer.write_char('\n')?;
-        }
+    /// Renders the document straight to `writer` in its [`PartialVdf::encoding`]
+    ///
+    /// See [`Vdf::render_to_writer()`] for the details
+    pub fn render_to_writer(
+        &self,
+        mut writer: impl io::Write,
+        renderer: &Renderer,
+    ) -> crate::error::Result<()> {
+        render_io(&mut writer, self.encoding, |writer| {
+            self.render_with(writer, renderer)
+        })
+    }
+
+    /// Renders the document to the file at `path` in its [`PartialVdf::encoding`], creating or
+    /// replacing it
+    pub fn render_to_path(
+        &self,
+        path: impl AsRef<Path>,
+        renderer: &Renderer,
+    ) -> crate::error::Result<()> {
+        check_raw(renderer, || self.find_invalid_raw_char())?;
+        check_paths(&self.bases, &self.includes)?;
+        render_path(path.as_ref(), self.encoding, |writer| {
+            self._render(writer, renderer).map_err(Into::into)
+        })
+    }
 
-        write_pair(writer, 0, &self.key, &self.value, render_type)
+    /// Renders the document to bytes in its [`PartialVdf::encoding`]
+    ///
+    /// Along with [`Parser::parse_bytes()`][crate::Parser::parse_bytes] this writes a file back in
+    /// the same encoding that it was read in
+    pub fn render_bytes(&self) -> crate::error::Result<Vec<u8>> {
+        let mut text = String::new();
+        self.render(&mut text)?;
+        Ok(self.encoding.encode(&text))
+    }
+
+    fn _render(&self, writer: &mut impl Write, renderer: &Renderer) -> fmt::Result {
+        renderer.write_directives(writer, &self.bases, &self.includes)?;
+        renderer.write_pair(writer, &self.key, &self.value, self.conditional.as_ref())
     }
 
     fn find_invalid_raw_char(&self) -> Option<char> {
@@ -111,71 +463,177 @@ impl PartialVdf<'_> {
     }
 }
 
-impl fmt::Display for Vdf<'_> {
+impl fmt::Display for RootVdf<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.write_indented(f, 0, RenderType::Escaped)
+        self._render(f, &Renderer::new())
     }
 }
 
-impl Vdf<'_> {
+impl RootVdf<'_> {
     pub fn render(&self, writer: &mut impl Write) -> crate::error::Result<()> {
+        check_paths(&self.bases, &self.includes)?;
         write!(writer, "{self}").map_err(Into::into)
     }
 
     pub fn render_raw(&self, writer: &mut impl Write) -> crate::error::Result<()> {
-        match self.find_invalid_raw_char() {
-            Some(invalid_char) => Err(Error::RawRenderError { invalid_char }),
-            None => self
-                .write_indented(writer, 0, RenderType::Raw)
-                .map_err(Into::into),
-        }
+        self.render_with(writer, &RAW)
+    }
+
+    /// Renders the document with the formatting from `renderer`
+    pub fn render_with(
+        &self,
+        writer: &mut impl Write,
+        renderer: &Renderer,
+    ) -> crate::error::Result<()> {
+        check_raw(renderer, || self.find_invalid_raw_char())?;
+        check_paths(&self.bases, &self.includes)?;
+        self._render(writer, renderer).map_err(Into::into)
+    }
+
+    /// Renders the document straight to `writer`
+    ///
+    /// See [`Vdf::render_to_writer()`] for the details
+    pub fn render_to_writer(
+        &self,
+        mut writer: impl io::Write,
+        renderer: &Renderer,
+    ) -> crate::error::Result<()> {
+        render_io(&mut writer, Encoding::Utf8, |writer| {
+            self.render_with(writer, renderer)
+        })
+    }
+
+    /// Renders the document to the file at `path`, creating or replacing it
+    pub fn render_to_path(
+        &self,
+        path: impl AsRef<Path>,
+        renderer: &Renderer,
+    ) -> crate::error::Result<()> {
+        check_raw(renderer, || self.find_invalid_raw_char())?;
+        check_paths(&self.bases, &self.includes)?;
+        render_path(path.as_ref(), Encoding::Utf8, |writer| {
+            self._render(writer, renderer).map_err(Into::into)
+        })
     }
 
     fn find_invalid_raw_char(&self) -> Option<char> {
-        find_invalid_raw_char(&self.key).or_else(|| self.value.find_invalid_raw_char())
+        self.root.iter().find_map(|(key, values)| {
+            find_invalid_raw_char(key)
+                .or_else(|| values.iter().find_map(Value::find_invalid_raw_char))
+        })
+    }
+
+    // Each top-level pair gets rendered just like a pair within an object, but without the indent
+    fn _render(&self, writer: &mut impl Write, renderer: &Renderer) -> fmt::Result {
+        renderer.write_directives(writer, &self.bases, &self.includes)?;
+        renderer.write_obj(writer, 0, &self.root, true)
+    }
+}
+
+impl fmt::Display for Vdf<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Renderer::new().write_pair(f, &self.key, &self.value, self.conditional.as_ref())
     }
+}
 
-    fn write_indented(
+impl Vdf<'_> {
+    pub fn render(&self, writer: &mut impl Write) -> crate::error::Result<()> {
+        write!(writer, "{self}").map_err(Into::into)
+    }
+
+    pub fn render_raw(&self, writer: &mut impl Write) -> crate::error::Result<()> {
+        self.render_with(writer, &RAW)
+    }
+
+    /// Renders the pair with the formatting from `renderer`
+    ///
+    /// See [`Renderer`] for an example
+    pub fn render_with(
         &self,
         writer: &mut impl Write,
-        num_indents: usize,
-        render_type: RenderType,
-    ) -> fmt::Result {
-        write_pair(writer, num_indents, &self.key, &self.value, render_type)
+        renderer: &Renderer,
+    ) -> crate::error::Result<()> {
+        check_raw(renderer, || self.find_invalid_raw_char())?;
+        renderer
+            .write_pair(writer, &self.key, &self.value, self.conditional.as_ref())
+            .map_err(Into::into)
+    }
+
+    /// Renders the pair straight to `writer`
+    ///
+    /// Unlike [`Vdf::render_with()`] this works with any [`io::Write`]. The writer's own errors
+    /// are returned as [`Error::Io`] while other errors (like a `"` in a string when rendering
+    /// with [`Renderer::literal_special_chars()`]) are returned before anything gets written
+    ///
+    /// The text is written in lots of small pieces, so wrap unbuffered writers like a [`File`] in a
+    /// [`BufWriter`]
+    ///
+    /// ```
+    /// use keyvalues_parser::{Renderer, Vdf};
+    /// let vdf = Vdf::parse("Config { Volume 80 }")?;
+    /// let mut bytes = Vec::new();
+    /// vdf.render_to_writer(&mut bytes, &Renderer::new())?;
+    /// assert_eq!(bytes, b"\"Config\"\n{\n\t\"Volume\"\t\"80\"\n}\n");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn render_to_writer(
+        &self,
+        mut writer: impl io::Write,
+        renderer: &Renderer,
+    ) -> crate::error::Result<()> {
+        render_io(&mut writer, Encoding::Utf8, |writer| {
+            self.render_with(writer, renderer)
+        })
+    }
+
+    /// Renders the pair to the file at `path`, creating or replacing it
+    ///
+    /// Problems with the pair itself (like a `"` in a string when rendering with
+    /// [`Renderer::literal_special_chars()`]) are caught before the file is touched, so an existing
+    /// file is left as-is
+    pub fn render_to_path(
+        &self,
+        path: impl AsRef<Path>,
+        renderer: &Renderer,
+    ) -> crate::error::Result<()> {
+        check_raw(renderer, || self.find_invalid_raw_char())?;
+        render_path(path.as_ref(), Encoding::Utf8, |writer| {
+            renderer
+                .write_pair(writer, &self.key, &self.value, self.conditional.as_ref())
+                .map_err(Into::into)
+        })
+    }
+
+    fn find_invalid_raw_char(&self) -> Option<char> {
+        find_invalid_raw_char(&self.key).or_else(|| self.value.find_invalid_raw_char())
     }
 }
 
 impl fmt::Display for Value<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.write_indented(f, 0, RenderType::Escaped)
+        Renderer::new().write_value(f, 0, self)
     }
 }
 
 impl Value<'_> {
-    fn write_indented(
-        &self,
-        writer: &mut impl Write,
-        num_indents: usize,
-        render_type: RenderType,
-    ) -> fmt::Result {
-        // Only `Obj` gets indented
-        match self {
-            Value::Str(s) => write_str(writer, s, render_type),
-            Value::Obj(obj) => {
-                writeln!(writer, "{}{{", multiple_char('\t', num_indents))?;
-                write_obj(writer, num_indents + 1, obj, render_type)?;
-                write!(writer, "{}}}", multiple_char('\t', num_indents))
+    fn find_invalid_raw_char(&self) -> Option<char> {
+        let mut stack = vec![self];
+        while let Some(value) = stack.pop() {
+            let invalid_char = match value {
+                Self::Str(s) | Self::Typed(TypedValue::WideStr(s)) => find_invalid_raw_char(s),
+                Self::Typed(_) => None,
+                Self::Obj(obj) => {
+                    for values in obj.values() {
+                        stack.extend(values);
+                    }
+                    obj.keys().find_map(|key| find_invalid_raw_char(key))
+                }
+            };
+            if invalid_char.is_some() {
+                return invalid_char;
             }
         }
-    }
 
-    fn find_invalid_raw_char(&self) -> Option<char> {
-        match self {
-            Self::Str(s) => find_invalid_raw_char(s),
-            Self::Obj(obj) => obj.iter().find_map(|(key, values)| {
-                find_invalid_raw_char(key)
-                    .or_else(|| values.iter().find_map(Value::find_invalid_raw_char))
-            }),
-        }
+        None
     }
 }
@@ -0,0 +1,122 @@
+use std::{borrow::Cow, cmp::Ordering, fmt, hash};
+
+/// A leaf value with a specific wire type
+///
+/// Text VDF only has strings, but binary KeyValues tags each leaf with its type. These are kept
+/// around so that the original type can be inspected and written back out unchanged
+///
+/// When rendered as text each variant is written as a string e.g. `Int(-3)` becomes `"-3"` and
+/// `Color([255, 0, 0, 255])` becomes `"255 0 0 255"`
+///
+/// ```
+/// use keyvalues_parser::TypedValue;
+///
+/// assert_eq!(TypedValue::Int(-3).to_string(), "-3");
+/// assert_eq!(TypedValue::Float(0.5).to_string(), "0.5");
+/// assert_eq!(TypedValue::Color([255, 0, 0, 255]).to_string(), "255 0 0 255");
+/// ```
+#[derive(Clone, Debug)]
+pub enum TypedValue<'text> {
+    /// A signed 32-bit int
+    Int(i32),
+    /// A 32-bit float
+    ///
+    /// Comparisons (including equality) are done on the bit pattern so that every value,
+    /// including `NaN`s, is equal to itself
+    Float(f32),
+    /// A 32-bit pointer. Only meaningful to the program that wrote it
+    Ptr(u32),
+    /// A string that was stored as UTF-16
+    WideStr(Cow<'text, str>),
+    /// An RGBA color
+    Color([u8; 4]),
+    /// An unsigned 64-bit int
+    UInt64(u64),
+    /// A signed 64-bit int
+    Int64(i64),
+}
+
+impl TypedValue<'_> {
+    /// Converts the value to one that owns all of its data
+    pub fn into_owned(self) -> TypedValue<'static> {
+        match self {
+            Self::Int(i) => TypedValue::Int(i),
+            Self::Float(f) => TypedValue::Float(f),
+            Self::Ptr(p) => TypedValue::Ptr(p),
+            Self::WideStr(s) => TypedValue::WideStr(Cow::Owned(s.into_owned())),
+            Self::Color(c) => TypedValue::Color(c),
+            Self::UInt64(u) => TypedValue::UInt64(u),
+            Self::Int64(i) => TypedValue::Int64(i),
+        }
+    }
+
+    fn variant_index(&self) -> u8 {
+        match self {
+            Self::Int(_) => 0,
+            Self::Float(_) => 1,
+            Self::Ptr(_) => 2,
+            Self::WideStr(_) => 3,
+            Self::Color(_) => 4,
+            Self::UInt64(_) => 5,
+            Self::Int64(_) => 6,
+        }
+    }
+}
+
+impl fmt::Display for TypedValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int(i) => write!(f, "{i}"),
+            Self::Float(float) => write!(f, "{float}"),
+            Self::Ptr(p) => write!(f, "{p}"),
+            Self::WideStr(s) => f.write_str(s),
+            Self::Color([r, g, b, a]) => write!(f, "{r} {g} {b} {a}"),
+            Self::UInt64(u) => write!(f, "{u}"),
+            Self::Int64(i) => write!(f, "{i}"),
+        }
+    }
+}
+
+impl PartialEq for TypedValue<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TypedValue<'_> {}
+
+impl PartialOrd for TypedValue<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TypedValue<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Int(a), Self::Int(b)) => a.cmp(b),
+            (Self::Float(a), Self::Float(b)) => a.to_bits().cmp(&b.to_bits()),
+            (Self::Ptr(a), Self::Ptr(b)) => a.cmp(b),
+            (Self::WideStr(a), Self::WideStr(b)) => a.cmp(b),
+            (Self::Color(a), Self::Color(b)) => a.cmp(b),
+            (Self::UInt64(a), Self::UInt64(b)) => a.cmp(b),
+            (Self::Int64(a), Self::Int64(b)) => a.cmp(b),
+            _ => self.variant_index().cmp(&other.variant_index()),
+        }
+    }
+}
+
+impl hash::Hash for TypedValue<'_> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.variant_index().hash(state);
+        match self {
+            Self::Int(i) => i.hash(state),
+            Self::Float(f) => f.to_bits().hash(state),
+            Self::Ptr(p) => p.hash(state),
+            Self::WideStr(s) => s.hash(state),
+            Self::Color(c) => c.hash(state),
+            Self::UInt64(u) => u.hash(state),
+            Self::Int64(i) => i.hash(state),
+        }
+    }
+}
@@ -0,0 +1,10 @@
+#base "hudlayout_base.res"
+#include "../../shared/extra.res"
+
+"Resource/HudLayout.res"
+{
+	"HudHealth"
+	{
+		"xpos"	"10"
+	}
+}
@@ -0,0 +1,7 @@
+"extra"
+{
+	"HudCrosshair"
+	{
+		"visible"	"0"
+	}
+}
@@ -0,0 +1,356 @@
+use insta::assert_snapshot;
+use keyvalues_parser::{
+    binary::{self, AppInfo, PackageInfo},
+    error::{BinaryParseError, BinaryParseErrorKind, Error},
+    Limits, Obj, Parser, TypedValue, Value, Vdf,
+};
+
+use std::{borrow::Cow, fs, path::Path};
+
+fn read_binary_asset(file_name: &str) -> Vec<u8> {
+    fs::read(
+        Path::new("tests")
+            .join("assets")
+            .join("binary")
+            .join(file_name),
+    )
+    .unwrap()
+}
+
+fn parse_err_kind(bytes: &[u8]) -> (usize, BinaryParseErrorKind) {
+    match binary::parse(bytes).unwrap_err() {
+        Error::BinaryParseError(BinaryParseError { offset, kind }) => (offset, kind),
+        other => panic!("Unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn shortcuts() {
+    let bytes = read_binary_asset("shortcuts.vdf");
+    let vdf = binary::parse(&bytes).unwrap();
+    assert_eq!(vdf.key, "shortcuts");
+
+    let shortcuts = vdf.value.get_obj().unwrap();
+    let first = shortcuts.get("0").unwrap()[0].get_obj().unwrap();
+    assert_eq!(
+        first.get("appid").unwrap()[0],
+        Value::Typed(TypedValue::Int(-1283938293))
+    );
+    assert_eq!(
+        first.get("AppName").unwrap()[0].get_str(),
+        Some("Non-Steam Game")
+    );
+
+    assert_snapshot!(vdf.to_string());
+}
+
+#[test]
+fn all_types() {
+    let bytes = read_binary_asset("all_types.vdf");
+    let vdf = Vdf::parse_binary(&bytes).unwrap();
+    let obj = vdf.value.get_obj().unwrap();
+    let get = |key| &obj.get(key).unwrap()[0];
+
+    assert_eq!(get("str").get_str(), Some("text"));
+    assert_eq!(get("int").get_typed(), Some(&TypedValue::Int(-42)));
+    assert_eq!(get("float").get_typed(), Some(&TypedValue::Float(1.5)));
+    assert_eq!(get("ptr").get_typed(), Some(&TypedValue::Ptr(0xdeadbeef)));
+    assert_eq!(
+        get("wide").get_typed(),
+        Some(&TypedValue::WideStr("wïde \"str\"".into()))
+    );
+    assert_eq!(
+        get("color").get_typed(),
+        Some(&TypedValue::Color([255, 128, 0, 255]))
+    );
+    assert_eq!(
+        get("uint64").get_typed(),
+        Some(&TypedValue::UInt64(76561197960287930))
+    );
+    assert_eq!(
+        get("int64").get_typed(),
+        Some(&TypedValue::Int64(-9000000000))
+    );
+
+    assert_snapshot!(vdf.to_string());
+}
+
+#[test]
+fn errors() {
+    assert_eq!(parse_err_kind(b""), (0, BinaryParseErrorKind::MissingPair));
+    assert_eq!(
+        parse_err_kind(b"\x08"),
+        (0, BinaryParseErrorKind::MissingPair)
+    );
+    assert_eq!(
+        parse_err_kind(b"\x00key\x00\x02int\x00\x01\x00"),
+        (10, BinaryParseErrorKind::UnexpectedEof)
+    );
+    assert_eq!(
+        parse_err_kind(b"\x00key\x00\x01unterminated"),
+        (18, BinaryParseErrorKind::UnexpectedEof)
+    );
+    assert_eq!(
+        parse_err_kind(b"\x00key\x00\x09weird\x00\x08"),
+        (5, BinaryParseErrorKind::InvalidType(0x09))
+    );
+    assert_eq!(
+        parse_err_kind(b"\x01key\x00\xffval\x00"),
+        (5, BinaryParseErrorKind::InvalidUtf8)
+    );
+    assert_eq!(
+        parse_err_kind(b"\x05key\x00\x00\xd8\x00\x00"),
+        (5, BinaryParseErrorKind::InvalidUtf16)
+    );
+    assert_eq!(
+        parse_err_kind(b"\x01key\x00val\x00\x08\x01trailing\x00\x00"),
+        (10, BinaryParseErrorKind::TrailingBytes)
+    );
+}
+
+#[test]
+fn depth_limit() {
+    // Far deeper than the stack could handle if reading objects wasn't limited
+    let mut bytes = b"\x00a\x00".repeat(1_000_000);
+    bytes.extend(std::iter::repeat(0x08).take(1_000_000));
+    assert_eq!(
+        parse_err_kind(&bytes),
+        (3 * 256 + 3, BinaryParseErrorKind::DepthExceeded)
+    );
+
+    let nested = b"\x00a\x00\x00b\x00\x01c\x00d\x00\x08\x08";
+    let limits = |max_depth| Limits {
+        max_depth,
+        ..Limits::new()
+    };
+    let err = Parser::new()
+        .limits(limits(Some(1)))
+        .parse_binary(nested)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::BinaryParseError(BinaryParseError {
+            offset: 6,
+            kind: BinaryParseErrorKind::DepthExceeded,
+        })
+    ));
+    let vdf = Parser::new()
+        .limits(limits(Some(2)))
+        .parse_binary(nested)
+        .unwrap();
+    assert_eq!(vdf, binary::parse(nested).unwrap());
+}
+
+#[test]
+fn render_deeply_nested() {
+    const DEPTH: usize = 2_000;
+
+    let mut value = Value::Str(Cow::from("Value"));
+    for _ in 0..DEPTH {
+        let mut obj = Obj::new();
+        obj.push(Cow::from("a"), value, None);
+        value = Value::Obj(obj);
+    }
+    let vdf = Vdf::new(Cow::from("a"), value);
+
+    let rendered = std::thread::scope(|s| {
+        std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn_scoped(s, || {
+                let mut rendered = Vec::new();
+                vdf.render_binary(&mut rendered).unwrap();
+                rendered
+            })
+            .unwrap()
+            .join()
+            .unwrap()
+    });
+    let mut expected = b"\x00a\x00".repeat(DEPTH);
+    expected.extend_from_slice(b"\x01a\x00Value\x00");
+    expected.extend(std::iter::repeat(0x08).take(DEPTH + 1));
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn render_round_trips_byte_for_byte() {
+    for file_name in ["shortcuts.vdf", "all_types.vdf"] {
+        let bytes = read_binary_asset(file_name);
+        let vdf = binary::parse(&bytes).unwrap();
+        let mut rendered = Vec::new();
+        vdf.render_binary(&mut rendered).unwrap();
+        assert_eq!(rendered, bytes, "{file_name}");
+    }
+}
+
+#[test]
+fn render_modified() {
+    let bytes = read_binary_asset("shortcuts.vdf");
+    let mut vdf = binary::parse(&bytes).unwrap();
+    let shortcut = vdf.value.get_mut_obj().unwrap().get_mut("1").unwrap()[0]
+        .get_mut_obj()
+        .unwrap();
+    shortcut.get_mut("IsHidden").unwrap()[0] = Value::Typed(TypedValue::Int(1));
+    shortcut.push(
+        "LaunchOptions".into(),
+        Value::Str("--fullscreen".into()),
+        None,
+    );
+
+    let mut rendered = Vec::new();
+    vdf.render_binary(&mut rendered).unwrap();
+    assert_eq!(
+        rendered.len(),
+        bytes.len() + "\x01LaunchOptions\0--fullscreen\0".len()
+    );
+    let reparsed = binary::parse(&rendered).unwrap();
+    assert_eq!(vdf, reparsed);
+}
+
+#[test]
+fn render_null_in_string() {
+    let vdf = Vdf::new("key".into(), Value::Str("nul\0byte".into()));
+    let err = vdf.render_binary(Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn appinfo() {
+    for (file_name, version) in [
+        ("appinfo_v27.vdf", 27),
+        ("appinfo_v28.vdf", 28),
+        ("appinfo_v29.vdf", 29),
+    ] {
+        let bytes = read_binary_asset(file_name);
+        let app_info = AppInfo::parse(&bytes).unwrap();
+        assert_eq!(app_info.version, version);
+        assert_eq!(app_info.universe, 1);
+
+        let app_ids: Vec<_> = app_info.entries().iter().map(|app| app.app_id).collect();
+        assert_eq!(app_ids, [440, 10], "{file_name}");
+        assert!(app_info.get(570).is_none());
+
+        let tf2 = app_info.get(440).unwrap();
+        assert_eq!(tf2.last_updated, 1700000000);
+        assert_eq!(tf2.change_number, 21000000);
+        let expected_checksum = if version >= 28 { Some(true) } else { None };
+        assert_eq!(tf2.verify_checksum(), expected_checksum, "{file_name}");
+
+        let vdf = app_info.vdf(tf2).unwrap();
+        assert_eq!(vdf.key, "appinfo");
+        let common = vdf.value.get_obj().unwrap().get("common").unwrap()[0]
+            .get_obj()
+            .unwrap();
+        assert_eq!(
+            common.get("name").unwrap()[0].get_str(),
+            Some("Team Fortress 2")
+        );
+    }
+}
+
+#[test]
+fn appinfo_checksum_mismatch() {
+    let mut bytes = read_binary_asset("appinfo_v28.vdf");
+    // Flip a byte in the last app's name
+    let pos = bytes.windows(6).position(|w| w == b"Strike").unwrap();
+    bytes[pos] = b's';
+
+    let app_info = AppInfo::parse(&bytes).unwrap();
+    assert_eq!(app_info.get(440).unwrap().verify_checksum(), Some(true));
+    let cs = app_info.get(10).unwrap();
+    assert_eq!(cs.verify_checksum(), Some(false));
+    // Decoding still works regardless
+    app_info.vdf(cs).unwrap();
+}
+
+#[test]
+fn appinfo_errors() {
+    let err_kind = |bytes: &[u8]| match AppInfo::parse(bytes).unwrap_err() {
+        Error::BinaryParseError(BinaryParseError { offset, kind }) => (offset, kind),
+        other => panic!("Unexpected error: {other:?}"),
+    };
+
+    assert_eq!(
+        err_kind(b"\x26\x44\x56\x07\x01\x00\x00\x00"),
+        (0, BinaryParseErrorKind::UnsupportedMagic(0x07564426))
+    );
+    assert_eq!(
+        err_kind(b"\x28\x44\x56\x07\x01\x00\x00\x00\x0a\x00\x00\x00\x04\x00\x00\x00"),
+        (12, BinaryParseErrorKind::InvalidEntrySize(4))
+    );
+
+    // Truncate in the middle of the first entry's text checksum
+    let bytes = read_binary_asset("appinfo_v28.vdf");
+    assert_eq!(
+        err_kind(&bytes[..40]),
+        (32, BinaryParseErrorKind::UnexpectedEof)
+    );
+
+    // Errors within a payload are reported relative to the whole file
+    let mut bytes = read_binary_asset("appinfo_v28.vdf");
+    let payload_start = 8 + 8 + 60;
+    bytes[payload_start] = 0x09;
+    let app_info = AppInfo::parse(&bytes).unwrap();
+    match app_info.vdf(app_info.get(440).unwrap()).unwrap_err() {
+        Error::BinaryParseError(BinaryParseError { offset, kind }) => {
+            assert_eq!(
+                (offset, kind),
+                (payload_start, BinaryParseErrorKind::InvalidType(0x09))
+            );
+        }
+        other => panic!("Unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn packageinfo() {
+    for (file_name, version) in [("packageinfo_v27.vdf", 27), ("packageinfo_v28.vdf", 28)] {
+        let bytes = read_binary_asset(file_name);
+        let package_info = PackageInfo::parse(&bytes).unwrap();
+        assert_eq!(package_info.version, version);
+        assert_eq!(package_info.universe, 1);
+
+        let package_ids: Vec<_> = package_info
+            .packages()
+            .iter()
+            .map(|package| package.package_id)
+            .collect();
+        assert_eq!(package_ids, [0, 469], "{file_name}");
+
+        let tf2 = package_info.get(469).unwrap();
+        assert_eq!(tf2.change_number, 1469);
+        assert_eq!(tf2.pics_token, (version >= 28).then_some(0));
+        assert_eq!(tf2.vdf.key, "469");
+        assert_eq!(tf2.app_ids(), [440]);
+        assert_eq!(tf2.depot_ids(), [441, 232251]);
+    }
+}
+
+#[test]
+fn packageinfo_many_ids() {
+    let bytes = read_binary_asset("packageinfo_many_ids.vdf");
+    let package_info = PackageInfo::parse(&bytes).unwrap();
+    let package = package_info.get(1000).unwrap();
+    assert_eq!(package.app_ids(), Vec::from_iter(2000..2012));
+    assert_eq!(package.depot_ids(), Vec::from_iter(3000..3012));
+}
+
+#[test]
+fn packageinfo_errors() {
+    let err_kind = |bytes: &[u8]| match PackageInfo::parse(bytes).unwrap_err() {
+        Error::BinaryParseError(BinaryParseError { offset, kind }) => (offset, kind),
+        other => panic!("Unexpected error: {other:?}"),
+    };
+
+    assert_eq!(
+        err_kind(b"\x28\x44\x56\x07\x01\x00\x00\x00"),
+        (0, BinaryParseErrorKind::UnsupportedMagic(0x07564428))
+    );
+
+    // Missing the end marker
+    let bytes = read_binary_asset("packageinfo_v28.vdf");
+    let truncated = &bytes[..bytes.len() - 4];
+    assert_eq!(
+        err_kind(truncated),
+        (truncated.len(), BinaryParseErrorKind::UnexpectedEof)
+    );
+}
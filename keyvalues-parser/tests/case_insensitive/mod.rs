@@ -0,0 +1,104 @@
+use keyvalues_parser::{Conditional, Obj, Parser, Value, Vdf};
+
+use std::borrow::Cow;
+
+fn str_value(s: &str) -> Value<'_> {
+    Value::Str(Cow::from(s))
+}
+
+fn mixed_case_obj() -> Obj<'static> {
+    let mut obj = Obj::new();
+    obj.push(Cow::from("installdir"), str_value("lower"), None);
+    obj.push(Cow::from("InstallDir"), str_value("exact"), None);
+    obj.push(Cow::from("LastOwner"), str_value("76561198000000000"), None);
+    obj
+}
+
+#[test]
+fn lookup() {
+    let obj = mixed_case_obj();
+    assert_eq!(obj.get_ci("INSTALLDIR"), Some(&vec![str_value("exact")]));
+    // An exact match wins even when another spelling comes first
+    assert_eq!(obj.get_ci("installdir"), Some(&vec![str_value("lower")]));
+    assert_eq!(
+        obj.key_ci("lastowner").map(AsRef::as_ref),
+        Some("LastOwner")
+    );
+    assert_eq!(obj.get_ci("Missing"), None);
+    // Only ASCII case gets folded
+    let mut obj = Obj::new();
+    obj.push(Cow::from("Straße"), str_value("value"), None);
+    assert!(obj.get_ci("STRASSE").is_none());
+    assert!(obj.get_ci("straße").is_some());
+}
+
+#[test]
+fn mutation() {
+    let mut obj = mixed_case_obj();
+    obj.get_mut_ci("LASTOWNER").unwrap()[0] = str_value("0");
+    assert_eq!(obj["LastOwner"], [str_value("0")]);
+    assert!(obj.get_mut_ci("Missing").is_none());
+
+    obj.push_ci(Cow::from("lastOwner"), str_value("1"), None);
+    obj.push_ci(Cow::from("NewKey"), str_value("new"), None);
+    assert_eq!(
+        obj.keys().collect::<Vec<_>>(),
+        ["InstallDir", "LastOwner", "NewKey", "installdir"]
+    );
+    assert_eq!(obj["LastOwner"], [str_value("0"), str_value("1")]);
+}
+
+#[test]
+fn parser_merges_keys() {
+    let text = r#"
+"AppState"
+{
+    "InstallDir" "Team Fortress 2" [$WIN32]
+    "UserConfig" { "language" "english" }
+    "installdir" "tf2"
+    "userconfig" { "LANGUAGE" "french" "BetaKey" "prerelease" }
+}
+"#;
+    let vdf = Parser::new()
+        .case_insensitive_keys(true)
+        .spans(true)
+        .parse(text)
+        .unwrap();
+    let obj = vdf.value.get_obj().unwrap();
+    assert_eq!(obj.keys().collect::<Vec<_>>(), ["InstallDir", "UserConfig"]);
+    assert_eq!(
+        obj["InstallDir"],
+        [str_value("Team Fortress 2"), str_value("tf2")]
+    );
+    assert_eq!(
+        obj.conditional("InstallDir", 0),
+        Some(&Conditional::symbol("WIN32"))
+    );
+    let span = obj.span("InstallDir", 1).unwrap();
+    assert_eq!(&text[span.value.range()], "\"tf2\"");
+
+    // Merging only happens within each object, so separate objects stay separate values
+    let configs = &obj["UserConfig"];
+    assert_eq!(configs.len(), 2);
+    let second = configs[1].get_obj().unwrap();
+    assert_eq!(second.keys().collect::<Vec<_>>(), ["BetaKey", "LANGUAGE"]);
+
+    // Rendering keeps the first spelling in the original order
+    let rendered = Vdf::from(vdf.clone()).to_string();
+    let reparsed = keyvalues_parser::parse(&rendered).unwrap();
+    let reparsed = reparsed.value.get_obj().unwrap();
+    let keys: Vec<_> = reparsed
+        .ordered_pairs()
+        .into_iter()
+        .map(|(key, _, _)| key.as_ref())
+        .collect();
+    assert_eq!(
+        keys,
+        ["InstallDir", "UserConfig", "InstallDir", "UserConfig"]
+    );
+
+    // And it's case-sensitive by default
+    let vdf = keyvalues_parser::parse(text).unwrap();
+    let obj = vdf.value.get_obj().unwrap();
+    assert_eq!(obj.len(), 4);
+}
@@ -0,0 +1,116 @@
+use keyvalues_parser::{Conditional, Value, Vdf};
+
+use std::borrow::Cow;
+
+fn sym(name: &str) -> Box<Conditional<'_>> {
+    Box::new(Conditional::symbol(name))
+}
+
+#[test]
+fn parse_expressions() {
+    let cases = [
+        ("[$WIN32]", Conditional::symbol("WIN32")),
+        ("[!$X360]", Conditional::Not(sym("X360"))),
+        ("[ $A||$B ]", Conditional::Or(sym("A"), sym("B"))),
+        (
+            "[$A || $B && !$C]",
+            Conditional::Or(
+                sym("A"),
+                Box::new(Conditional::And(
+                    sym("B"),
+                    Box::new(Conditional::Not(sym("C"))),
+                )),
+            ),
+        ),
+        (
+            "[($A || $B) && $C]",
+            Conditional::And(Box::new(Conditional::Or(sym("A"), sym("B"))), sym("C")),
+        ),
+    ];
+
+    for (cond_text, expected) in cases {
+        let vdf_text = format!("key value {cond_text}");
+        let vdf = Vdf::parse(&vdf_text).unwrap();
+        assert_eq!(vdf.conditional.as_ref(), Some(&expected), "{cond_text}");
+
+        // Rendering goes back through the parser to the same conditional
+        let rendered = vdf.to_string();
+        let reparsed = Vdf::parse(&rendered).unwrap();
+        assert_eq!(vdf, reparsed);
+    }
+}
+
+#[test]
+fn non_conditional_brackets_are_still_strings() {
+    let vdf = Vdf::parse("outer { [key] [value] }").unwrap();
+    let obj = vdf.value.unwrap_obj();
+    assert_eq!(obj.get("[key]").unwrap()[0].get_str(), Some("[value]"));
+    assert_eq!(obj.conditional("[key]", 0), None);
+}
+
+#[test]
+fn invalid_conditional_after_obj_key() {
+    Vdf::parse("outer [$WIN32 &&] { }").unwrap_err();
+    Vdf::parse("outer [$] { }").unwrap_err();
+}
+
+#[test]
+fn raw_parse_conditionals() {
+    let vdf = Vdf::parse_raw(r#""path" "C:\Games\" [$WIN32]"#).unwrap();
+    assert_eq!(vdf.conditional, Some(Conditional::symbol("$WIN32")));
+    assert_eq!(vdf.value.get_str(), Some(r"C:\Games\"));
+}
+
+#[test]
+fn resolve_conditionals() {
+    let vdf_text = include_str!("../assets/conditionals.vdf");
+    let vdf = Vdf::parse(vdf_text).unwrap();
+
+    let linux = vdf
+        .clone()
+        .resolve_conditionals(&["LINUX", "POSIX", "DECK"])
+        .unwrap();
+    let hud = linux.value.get_obj().unwrap();
+    let health = hud.get("HudHealth").unwrap();
+    assert_eq!(health.len(), 1);
+    let health = health[0].get_obj().unwrap();
+    let xpos = health.get("xpos").unwrap();
+    assert_eq!(xpos, &[Value::Str(Cow::from("8"))]);
+    assert!(hud.get("HudAmmo").is_some());
+    // Everything left is unconditional
+    assert_eq!(linux.to_string().matches('[').count(), 0);
+
+    let windows = vdf.resolve_conditionals(&["win32"]).unwrap();
+    let health = &windows.value.get_obj().unwrap().get("HudHealth").unwrap()[0];
+    let health = health.get_obj().unwrap();
+    assert_eq!(health.get("xpos").unwrap().len(), 1);
+    assert!(health.get("visible").is_some());
+    assert!(health.get("enabled").is_none());
+}
+
+#[test]
+fn top_level_pair_excluded() {
+    let vdf = Vdf::parse("key value [$X360]").unwrap();
+    assert_eq!(vdf.resolve_conditionals(&["WIN32"]), None);
+}
+
+#[test]
+fn removing_values_keeps_conditionals_in_sync() {
+    let vdf = Vdf::parse(r#"Outer { a "1" [$WIN32] a "2" b "3" [$X360] }"#).unwrap();
+    let mut obj = vdf.value.unwrap_obj();
+
+    assert_eq!(obj.remove_value("a", 0), Some(Value::Str(Cow::from("1"))));
+    assert_eq!(obj.conditional("a", 0), None);
+    assert_eq!(obj.remove_value("a", 1), None);
+    let vdf = Vdf::new(Cow::from("Outer"), Value::Obj(obj.clone()));
+    assert_eq!(
+        vdf.to_string(),
+        "\"Outer\"\n{\n\t\"a\"\t\"2\"\n\t\"b\"\t\"3\" [$X360]\n}\n"
+    );
+
+    // Replacing a key's values drops its old conditionals too
+    obj.insert(Cow::from("b"), vec![Value::Str(Cow::from("4"))]);
+    assert_eq!(obj.conditional("b", 0), None);
+    assert_eq!(obj.remove("b"), Some(vec![Value::Str(Cow::from("4"))]));
+    assert_eq!(obj.remove("b"), None);
+}
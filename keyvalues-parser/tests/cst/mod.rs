@@ -0,0 +1,358 @@
+use crate::utils::read_asset_file;
+
+use insta::assert_snapshot;
+use keyvalues_parser::{
+    cst::{self, Document},
+    error::Error,
+    Conditional, DuplicateKeys, Limits, Newline, Obj, Parser, Renderer, Separator, Value,
+};
+
+use std::borrow::Cow;
+
+const HUD: &str = r#"// Health panel
+"Resource/HudLayout.res"
+{
+	// Position
+	"xpos"		"10"	// from the left
+	"ypos"		"20" [$WIN32]
+	wide 100
+
+	"Colors" [!$X360]
+	{
+		"fg"	"255 255 255 255"
+	}
+}
+"#;
+
+#[test]
+fn round_trips_assets() {
+    let assets = [
+        "base_multiple.vdf",
+        "base_quoted.vdf",
+        "base_unquoted.vdf",
+        "basic.vdf",
+        "comments.vdf",
+        "compact.vdf",
+        "conditionals.vdf",
+        "include_mixed.vdf",
+        "null_byte.vdf",
+        "special_characters.vdf",
+        "unquoted_strings.vdf",
+    ];
+    for asset in assets {
+        let text = read_asset_file(asset);
+        let doc = Document::parse(&text).unwrap();
+        assert_eq!(doc.to_string(), text, "{asset}");
+        assert_eq!(
+            doc.to_vdf(),
+            keyvalues_parser::parse(&text).unwrap(),
+            "{asset}"
+        );
+    }
+
+    let parser = Parser::new().literal_special_chars(true);
+    for asset in ["base_multiple_raw_strings.vdf", "raw_strings.vdf"] {
+        let text = read_asset_file(asset);
+        let doc = parser.parse_cst(&text).unwrap();
+        assert_eq!(doc.to_string(), text, "{asset}");
+        assert_eq!(doc.to_vdf(), parser.parse(&text).unwrap(), "{asset}");
+    }
+}
+
+#[test]
+fn conditional_after_obj() {
+    let text = "a\n{\n\tb c\n} [$X] // after\n";
+    let mut doc = Document::parse(text).unwrap();
+    assert_eq!(doc.to_string(), text);
+    assert_eq!(doc.to_vdf(), keyvalues_parser::parse(text).unwrap());
+
+    // It stays after the object when changed
+    doc.pair_mut()
+        .set_conditional(Some(Conditional::symbol("Y")));
+    assert_eq!(doc.to_string(), "a\n{\n\tb c\n} [$Y] // after\n");
+    doc.pair_mut().set_conditional(None);
+    assert_eq!(doc.to_string(), "a\n{\n\tb c\n} // after\n");
+
+    let formatted = check_format(&Parser::new(), text, &Renderer::new());
+    assert_eq!(formatted, "\"a\" [$X]\n{\n\t\"b\"\t\"c\"\n} // after\n");
+}
+
+#[test]
+fn same_errors_as_parser() {
+    let texts = [
+        "",
+        "}",
+        "Key",
+        "Key \"C:\\Games\"",
+        "Key Value\nOther Value",
+        "Key Value\0\n}",
+        "Outer\n{\n\tInner\n}",
+        r#""key" { "unclosed" "obj" "#,
+        "Key\n{\n\t\"unterminated\" \"value\n}",
+        "Key { { } }",
+        "#base \"unterminated",
+        "Key { A 1 a 2 A 3 }",
+    ];
+    let limits = Limits {
+        max_depth: Some(2),
+        max_string_len: Some(8),
+        max_pairs: Some(4),
+        ..Limits::new()
+    };
+    let parsers = [
+        Parser::new(),
+        Parser::new().literal_special_chars(true),
+        Parser::new().duplicate_keys(DuplicateKeys::Error),
+        Parser::new()
+            .duplicate_keys(DuplicateKeys::Error)
+            .case_insensitive_keys(true),
+        Parser::new().limits(limits),
+        Parser::new().limits(Limits {
+            max_input_len: Some(8),
+            ..Limits::new()
+        }),
+    ];
+    let limit_texts = [
+        "A { B { C { D E } } }",
+        "Key \"A long value\"",
+        "A { B 1 C 2 D 3 E 4 }",
+        "Key Value [!($A && !$B)]",
+    ];
+    for parser in &parsers {
+        for text in texts.iter().chain(&limit_texts) {
+            match (parser.parse_cst(text), parser.parse(text)) {
+                (Ok(_), Ok(_)) => {}
+                (Err(Error::ParseError(cst_err)), Err(Error::ParseError(err))) => {
+                    assert_eq!(cst_err, err, "{text:?}");
+                }
+                (cst, parsed) => panic!("Mismatch for {text:?}: {cst:?} {parsed:?}"),
+            }
+        }
+    }
+}
+
+#[test]
+fn set_values() {
+    let mut doc = Document::parse(HUD).unwrap();
+    let obj = doc.pair_mut().value_mut().get_mut_obj().unwrap();
+    obj.get_mut("xpos")
+        .unwrap()
+        .set_value(Value::Str(Cow::from("15")));
+    // Unquoted strings stay unquoted when possible
+    obj.get_mut("wide")
+        .unwrap()
+        .set_value(Value::Str(Cow::from("200")));
+    let colors = obj.get_mut("Colors").unwrap();
+    colors.set_conditional(None);
+    colors
+        .value_mut()
+        .get_mut_obj()
+        .unwrap()
+        .get_mut("fg")
+        .unwrap()
+        .set_value(Value::Str(Cow::from("0 \"0\" 0 255")));
+    obj.get_mut("ypos")
+        .unwrap()
+        .set_conditional(Some(Conditional::symbol("LINUX")));
+
+    assert_snapshot!(doc.to_string());
+}
+
+#[test]
+fn unchanged_values_keep_formatting() {
+    let mut doc = Document::parse(HUD).unwrap();
+    let obj = doc.pair_mut().value_mut().get_mut_obj().unwrap();
+    obj.get_mut("xpos")
+        .unwrap()
+        .set_value(Value::Str(Cow::from("10")));
+    assert_eq!(doc.to_string(), HUD);
+}
+
+#[test]
+fn str_to_obj_and_back() {
+    let mut doc = Document::parse(HUD).unwrap();
+    let obj = doc.pair_mut().value_mut().get_mut_obj().unwrap();
+
+    let mut nested = Obj::new();
+    nested.push(Cow::from("x"), Value::Str(Cow::from("1")), None);
+    obj.get_mut("wide").unwrap().set_value(Value::Obj(nested));
+    obj.get_mut("Colors")
+        .unwrap()
+        .set_value(Value::Str(Cow::from("none")));
+
+    assert_snapshot!(doc.to_string());
+    assert_eq!(
+        doc.to_vdf(),
+        keyvalues_parser::parse(&doc.to_string()).unwrap()
+    );
+}
+
+#[test]
+fn insert_and_remove_pairs() {
+    let mut doc = Document::parse(HUD).unwrap();
+    let obj = doc.pair_mut().value_mut().get_mut_obj().unwrap();
+
+    // The trailing comment stays with `xpos`
+    let xpos = obj.position("xpos").unwrap();
+    obj.insert_after(xpos, Cow::from("zpos"), Value::Str(Cow::from("5")));
+    let colors = obj.get_mut("Colors").unwrap();
+    let colors = colors.value_mut().get_mut_obj().unwrap();
+    colors.push(Cow::from("bg"), Value::Str(Cow::from("0 0 0 0")));
+    let mut nested = Obj::new();
+    nested.push(Cow::from("inner"), Value::Str(Cow::from("value")), None);
+    obj.push(Cow::from("Nested"), Value::Obj(nested));
+
+    assert_snapshot!("insert", doc.to_string());
+
+    // Removing `xpos` also removes the comments above and trailing it
+    let obj = doc.pair_mut().value_mut().get_mut_obj().unwrap();
+    let removed = obj.remove(obj.position("xpos").unwrap());
+    assert_eq!(removed.key(), "xpos");
+    let ypos = obj.position("ypos").unwrap();
+    obj.remove(ypos);
+
+    assert_snapshot!("remove", doc.to_string());
+    assert_eq!(
+        doc.to_vdf(),
+        keyvalues_parser::parse(&doc.to_string()).unwrap()
+    );
+}
+
+#[test]
+fn insert_into_empty_objs() {
+    let mut doc = Document::parse("Outer\n{\n\tInline {}\n\tBlock\n\t{\n\t}\n}\n").unwrap();
+    let obj = doc.pair_mut().value_mut().get_mut_obj().unwrap();
+    for key in ["Inline", "Block"] {
+        let inner = obj.get_mut(key).unwrap().value_mut().get_mut_obj().unwrap();
+        inner.push(Cow::from("key"), Value::Str(Cow::from("value")));
+    }
+
+    assert_eq!(
+        doc.to_string(),
+        "Outer\n{\n\tInline { \"key\"\t\"value\" }\n\tBlock\n\t{\n\t\t\"key\"\t\"value\"\n\t}\n}\n"
+    );
+}
+
+#[test]
+fn raw_render_errors() {
+    let parser = Parser::new().literal_special_chars(true);
+    let mut doc = parser.parse_cst(r#"Key "C:\Games""#).unwrap();
+    doc.pair_mut()
+        .set_value(Value::Str(Cow::from("\"quoted\"")));
+    assert!(doc.render(&mut String::new()).is_err());
+}
+
+// Every comment in the text. Strings containing `//` show up too, but they show up the same way
+fn comments(text: &str) -> Vec<&str> {
+    text.lines()
+        .filter_map(|line| line.find("//").map(|i| line[i..].trim_end()))
+        .collect()
+}
+
+// Formats the text checking that formatting is idempotent, round-trip safe, and keeps comments
+fn check_format(parser: &Parser, text: &str, renderer: &Renderer) -> String {
+    let mut doc = parser.parse_cst(text).unwrap();
+    doc.format(renderer);
+    let formatted = doc.to_string();
+
+    let mut reformatted = parser.parse_cst(&formatted).unwrap();
+    reformatted.format(renderer);
+    assert_eq!(reformatted.to_string(), formatted, "Not idempotent");
+    assert_eq!(
+        parser.parse(&formatted).unwrap(),
+        parser.parse(text).unwrap(),
+        "{formatted}"
+    );
+    assert_eq!(comments(&formatted), comments(text), "{formatted}");
+    formatted
+}
+
+const MESSY: &str = "
+
+// Health panel
+#base   base.res // shared settings
+#include \"extra.res\"
+\"Resource/HudLayout.res\" {
+  // Position
+
+
+  xpos \"10\"   // from the left
+  \"ypos\"		\"20\"	[$WIN32]
+
+
+
+       wide    100
+  \"Colors\" [!$X360] { fg \"255 255 255 255\" }
+  \"Escapes\" \"tab\\there\"
+  \"Empty\" {
+
+  // Nothing yet
+
+  }
+  \"Commented\" // value on the next line
+  \"value\"
+
+}
+
+
+// The end
+";
+
+#[test]
+fn format_canonical() {
+    let formatted = check_format(&Parser::new(), MESSY, &Renderer::new());
+    assert_snapshot!(formatted);
+    assert_eq!(cst::format(MESSY).unwrap(), formatted);
+}
+
+#[test]
+fn format_custom_style() {
+    let renderer = Renderer::new()
+        .indent("    ")
+        .separator(Separator::Aligned)
+        .quote_only_when_needed(true)
+        .newline(Newline::CrLf);
+    let formatted = check_format(&Parser::new(), MESSY, &renderer);
+    assert_eq!(
+        formatted.matches('\n').count(),
+        formatted.matches("\r\n").count()
+    );
+    assert_snapshot!(formatted.replace("\r\n", "\n"));
+}
+
+#[test]
+fn format_assets() {
+    let assets = [
+        "base_multiple.vdf",
+        "base_quoted.vdf",
+        "base_unquoted.vdf",
+        "basic.vdf",
+        "comments.vdf",
+        "compact.vdf",
+        "conditionals.vdf",
+        "include_mixed.vdf",
+        "null_byte.vdf",
+        "special_characters.vdf",
+        "unquoted_strings.vdf",
+    ];
+    let renderers = [
+        Renderer::new(),
+        Renderer::new()
+            .separator(Separator::Aligned)
+            .quote_only_when_needed(true),
+    ];
+    for asset in assets {
+        let text = read_asset_file(asset);
+        for renderer in &renderers {
+            check_format(&Parser::new(), &text, renderer);
+        }
+    }
+
+    let parser = Parser::new().literal_special_chars(true);
+    for asset in ["base_multiple_raw_strings.vdf", "raw_strings.vdf"] {
+        let text = read_asset_file(asset);
+        for renderer in &renderers {
+            check_format(&parser, &text, renderer);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use insta::assert_snapshot;
+use keyvalues_parser::{
+    dmx::{self, Attribute, AttributeType, Dmx, Element, Header, Value},
+    error::{DmxParseError, DmxParseErrorKind, Error},
+    Limits,
+};
+
+use std::{fs, path::Path};
+
+fn read_dmx_asset(file_name: &str) -> String {
+    fs::read_to_string(
+        Path::new("tests")
+            .join("assets")
+            .join("dmx")
+            .join(file_name),
+    )
+    .unwrap()
+}
+
+fn parse_err(text: &str) -> (usize, usize, DmxParseErrorKind) {
+    match dmx::parse(text).unwrap_err() {
+        Error::DmxParseError(DmxParseError { line, col, kind }) => (line, col, kind),
+        other => panic!("Unexpected error: {other:?}"),
+    }
+}
+
+fn get_element(attr: Option<&Attribute<'_>>) -> dmx::ElementId {
+    match attr {
+        Some(Attribute::Value(Value::Element(Some(id)))) => *id,
+        other => panic!("Expected an element reference. Found: {other:?}"),
+    }
+}
+
+#[test]
+fn parse_session() {
+    let text = read_dmx_asset("session.dmx");
+    let dmx = dmx::parse(&text).unwrap();
+    assert_eq!(dmx.header.format, "sfm_session");
+    assert_eq!(dmx.header.format_version, 22);
+    assert_eq!(dmx.elements().count(), 4);
+
+    let root = &dmx[dmx.root().unwrap()];
+    assert_eq!(
+        root.get("name"),
+        Some(&Attribute::Value(Value::Str("session".into())))
+    );
+
+    // References by id and inline elements both resolve
+    let active_clip = get_element(root.get("activeClip"));
+    assert_eq!(dmx[active_clip].class, "DmeFilmClip");
+    assert_eq!(
+        dmx.find("8a5f3c2e-0000-0000-0000-000000000003"),
+        Some(active_clip)
+    );
+    assert_eq!(
+        get_element(dmx[active_clip].get("parent")),
+        dmx.root().unwrap()
+    );
+    let Some(Attribute::Array(AttributeType::Element, clips)) = root.get("clipBin") else {
+        panic!("Missing clip bin");
+    };
+    assert_eq!(clips[0], Value::Element(Some(active_clip)));
+    let Value::Element(Some(inline_clip)) = clips[1] else {
+        panic!("Missing inline clip");
+    };
+    assert_eq!(
+        dmx[inline_clip].get("frames"),
+        Some(&Attribute::Array(
+            AttributeType::Int,
+            vec![Value::Int(1), Value::Int(2), Value::Int(3)]
+        ))
+    );
+
+    let settings = &dmx[get_element(root.get("settings"))];
+    let value = |name| match settings.get(name) {
+        Some(Attribute::Value(value)) => value.clone(),
+        other => panic!("Unexpected attribute: {other:?}"),
+    };
+    assert_eq!(value("enabled"), Value::Bool(true));
+    assert_eq!(value("duration"), Value::Time(12.5));
+    assert_eq!(value("tint"), Value::Color([255, 128, 0, 255]));
+    assert_eq!(value("offset"), Value::Vector2([0.5, -1.0]));
+    assert_eq!(value("steamId"), Value::UInt64(76561197960287930));
+    assert_eq!(value("blob"), Value::Binary(vec![0xde, 0xad, 0xbe, 0xef]));
+    assert_eq!(value("title"), Value::Str("A \"quoted\" title".into()));
+    assert_eq!(value("nothing"), Value::Element(None));
+}
+
+#[test]
+fn render_session() {
+    let text = read_dmx_asset("session.dmx");
+    let dmx = dmx::parse(&text).unwrap();
+    let rendered = dmx.to_string();
+    assert_snapshot!(rendered);
+
+    let reparsed = dmx::parse(&rendered).unwrap();
+    assert_eq!(dmx, reparsed);
+}
+
+#[test]
+fn render_modified() {
+    let text = read_dmx_asset("session.dmx");
+    let mut dmx = dmx::parse(&text).unwrap();
+
+    let mut camera = Element::new("DmeCamera", "8a5f3c2e-0000-0000-0000-000000000005");
+    camera.set("fieldOfView", Attribute::Value(Value::Float(75.0)));
+    let camera = dmx.push(camera);
+    let root = dmx.root().unwrap();
+    dmx[root].set("camera", Attribute::Value(Value::Element(Some(camera))));
+    dmx[root].set("name", Attribute::Value(Value::Str("renamed".into())));
+
+    let rendered = dmx.to_string();
+    let reparsed = dmx::parse(&rendered).unwrap();
+    assert_eq!(dmx, reparsed);
+    // The camera is only referenced once, so it's written inline
+    assert!(rendered.contains("\"camera\" \"DmeCamera\"\n\t{"));
+}
+
+#[test]
+fn render_flat() {
+    let text = read_dmx_asset("session.dmx");
+    let mut dmx = dmx::parse(&text).unwrap();
+    dmx.header = Header {
+        encoding: "keyvalues2_flat".into(),
+        ..Header::default()
+    };
+
+    let rendered = dmx.to_string();
+    assert!(!rendered.contains("\"DmElement\"\n\t{"));
+    assert_eq!(dmx::parse(&rendered).unwrap(), dmx);
+}
+
+// A chain of elements that alternates between being inline within an attribute and an array
+fn nested_dmx(depth: usize) -> Dmx<'static> {
+    let mut dmx = Dmx::default();
+    let mut parent = dmx.push(Element::new("DmElement", "0"));
+    for i in 1..depth {
+        let child = dmx.push(Element::new("DmElement", i.to_string()));
+        let attr = if i % 2 == 0 {
+            Attribute::Value(Value::Element(Some(child)))
+        } else {
+            let values = vec![Value::Element(Some(child)), Value::Element(None)];
+            Attribute::Array(AttributeType::Element, values)
+        };
+        dmx[parent].set("child", attr);
+        parent = child;
+    }
+    dmx
+}
+
+#[test]
+fn render_deeply_nested() {
+    let dmx = nested_dmx(2_000);
+    let rendered = std::thread::scope(|s| {
+        std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn_scoped(s, || dmx.to_string())
+            .unwrap()
+            .join()
+            .unwrap()
+    });
+    assert_eq!(rendered.matches("\"elementid\"").count(), 2_000);
+    assert!(rendered.contains("\t\"id\" \"elementid\" \"1999\"\n"));
+    assert!(rendered.ends_with("\t\t},\n\t\t\"element\" \"\"\n\t]\n}\n"));
+}
+
+#[test]
+fn depth_limit() {
+    let max = Limits::DEFAULT_MAX_DEPTH;
+    let dmx = nested_dmx(max);
+    assert_eq!(dmx::parse(&dmx.to_string()).unwrap(), dmx);
+    let too_deep = nested_dmx(max + 1).to_string();
+    assert_eq!(parse_err(&too_deep).2, DmxParseErrorKind::DepthExceeded);
+
+    // Far deeper than the stack could handle if parsing wasn't limited
+    let text = format!("\"r\" {{ {}", "\"c\" \"b\" {".repeat(200_000));
+    assert_eq!(
+        parse_err(&text),
+        (1, 6 + 9 * max, DmxParseErrorKind::DepthExceeded)
+    );
+}
+
+#[test]
+fn errors() {
+    assert_eq!(
+        parse_err("<!-- dmx encoding binary 5 format dmx 1 -->"),
+        (1, 1, DmxParseErrorKind::InvalidHeader)
+    );
+    assert_eq!(
+        parse_err("\"DmElement\"\n{\n\t\"name\" \"string\" \"x\"\n}"),
+        (2, 1, DmxParseErrorKind::MissingId)
+    );
+    assert_eq!(
+        parse_err("\"DmElement\"\n{\n\t\"id\" \"elementid\" \"a\"\n\t\"x\" \"strin\" \"y\"\n}"),
+        (4, 6, DmxParseErrorKind::UnknownType)
+    );
+    assert_eq!(
+        parse_err("\"DmElement\"\n{\n\t\"id\" \"elementid\" \"a\"\n\t\"x\" \"vector3\" \"1 2\"\n}"),
+        (
+            4,
+            16,
+            DmxParseErrorKind::InvalidValue(AttributeType::Vector3)
+        )
+    );
+    assert_eq!(
+        parse_err("\"DmElement\"\n{\n\t\"id\" \"elementid\" \"a\"\n\t\"x\" \"element\" \"b\"\n}"),
+        (4, 16, DmxParseErrorKind::UnresolvedReference)
+    );
+    assert_eq!(
+        parse_err(
+            "\"DmElement\" { \"id\" \"elementid\" \"a\" }\n\"DmElement\" { \"id\" \"elementid\" \"a\" }"
+        ),
+        (2, 13, DmxParseErrorKind::DuplicateId)
+    );
+    assert_eq!(
+        parse_err("\"DmElement\" { \"id\" \"elementid\" \"a\\q\" }"),
+        (1, 35, DmxParseErrorKind::InvalidEscape('q'))
+    );
+    // The end of the header overlaps with its start
+    assert_eq!(parse_err("<!-->"), (1, 1, DmxParseErrorKind::InvalidHeader));
+}
+
+#[test]
+fn empty() {
+    let dmx = dmx::parse("<!-- dmx encoding keyvalues2 1 format dmx 1 -->\n").unwrap();
+    assert_eq!(dmx, Dmx::default());
+    assert!(dmx.root().is_none());
+}
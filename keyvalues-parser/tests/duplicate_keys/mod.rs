@@ -0,0 +1,154 @@
+use insta::assert_snapshot;
+use keyvalues_parser::{
+    error::{Error, ParseErrorKind},
+    Conditional, DuplicateKeys, Obj, Parser, Vdf,
+};
+
+const TEXT: &str = r#"
+"Settings"
+{
+    "Volume" "80"
+    "Video"
+    {
+        "Width" "1920"
+        "Height" "1080"
+    }
+    "Volume" "20" [$WIN32]
+    "Video"
+    {
+        "Height" "1440"
+        "Vsync" "1"
+    }
+}
+"#;
+
+fn parse_with(policy: DuplicateKeys) -> Vdf<'static> {
+    let vdf = Parser::new().duplicate_keys(policy).parse(TEXT).unwrap();
+    Vdf::from(vdf).into_owned()
+}
+
+fn strs<'a>(obj: &'a Obj<'_>, key: &str) -> Vec<&'a str> {
+    obj[key]
+        .iter()
+        .map(|value| value.get_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn keep_all() {
+    let vdf = parse_with(DuplicateKeys::KeepAll);
+    let obj = vdf.value.get_obj().unwrap();
+    assert_eq!(strs(obj, "Volume"), ["80", "20"]);
+    assert_eq!(obj["Video"].len(), 2);
+}
+
+#[test]
+fn first_wins() {
+    let vdf = parse_with(DuplicateKeys::FirstWins);
+    let obj = vdf.value.get_obj().unwrap();
+    assert_eq!(strs(obj, "Volume"), ["80"]);
+    assert_eq!(obj.conditional("Volume", 0), None);
+    let video = obj["Video"][0].get_obj().unwrap();
+    assert_eq!(strs(video, "Height"), ["1080"]);
+    assert!(!video.contains_key("Vsync"));
+}
+
+#[test]
+fn last_wins() {
+    let vdf = parse_with(DuplicateKeys::LastWins);
+    let obj = vdf.value.get_obj().unwrap();
+    assert_eq!(strs(obj, "Volume"), ["20"]);
+    assert_eq!(
+        obj.conditional("Volume", 0),
+        Some(&Conditional::symbol("WIN32"))
+    );
+    let video = obj["Video"][0].get_obj().unwrap();
+    assert_eq!(video.keys().collect::<Vec<_>>(), ["Height", "Vsync"]);
+    // The pairs stay where they were first used
+    assert_snapshot!(vdf.to_string());
+}
+
+#[test]
+fn merge_objs() {
+    let vdf = Parser::new()
+        .duplicate_keys(DuplicateKeys::MergeObjs)
+        .spans(true)
+        .parse(TEXT)
+        .unwrap();
+    let obj = vdf.value.get_obj().unwrap();
+    assert_eq!(strs(obj, "Volume"), ["20"]);
+    let video = obj["Video"][0].get_obj().unwrap();
+    assert_eq!(strs(video, "Width"), ["1920"]);
+    assert_eq!(strs(video, "Height"), ["1440"]);
+    assert_eq!(strs(video, "Vsync"), ["1"]);
+
+    // Spans follow the values that were kept
+    let span = video.span("Height", 0).unwrap();
+    assert_eq!(&TEXT[span.value.range()], "\"1440\"");
+    let span = video.span("Width", 0).unwrap();
+    assert_eq!(&TEXT[span.value.range()], "\"1920\"");
+
+    assert_snapshot!(Vdf::from(vdf).to_string());
+}
+
+#[test]
+fn merge_objs_replaces_mismatched_values() {
+    let text = "Outer { Inner { Key Value } Inner Str Other { } Other { Nested Value } }";
+    let vdf = Parser::new()
+        .duplicate_keys(DuplicateKeys::MergeObjs)
+        .parse(text)
+        .unwrap();
+    let obj = vdf.value.get_obj().unwrap();
+    assert_eq!(strs(obj, "Inner"), ["Str"]);
+    let other = obj["Other"][0].get_obj().unwrap();
+    assert_eq!(strs(other, "Nested"), ["Value"]);
+}
+
+#[test]
+fn error() {
+    let parser = Parser::new().duplicate_keys(DuplicateKeys::Error);
+    let Error::ParseError(err) = parser.parse(TEXT).unwrap_err() else {
+        panic!("Expected a parse error");
+    };
+    let ParseErrorKind::DuplicateKey { first } = err.kind() else {
+        panic!("Unexpected kind: {:?}", err.kind());
+    };
+    assert_eq!((first.line, first.col), (4, 5));
+    assert_eq!((err.line(), err.col()), (10, 5));
+    assert_snapshot!(err.snippet().to_string());
+
+    // Lenient parsing reports every duplicate and keeps the first values
+    let recovered = parser.parse_lenient(TEXT);
+    let positions: Vec<_> = recovered
+        .errors
+        .iter()
+        .map(|err| (err.line(), err.col()))
+        .collect();
+    assert_eq!(positions, [(10, 5), (11, 5)]);
+    let vdf = recovered.vdf.unwrap();
+    let obj = vdf.value.get_obj().unwrap();
+    assert_eq!(strs(obj, "Volume"), ["80"]);
+
+    // Nested objects track their own keys
+    assert!(parser.parse("A { B { Key 1 } C { Key 2 } }").is_ok());
+}
+
+#[test]
+fn error_same_line() {
+    let parser = Parser::new().duplicate_keys(DuplicateKeys::Error);
+    let Error::ParseError(err) = parser.parse("Config { Volume 80 Volume 20 }").unwrap_err() else {
+        panic!("Expected a parse error");
+    };
+    assert_snapshot!(err.snippet().to_string());
+}
+
+#[test]
+fn with_case_insensitive_keys() {
+    let parser = Parser::new()
+        .case_insensitive_keys(true)
+        .duplicate_keys(DuplicateKeys::LastWins);
+    let vdf = parser.parse("Config { Volume 80 VOLUME 20 }").unwrap();
+    let obj = vdf.value.get_obj().unwrap();
+    assert_eq!(obj.keys().collect::<Vec<_>>(), ["Volume"]);
+    assert_eq!(strs(obj, "Volume"), ["20"]);
+}
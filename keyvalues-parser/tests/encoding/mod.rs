@@ -0,0 +1,95 @@
+use crate::utils::read_asset_file;
+
+use keyvalues_parser::{
+    error::{Error, ParseErrorKind},
+    Encoding, Parser, PartialVdf,
+};
+
+fn parse_err(parser: &Parser, bytes: &[u8]) -> (usize, usize, ParseErrorKind) {
+    match parser.parse_bytes(bytes).unwrap_err() {
+        Error::ParseError(err) => (err.line(), err.col(), err.kind()),
+        other => panic!("Unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn round_trips_each_encoding() {
+    let text = read_asset_file("special_characters.vdf");
+    let expected = keyvalues_parser::parse(&text).unwrap();
+    let encodings = [
+        Encoding::Utf8,
+        Encoding::Utf8Bom,
+        Encoding::Utf16Le { bom: true },
+        Encoding::Utf16Le { bom: false },
+        Encoding::Utf16Be { bom: true },
+        Encoding::Utf16Be { bom: false },
+    ];
+    for encoding in encodings {
+        let bytes = encoding.encode(&text);
+        let vdf = Parser::new().parse_bytes(&bytes).unwrap();
+        assert_eq!(vdf.encoding, encoding);
+        assert_eq!(
+            vdf,
+            PartialVdf {
+                encoding,
+                ..expected.clone()
+            }
+        );
+
+        let mut rendered = String::new();
+        vdf.render(&mut rendered).unwrap();
+        assert_eq!(vdf.render_bytes().unwrap(), encoding.encode(&rendered));
+    }
+}
+
+#[test]
+fn str_input_is_plain_utf8() {
+    let vdf = keyvalues_parser::parse("Key Value").unwrap();
+    assert_eq!(vdf.encoding, Encoding::Utf8);
+}
+
+#[test]
+fn spans_skip_the_bom() {
+    let text = "Key Value";
+    let bytes = Encoding::Utf8Bom.encode(text);
+    let vdf = Parser::new().spans(true).parse_bytes(&bytes).unwrap();
+    let span = vdf.spans.unwrap().pair.value;
+    assert_eq!(&text[span.range()], "Value");
+}
+
+#[test]
+fn invalid_utf8() {
+    let bytes = b"\"Persona\"\n{\n\t\"Name\" \"Bad\xFF\xFEName\"\n}";
+    assert_eq!(
+        parse_err(&Parser::new(), bytes),
+        (3, 13, ParseErrorKind::InvalidEncoding)
+    );
+
+    let vdf = Parser::new().lossy(true).parse_bytes(bytes).unwrap();
+    let name = vdf.value.get_obj().unwrap()["Name"][0].get_str();
+    assert_eq!(name, Some("Bad\u{FFFD}\u{FFFD}Name"));
+    assert_eq!(vdf.encoding, Encoding::Utf8);
+}
+
+#[test]
+fn invalid_utf16() {
+    let units = "Key \"Bad"
+        .encode_utf16()
+        .chain([0xD800])
+        .chain("Value\"".encode_utf16());
+    let bytes: Vec<u8> = units.flat_map(u16::to_le_bytes).collect();
+    assert_eq!(
+        parse_err(&Parser::new(), &bytes),
+        (1, 9, ParseErrorKind::InvalidEncoding)
+    );
+    let vdf = Parser::new().lossy(true).parse_bytes(&bytes).unwrap();
+    assert_eq!(vdf.value.get_str(), Some("Bad\u{FFFD}Value"));
+
+    // A dangling byte at the end
+    let mut bytes = Encoding::Utf16Le { bom: true }.encode("Key Value");
+    bytes.push(b'\n');
+    assert_eq!(
+        parse_err(&Parser::new(), &bytes),
+        (1, 10, ParseErrorKind::InvalidEncoding)
+    );
+}
@@ -0,0 +1,231 @@
+use insta::{assert_debug_snapshot, assert_snapshot};
+use keyvalues_parser::{
+    error::{Error, Kv3ConvertError, Kv3ConvertErrorKind, Kv3ParseError, Kv3ParseErrorKind},
+    kv3::{self, Kv3, Obj, Value},
+    Limits, TypedValue, Vdf,
+};
+
+use std::{fs, path::Path};
+
+fn read_kv3_asset(file_name: &str) -> String {
+    fs::read_to_string(
+        Path::new("tests")
+            .join("assets")
+            .join("kv3")
+            .join(file_name),
+    )
+    .unwrap()
+}
+
+fn parse_err(text: &str) -> (usize, usize, Kv3ParseErrorKind) {
+    match kv3::parse(text).unwrap_err() {
+        Error::Kv3ParseError(Kv3ParseError { line, col, kind }) => (line, col, kind),
+        other => panic!("Unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn parse_example() {
+    let text = read_kv3_asset("example.vdata");
+    let kv3 = kv3::parse(&text).unwrap();
+    assert_eq!(kv3.header, kv3::Header::default());
+
+    let ability = kv3.root.get_obj().unwrap().get("ability_base").unwrap();
+    let ability = ability.get_obj().unwrap();
+    assert_eq!(ability.get("m_bEnabled"), Some(&Value::Bool(true)));
+    assert_eq!(ability.get("m_flCooldown"), Some(&Value::Float(12.5)));
+    assert_eq!(ability.get("m_nBigNumber"), Some(&Value::UInt(u64::MAX)));
+    assert_eq!(
+        ability.get("m_strDescription").unwrap().get_str(),
+        Some("Multiple\n  lines")
+    );
+    assert_eq!(
+        ability.get("m_Blob"),
+        Some(&Value::Binary(vec![0x00, 0x01, 0xfe, 0xff]))
+    );
+
+    assert_debug_snapshot!(kv3);
+}
+
+#[test]
+fn render_example() {
+    let text = read_kv3_asset("example.vdata");
+    let kv3 = kv3::parse(&text).unwrap();
+    let rendered = kv3.to_string();
+    assert_snapshot!(rendered);
+
+    let reparsed = kv3::parse(&rendered).unwrap();
+    assert_eq!(kv3, reparsed);
+}
+
+#[test]
+fn custom_header() {
+    let text = "<!-- kv3 encoding:text:version{e21c7f3c-8a33-41c5-9977-a76d3a32aa0d} \
+                format:vpcf36:version{d15c9157-10e0-47bc-9333-1ac81da07b8a} -->\n{}";
+    let kv3 = kv3::parse(text).unwrap();
+    assert_eq!(kv3.header.format.name, "vpcf36");
+    assert_eq!(
+        kv3.header.format.version,
+        "d15c9157-10e0-47bc-9333-1ac81da07b8a"
+    );
+    assert_eq!(kv3.root, Value::Obj(Obj::new()));
+    assert_eq!(kv3.to_string(), format!("{text}\n"));
+}
+
+#[test]
+fn errors() {
+    assert_eq!(
+        parse_err("<!-- kv2 encoding:text:version{} -->\n{}"),
+        (1, 1, Kv3ParseErrorKind::InvalidHeader)
+    );
+    assert_eq!(
+        parse_err("{\n\tkey = \"unterminated"),
+        (2, 21, Kv3ParseErrorKind::UnexpectedEof)
+    );
+    assert_eq!(
+        parse_err("{\n\tkey = bare\n}"),
+        (2, 8, Kv3ParseErrorKind::UnexpectedChar('b'))
+    );
+    assert_eq!(
+        parse_err("{ key = \"\\q\" }"),
+        (1, 11, Kv3ParseErrorKind::InvalidEscape('q'))
+    );
+    assert_eq!(
+        parse_err("{ key = 1.2.3 }"),
+        (1, 9, Kv3ParseErrorKind::InvalidNumber)
+    );
+    assert_eq!(
+        parse_err("{ key = #[ 0 ] }"),
+        (1, 12, Kv3ParseErrorKind::InvalidBinary)
+    );
+    assert_eq!(
+        parse_err("{ key = [ 1 2 ] }"),
+        (1, 13, Kv3ParseErrorKind::UnexpectedChar('2'))
+    );
+    assert_eq!(parse_err("{} {}"), (1, 4, Kv3ParseErrorKind::TrailingInput));
+    // The end of the header overlaps with its start
+    assert_eq!(parse_err("<!-->"), (1, 1, Kv3ParseErrorKind::InvalidHeader));
+    assert_eq!(
+        parse_err("<!--> kv3 -->\n{}"),
+        (1, 1, Kv3ParseErrorKind::InvalidHeader)
+    );
+}
+
+#[test]
+fn depth_limit() {
+    // Far deeper than the stack could handle if parsing wasn't limited
+    let max = Limits::DEFAULT_MAX_DEPTH;
+    for text in [
+        "[".repeat(200_000),
+        "{a=".repeat(200_000),
+        "a:".repeat(200_000),
+    ] {
+        assert_eq!(parse_err(&text).2, Kv3ParseErrorKind::DepthExceeded);
+    }
+    assert_eq!(
+        parse_err(&"[".repeat(max + 1)),
+        (1, max + 1, Kv3ParseErrorKind::DepthExceeded)
+    );
+    let nested = format!("{}{}", "[".repeat(max), "]".repeat(max));
+    assert!(kv3::parse(&nested).is_ok());
+}
+
+#[test]
+fn render_deeply_nested() {
+    const DEPTH: usize = 2_000;
+
+    let mut value = Value::Int(1);
+    for i in 0..DEPTH {
+        value = if i % 2 == 0 {
+            Value::Array(vec![value])
+        } else {
+            Value::Flagged(
+                "flag".into(),
+                Box::new(Value::Obj([("a".into(), value)].into_iter().collect())),
+            )
+        };
+    }
+
+    let rendered = std::thread::scope(|s| {
+        std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn_scoped(s, || value.to_string())
+            .unwrap()
+            .join()
+            .unwrap()
+    });
+    assert!(rendered.starts_with("flag:{\n\ta =\n\t[\n\t\tflag:{\n"));
+    let innermost = format!("{}a = [ 1 ]\n", "\t".repeat(DEPTH - 1));
+    assert!(rendered.contains(&innermost));
+    assert!(rendered.ends_with("\t]\n}"));
+}
+
+#[test]
+fn from_vdf() {
+    let vdf = Vdf::parse(
+        r#"
+"Root"
+{
+    "b"  "1"
+    "a"
+    {
+        "dup"  "first"
+        "dup"  "second"
+    }
+}
+"#,
+    )
+    .unwrap();
+    let kv3 = Kv3::from(vdf.clone());
+    assert_snapshot!(kv3.to_string());
+
+    // Strings and objects survive the round trip
+    assert_eq!(Vdf::try_from(kv3).unwrap(), vdf);
+}
+
+#[test]
+fn typed_round_trip() {
+    let mut obj = keyvalues_parser::Obj::new();
+    for (key, typed) in [
+        ("int", TypedValue::Int(-7)),
+        ("int64", TypedValue::Int64(1 << 40)),
+        ("uint64", TypedValue::UInt64(u64::MAX)),
+        ("float", TypedValue::Float(0.25)),
+    ] {
+        obj.push(key.into(), keyvalues_parser::Value::Typed(typed), None);
+    }
+    let vdf = Vdf::new("Typed".into(), keyvalues_parser::Value::Obj(obj));
+
+    let kv3 = Kv3::from(vdf.clone());
+    let rendered = kv3.to_string();
+    let reparsed = kv3::parse(&rendered).unwrap();
+    assert_eq!(Vdf::try_from(reparsed).unwrap(), vdf);
+}
+
+#[test]
+fn into_vdf_errors() {
+    let convert_err = |text| match Vdf::try_from(kv3::parse(text).unwrap()).unwrap_err() {
+        Error::Kv3ConvertError(Kv3ConvertError { path, kind }) => (path, kind),
+        other => panic!("Unexpected error: {other:?}"),
+    };
+
+    assert_eq!(
+        convert_err("{ a = 1 b = 2 }"),
+        (String::new(), Kv3ConvertErrorKind::RootNotSinglePair)
+    );
+    assert_eq!(
+        convert_err("[ 1 ]"),
+        (String::new(), Kv3ConvertErrorKind::RootNotSinglePair)
+    );
+    assert_eq!(
+        convert_err("{ root = { inner = { list = [] } } }"),
+        (
+            "root.inner.list".to_owned(),
+            Kv3ConvertErrorKind::UnsupportedType("array")
+        )
+    );
+    assert_eq!(
+        convert_err("{ root = { precise = 0.1 } }"),
+        ("root.precise".to_owned(), Kv3ConvertErrorKind::LossyFloat)
+    );
+}
@@ -0,0 +1,145 @@
+use crate::utils::read_asset_file;
+
+use insta::assert_snapshot;
+use keyvalues_parser::{
+    error::{Error, ParseErrorKind},
+    Parser, Position, Recovered,
+};
+
+fn kinds(recovered: &Recovered<'_>) -> Vec<(usize, usize, ParseErrorKind)> {
+    recovered
+        .errors
+        .iter()
+        .map(|err| (err.line(), err.col(), err.kind()))
+        .collect()
+}
+
+#[test]
+fn valid_text_matches_strict_parser() {
+    let assets = [
+        "base_multiple.vdf",
+        "base_quoted.vdf",
+        "base_unquoted.vdf",
+        "basic.vdf",
+        "comments.vdf",
+        "compact.vdf",
+        "conditionals.vdf",
+        "include_mixed.vdf",
+        "null_byte.vdf",
+        "special_characters.vdf",
+        "unquoted_strings.vdf",
+    ];
+    let parser = Parser::new().spans(true);
+    for asset in assets {
+        let text = read_asset_file(asset);
+        let recovered = parser.parse_lenient(&text);
+        assert_eq!(recovered.errors, [], "{asset}");
+        let vdf = recovered.vdf.unwrap();
+        let strict = parser.parse(&text).unwrap();
+        assert_eq!(vdf, strict, "{asset}");
+        assert_eq!(vdf.spans, strict.spans, "{asset}");
+    }
+
+    let parser = Parser::new().literal_special_chars(true);
+    for asset in ["base_multiple_raw_strings.vdf", "raw_strings.vdf"] {
+        let text = read_asset_file(asset);
+        let recovered = parser.parse_lenient(&text);
+        assert_eq!(recovered.errors, [], "{asset}");
+        assert_eq!(
+            recovered.vdf.unwrap(),
+            parser.parse(&text).unwrap(),
+            "{asset}"
+        );
+    }
+}
+
+#[test]
+fn first_error_matches_strict_parser() {
+    let texts = [
+        "",
+        "Key",
+        "Key \"C:\\Games\"",
+        "Key Value\nOther Value",
+        "Outer\n{\n\tInner\n}",
+        "Outer\n{\n\tInner\n\t{\n\t\tKey Value\n}\n",
+        "Key\n{\n\t\"unterminated\" \"value\n}",
+        "Key { { } }",
+    ];
+    for text in texts {
+        let Error::ParseError(strict) = keyvalues_parser::parse(text).unwrap_err() else {
+            panic!("Unexpected error kind");
+        };
+        let recovered = Parser::new().parse_lenient(text);
+        assert_eq!(recovered.errors.first(), Some(&strict), "{text:?}");
+    }
+}
+
+#[test]
+fn truncated() {
+    let text = r#""AppState"
+{
+	"appid"		"440"
+	"UserConfig"
+	{
+		"language"		"english"
+		"betakey"		"pre"#;
+    let recovered = Parser::new().parse_lenient(text);
+    let opening = |offset, line, col| ParseErrorKind::UnbalancedBrace {
+        opening: Position { offset, line, col },
+    };
+    // Each unclosed object gets reported, innermost first
+    assert_eq!(
+        kinds(&recovered),
+        [
+            (7, 14, ParseErrorKind::UnterminatedString),
+            (7, 18, opening(44, 5, 2)),
+            (7, 18, opening(11, 2, 1)),
+        ]
+    );
+    assert_snapshot!(recovered.vdf.unwrap());
+}
+
+#[test]
+fn stray_brace() {
+    let text = "Config\n{\n\tLibraryPath /mnt/games\n}\n}\n\tVolume 80\n\tMuted 0\n}\n";
+    let recovered = Parser::new().parse_lenient(text);
+    assert_eq!(
+        kinds(&recovered),
+        [
+            (5, 1, ParseErrorKind::TrailingContent),
+            (8, 1, ParseErrorKind::TrailingContent),
+        ]
+    );
+    assert_snapshot!(recovered.vdf.unwrap());
+}
+
+#[test]
+fn recovers_within_objs() {
+    let text = r#"Outer
+{
+	path "C:\Games"
+	{ nested keyless }
+	broken "value
+	after value
+	missing
+}
+"#;
+    let recovered = Parser::new().parse_lenient(text);
+    assert_eq!(
+        kinds(&recovered),
+        [
+            (3, 10, ParseErrorKind::InvalidEscape('G')),
+            (4, 2, ParseErrorKind::MissingKey),
+            (5, 9, ParseErrorKind::UnterminatedString),
+            (8, 1, ParseErrorKind::MissingValue),
+        ]
+    );
+    assert_snapshot!(recovered.vdf.unwrap());
+}
+
+#[test]
+fn nothing_to_recover() {
+    let recovered = Parser::new().parse_lenient("// Just a comment\n");
+    assert_eq!(recovered.vdf, None);
+    assert_eq!(kinds(&recovered), [(2, 1, ParseErrorKind::MissingKey)]);
+}
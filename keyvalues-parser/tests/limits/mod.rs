@@ -0,0 +1,170 @@
+use keyvalues_parser::{
+    error::{Error, Limit, ParseErrorKind},
+    Limits, Obj, Parser, Value, Vdf,
+};
+
+use std::borrow::Cow;
+
+fn limit_err(parser: &Parser, text: &str) -> (usize, usize, ParseErrorKind) {
+    match parser.parse(text).unwrap_err() {
+        Error::ParseError(err) => (err.line(), err.col(), err.kind()),
+        other => panic!("Unexpected error: {other:?}"),
+    }
+}
+
+fn exceeded(limit: Limit, max: usize) -> ParseErrorKind {
+    ParseErrorKind::LimitExceeded { limit, max }
+}
+
+fn nested(depth: usize) -> String {
+    format!(
+        "Outer {{ {}Key Value{}",
+        "Key { ".repeat(depth - 1),
+        " }".repeat(depth)
+    )
+}
+
+#[test]
+fn depth() {
+    let limits = Limits {
+        max_depth: Some(3),
+        ..Limits::none()
+    };
+    let parser = Parser::new().limits(limits);
+    assert!(parser.parse(&nested(3)).is_ok());
+    assert_eq!(
+        limit_err(&parser, &nested(4)),
+        (1, 25, exceeded(Limit::Depth, 3))
+    );
+}
+
+#[test]
+fn default_depth() {
+    // Way past the default limit, so this would overflow the stack without one
+    let text = nested(1_000_000);
+    let max = Limits::DEFAULT_MAX_DEPTH;
+    let (_, _, kind) = limit_err(&Parser::new(), &text);
+    assert_eq!(kind, exceeded(Limit::Depth, max));
+    assert!(Parser::new().parse(&nested(max)).is_ok());
+
+    // Lenient parsing stops at limits instead of recovering
+    let recovered = Parser::new().parse_lenient(&text);
+    assert_eq!(recovered.errors.len(), 1);
+    assert_eq!(recovered.errors[0].kind(), exceeded(Limit::Depth, max));
+}
+
+#[test]
+fn conditional_depth() {
+    let limits = Limits {
+        max_depth: Some(4),
+        ..Limits::none()
+    };
+    let text = "Key Value [!($A && !$B)]";
+    assert!(Parser::new().limits(limits).parse(text).is_ok());
+    let limits = Limits {
+        max_depth: Some(3),
+        ..limits
+    };
+    assert_eq!(
+        limit_err(&Parser::new().limits(limits), text),
+        (1, 11, exceeded(Limit::Depth, 3))
+    );
+
+    // These would overflow the stack when parsing or dropping without a limit
+    let max = Limits::DEFAULT_MAX_DEPTH;
+    let texts = [
+        format!("Key Value [{}$A]", "!".repeat(1_000_000)),
+        format!("Key Value [{}$A]", "(".repeat(1_000_000)),
+        format!("Key Value [$A{}]", " && $A".repeat(1_000_000)),
+    ];
+    for text in &texts {
+        assert_eq!(
+            limit_err(&Parser::new(), text),
+            (1, 11, exceeded(Limit::Depth, max))
+        );
+    }
+}
+
+#[test]
+fn input_len() {
+    let limits = Limits {
+        max_input_len: Some(16),
+        ..Limits::new()
+    };
+    let parser = Parser::new().limits(limits);
+    assert!(parser.parse("Key \"Some Value\"").is_ok());
+    assert_eq!(
+        limit_err(&parser, "Key \"Some Values\""),
+        (1, 1, exceeded(Limit::InputLen, 16))
+    );
+    let err = parser.parse_bytes(&[b' '; 17]).unwrap_err();
+    assert!(matches!(err, Error::ParseError(err) if err.kind() == exceeded(Limit::InputLen, 16)));
+}
+
+#[test]
+fn string_len() {
+    let limits = Limits {
+        max_string_len: Some(4),
+        ..Limits::new()
+    };
+    let parser = Parser::new().limits(limits);
+    assert!(parser.parse(r#"Key { "Long" Valu }"#).is_ok());
+    assert_eq!(
+        limit_err(&parser, r#"Key { "Long" Value }"#),
+        (1, 14, exceeded(Limit::StringLen, 4))
+    );
+    // The length is checked before unescaping
+    assert_eq!(
+        limit_err(&parser, r#"Key "\"\"\"""#),
+        (1, 5, exceeded(Limit::StringLen, 4))
+    );
+}
+
+#[test]
+fn pairs() {
+    let limits = Limits {
+        max_pairs: Some(3),
+        ..Limits::new()
+    };
+    let parser = Parser::new().limits(limits);
+    assert!(parser.parse("Outer { A 1 B { } }").is_ok());
+    assert_eq!(
+        limit_err(&parser, "Outer { A 1 B { C 3 } }"),
+        (1, 17, exceeded(Limit::Pairs, 3))
+    );
+    let err = parser.parse_root("A 1\nB 2\nC 3\nD 4").unwrap_err();
+    assert!(matches!(err, Error::ParseError(err) if err.line() == 4));
+}
+
+#[test]
+fn render_deeply_nested() {
+    // Indentation makes the output grow quadratically, so this is kept fairly shallow and rendered
+    // with a tiny stack instead
+    const DEPTH: usize = 2_000;
+
+    let mut value = Value::Str(Cow::from("Value"));
+    for _ in 0..DEPTH {
+        let mut obj = Obj::new();
+        obj.push(Cow::from("Key"), value, None);
+        value = Value::Obj(obj);
+    }
+    let vdf = Vdf::new(Cow::from("Outer"), value);
+
+    let (rendered, raw) = std::thread::scope(|s| {
+        std::thread::Builder::new()
+            .stack_size(64 * 1024)
+            .spawn_scoped(s, || {
+                let mut raw = String::new();
+                vdf.render_raw(&mut raw).unwrap();
+                (vdf.to_string(), raw)
+            })
+            .unwrap()
+            .join()
+            .unwrap()
+    });
+    assert!(rendered.starts_with("\"Outer\"\n{\n\t\"Key\"\n\t{\n"));
+    let innermost = format!("{}\"Key\"\t\"Value\"\n", "\t".repeat(DEPTH));
+    assert!(rendered.contains(&innermost));
+    assert!(rendered.ends_with("\t}\n}\n"));
+    assert_eq!(raw, rendered);
+}
@@ -0,0 +1,110 @@
+use insta::assert_snapshot;
+use keyvalues_parser::{
+    error::{Error, ParseError, ParseErrorKind},
+    Parser, Position,
+};
+
+fn parse_err_with(parser: &Parser, text: &str) -> ParseError {
+    match parser.parse(text).unwrap_err() {
+        Error::ParseError(err) => err,
+        other => panic!("Unexpected error: {other:?}"),
+    }
+}
+
+fn parse_err(text: &str) -> ParseError {
+    parse_err_with(&Parser::new(), text)
+}
+
+fn summary(err: &ParseError) -> (usize, usize, ParseErrorKind) {
+    (err.line(), err.col(), err.kind())
+}
+
+#[test]
+fn kinds() {
+    assert_eq!(
+        summary(&parse_err("Key\n{\n\t\"unterminated\" \"value\n}")),
+        (3, 17, ParseErrorKind::UnterminatedString)
+    );
+    assert_eq!(
+        summary(&parse_err("Key \"C:\\Games\"")),
+        (1, 8, ParseErrorKind::InvalidEscape('G'))
+    );
+    assert_eq!(
+        summary(&parse_err("Key Value\nOther Value")),
+        (2, 1, ParseErrorKind::TrailingContent)
+    );
+    assert_eq!(
+        summary(&parse_err("Outer\n{\n\tInner\n}")),
+        (4, 1, ParseErrorKind::MissingValue)
+    );
+    assert_eq!(
+        summary(&parse_err("Key")),
+        (1, 4, ParseErrorKind::MissingValue)
+    );
+    assert_eq!(summary(&parse_err("")), (1, 1, ParseErrorKind::MissingKey));
+    assert_eq!(
+        summary(&parse_err("Key { { } }")),
+        (1, 7, ParseErrorKind::MissingKey)
+    );
+    // `#base` without a path is the key of the top-level pair instead
+    assert_eq!(
+        summary(&parse_err("#base \"unterminated")),
+        (1, 7, ParseErrorKind::UnterminatedString)
+    );
+}
+
+#[test]
+fn unbalanced_brace_points_to_opening() {
+    let err = parse_err("Outer\n{\n\tInner\n\t{\n\t\tKey Value\n}\n");
+    let ParseErrorKind::UnbalancedBrace { opening } = err.kind() else {
+        panic!("Unexpected kind: {:?}", err.kind());
+    };
+    assert_eq!(
+        opening,
+        Position {
+            offset: 6,
+            line: 2,
+            col: 1
+        }
+    );
+    assert_eq!((err.line(), err.col()), (7, 1));
+}
+
+#[test]
+fn raw_mode() {
+    let raw = Parser::new().literal_special_chars(true);
+    // Backslashes aren't escapes, so the string ends at the first `"`
+    assert_eq!(
+        summary(&parse_err_with(&raw, "Key \"C:\\Games\\\" trailing")),
+        (1, 17, ParseErrorKind::TrailingContent)
+    );
+    assert_eq!(
+        summary(&parse_err_with(&raw, "Key \"unterminated")),
+        (1, 5, ParseErrorKind::UnterminatedString)
+    );
+}
+
+#[test]
+fn error_display() {
+    let err = keyvalues_parser::parse("Key \"Value").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Failed parsing input at 1:5: Unterminated string"
+    );
+}
+
+#[test]
+fn snippets() {
+    assert_snapshot!(
+        "invalid_escape",
+        parse_err("Key\n{\n\tPath\t\"C:\\Games\"\n}").snippet()
+    );
+    assert_snapshot!(
+        "unbalanced_brace",
+        parse_err("Outer\n{\n    Inner { Key Value }").snippet()
+    );
+    assert_snapshot!(
+        "unbalanced_brace_same_line",
+        parse_err("Outer { Key Value").snippet()
+    );
+}
@@ -0,0 +1,256 @@
+use insta::assert_snapshot;
+use keyvalues_parser::{
+    error::Error, Encoding, Newline, Parser, Renderer, RootVdf, Separator, Vdf,
+};
+
+use std::{fs, io};
+
+const TEXT: &str = r##"#base "shared.vdf"
+"AppState"
+{
+    "appid" "440"
+    "name" "Team Fortress 2"
+    "LauncherPath" "C:\\Program Files (x86)\\Steam\\steam.exe"
+    "Empty" ""
+    "#NotADirective" "[NotAConditional]"
+    "//NotAComment" "{}"
+    "UserConfig" [$WIN32]
+    {
+        "language" "english"
+        "BetaKey" "public" [!$X360]
+        "Nested" { }
+    }
+    "StateFlags" "4"
+}
+"##;
+
+fn render(vdf: &Vdf<'_>, renderer: &Renderer) -> String {
+    let mut text = String::new();
+    vdf.render_with(&mut text, renderer).unwrap();
+    text
+}
+
+fn round_trips(renderer: &Renderer) -> String {
+    let vdf = Vdf::parse(TEXT).unwrap();
+    let rendered = render(&vdf, renderer);
+    assert_eq!(Vdf::parse(&rendered).unwrap(), vdf, "{rendered}");
+    rendered
+}
+
+#[test]
+fn default_matches_display() {
+    let vdf = Vdf::parse(TEXT).unwrap();
+    assert_eq!(render(&vdf, &Renderer::new()), vdf.to_string());
+    assert_eq!(render(&vdf, &Renderer::default()), vdf.to_string());
+
+    let partial = Parser::new().parse(TEXT).unwrap();
+    let mut rendered = String::new();
+    partial
+        .render_with(&mut rendered, &Renderer::new())
+        .unwrap();
+    assert_eq!(Parser::new().parse(&rendered).unwrap(), partial);
+}
+
+#[test]
+fn steam_style() {
+    let renderer = Renderer::new().separator(Separator::Str("\t\t".into()));
+    assert_snapshot!(round_trips(&renderer));
+}
+
+#[test]
+fn aligned_spaces() {
+    let renderer = Renderer::new().indent("  ").separator(Separator::Aligned);
+    assert_snapshot!(round_trips(&renderer));
+}
+
+#[test]
+fn unquoted() {
+    let renderer = Renderer::new().quote_only_when_needed(true);
+    assert_snapshot!(round_trips(&renderer));
+}
+
+#[test]
+fn crlf() {
+    let renderer = Renderer::new().newline(Newline::CrLf);
+    let rendered = round_trips(&renderer);
+    assert_eq!(
+        rendered.matches('\n').count(),
+        rendered.matches("\r\n").count()
+    );
+    assert_eq!(
+        rendered.replace("\r\n", "\n"),
+        Vdf::parse(TEXT).unwrap().to_string()
+    );
+}
+
+#[test]
+fn minified() {
+    let renderer = Renderer::new().minified(true).quote_only_when_needed(true);
+    let rendered = round_trips(&renderer);
+    assert!(!rendered.contains('\n'));
+    assert_snapshot!(rendered);
+
+    // Directives and multiple top-level pairs stay on the same line too
+    let text = "#base shared.vdf\nA { B C }\nD E\n";
+    let root = Parser::new().parse_root(text).unwrap();
+    let mut rendered = String::new();
+    root.render_with(&mut rendered, &renderer).unwrap();
+    assert_eq!(rendered, r#"#base "shared.vdf" A { B C } D E"#);
+    assert_eq!(Parser::new().parse_root(&rendered).unwrap(), root);
+}
+
+#[test]
+fn literal_special_chars() {
+    let renderer = Renderer::new()
+        .literal_special_chars(true)
+        .quote_only_when_needed(true);
+    let text = r#"InstallDir "C:\Games\Team Fortress 2""#;
+    let raw_parser = Parser::new().literal_special_chars(true);
+    let vdf = Vdf::from(raw_parser.parse(text).unwrap());
+    let rendered = render(&vdf, &renderer);
+    assert_eq!(rendered, "InstallDir\t\"C:\\Games\\Team Fortress 2\"\n");
+    assert_eq!(Vdf::from(raw_parser.parse(&rendered).unwrap()), vdf);
+
+    // Backslashes only force quotes when they'd get escaped
+    let vdf = Vdf::from(raw_parser.parse(r"Path C:\Games").unwrap());
+    assert_eq!(render(&vdf, &renderer), "Path\tC:\\Games\n");
+    assert_eq!(
+        render(&vdf, &Renderer::new().quote_only_when_needed(true)),
+        "Path\t\"C:\\\\Games\"\n"
+    );
+
+    let vdf = Vdf::parse(r#"Quote "\"""#).unwrap();
+    let mut rendered = String::new();
+    let err = vdf.render_with(&mut rendered, &renderer).unwrap_err();
+    assert!(matches!(err, Error::RawRenderError { invalid_char: '"' }));
+    let err = RootVdf::from(Parser::new().parse(r#"Quote "\"""#).unwrap())
+        .render_with(&mut rendered, &renderer)
+        .unwrap_err();
+    assert!(matches!(err, Error::RawRenderError { invalid_char: '"' }));
+}
+
+// Fails after accepting `limit` bytes
+struct FailingWriter {
+    written: Vec<u8>,
+    limit: usize,
+}
+
+impl io::Write for FailingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.limit - self.written.len());
+        if len == 0 {
+            return Err(io::Error::new(io::ErrorKind::WriteZero, "disk is full"));
+        }
+        self.written.extend_from_slice(&buf[..len]);
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn io_writer() {
+    let vdf = Vdf::parse(TEXT).unwrap();
+    let renderer = Renderer::new().indent("  ").newline(Newline::CrLf);
+    let mut bytes = Vec::new();
+    vdf.render_to_writer(&mut bytes, &renderer).unwrap();
+    assert_eq!(String::from_utf8(bytes).unwrap(), render(&vdf, &renderer));
+
+    let root = Parser::new().parse_root(TEXT).unwrap();
+    let mut bytes = Vec::new();
+    root.render_to_writer(&mut bytes, &Renderer::new()).unwrap();
+    assert_eq!(String::from_utf8(bytes).unwrap(), root.to_string());
+}
+
+#[test]
+fn io_errors_are_kept() {
+    let vdf = Vdf::parse(TEXT).unwrap();
+    let mut writer = FailingWriter {
+        written: Vec::new(),
+        limit: 20,
+    };
+    let err = vdf
+        .render_to_writer(&mut writer, &Renderer::new())
+        .unwrap_err();
+    let Error::Io(err) = err else {
+        panic!("Unexpected error: {err:?}");
+    };
+    assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+    assert_eq!(err.to_string(), "disk is full");
+    assert_eq!(writer.written.len(), 20);
+
+    // Other errors are returned before anything is written
+    let vdf = Vdf::parse(r#"Quote "\"""#).unwrap();
+    let mut bytes = Vec::new();
+    let renderer = Renderer::new().literal_special_chars(true);
+    let err = vdf.render_to_writer(&mut bytes, &renderer).unwrap_err();
+    assert!(matches!(err, Error::RawRenderError { invalid_char: '"' }));
+    assert!(bytes.is_empty());
+}
+
+// `PartialVdf::render_bytes()` renders raw strings
+const RAW: Renderer = Renderer::new().literal_special_chars(true);
+
+#[test]
+fn io_writer_keeps_encoding() {
+    let encoding = Encoding::Utf16Le { bom: true };
+    let text = "Tokens { Hello Bonjour Emoji \"\u{1F600}\" }";
+    let vdf = Parser::new().parse_bytes(&encoding.encode(text)).unwrap();
+    let mut bytes = Vec::new();
+    vdf.render_to_writer(&mut bytes, &RAW).unwrap();
+    assert_eq!(bytes, vdf.render_bytes().unwrap());
+    assert_eq!(Parser::new().parse_bytes(&bytes).unwrap(), vdf);
+}
+
+#[test]
+fn render_to_path() {
+    let path = std::env::temp_dir().join(format!("keyvalues-render-{}.vdf", std::process::id()));
+    let vdf = Vdf::parse(TEXT).unwrap();
+    vdf.render_to_path(&path, &Renderer::new()).unwrap();
+    let written = fs::read_to_string(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(written.unwrap(), vdf.to_string());
+
+    let missing_dir = path.with_extension("missing").join("file.vdf");
+    let err = vdf
+        .render_to_path(&missing_dir, &Renderer::new())
+        .unwrap_err();
+    assert!(matches!(err, Error::Io(err) if err.kind() == io::ErrorKind::NotFound));
+}
+
+#[test]
+fn failed_render_to_path_keeps_file() {
+    let path = std::env::temp_dir().join(format!("keyvalues-keep-{}.vdf", std::process::id()));
+    fs::write(&path, "existing").unwrap();
+    let vdf = Vdf::parse(r#"Quote "\"""#).unwrap();
+    let renderer = Renderer::new().literal_special_chars(true);
+    let err = vdf.render_to_path(&path, &renderer).unwrap_err();
+    let contents = fs::read_to_string(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(matches!(err, Error::RawRenderError { invalid_char: '"' }));
+    assert_eq!(contents.unwrap(), "existing");
+}
+
+#[test]
+fn invalid_directive_paths() {
+    for path in ["quote\".vdf", "new\nline.vdf"] {
+        let mut vdf = Parser::new().parse("#base base.vdf\nKey Value").unwrap();
+        vdf.bases.push(path.into());
+        let mut rendered = String::new();
+        let err = vdf.render(&mut rendered).unwrap_err();
+        assert!(matches!(err, Error::InvalidDirectivePath { path: p } if p == path));
+        assert!(rendered.is_empty());
+
+        let mut root = Parser::new()
+            .parse_root("#include a.vdf\nKey Value")
+            .unwrap();
+        root.includes[0].path = path.into();
+        let err = root
+            .render_with(&mut rendered, &Renderer::new())
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidDirectivePath { path: p } if p == path));
+        assert!(rendered.is_empty());
+    }
+}
@@ -0,0 +1,295 @@
+use insta::assert_snapshot;
+use keyvalues_parser::{
+    error::{Error, ResolveError, ResolveErrorKind},
+    resolve::{Loader, Resolved, Resolver},
+    Vdf,
+};
+
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Default)]
+struct MemLoader(HashMap<PathBuf, &'static str>);
+
+impl MemLoader {
+    fn file(mut self, path: &str, contents: &'static str) -> Self {
+        self.0.insert(path.into(), contents);
+        self
+    }
+}
+
+impl Loader for MemLoader {
+    fn load(&mut self, path: &Path) -> io::Result<String> {
+        self.0
+            .get(path)
+            .map(|&contents| contents.to_owned())
+            .ok_or_else(|| io::ErrorKind::NotFound.into())
+    }
+}
+
+fn resolve(loader: MemLoader, path: &str) -> Resolved {
+    Resolver::new(loader).resolve(path).unwrap()
+}
+
+fn resolve_err(loader: MemLoader, path: &str) -> (PathBuf, ResolveErrorKind) {
+    match Resolver::new(loader).resolve(path).unwrap_err() {
+        Error::ResolveError(ResolveError { path, kind }) => (path, kind),
+        other => panic!("Unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn base_fills_missing_keys() {
+    let loader = MemLoader::default()
+        .file(
+            "main.res",
+            r#"
+            #base "base.res"
+            "Main" { "a" "main" "nested" { "x" "main" } }
+            "#,
+        )
+        .file(
+            "base.res",
+            r#"
+            "Ignored"
+            {
+                "a" "base"
+                "b" "base"
+                "nested" { "x" "base" "y" "base" }
+            }
+            "#,
+        );
+    let resolved = resolve(loader, "main.res");
+
+    let expected = Vdf::parse(
+        r#"
+        "Main"
+        {
+            "a" "main"
+            "nested" { "x" "main" "y" "base" }
+            "b" "base"
+        }
+        "#,
+    )
+    .unwrap();
+    assert_eq!(resolved.vdf, expected);
+    assert_eq!(
+        resolved.files(),
+        [Path::new("main.res"), Path::new("base.res")]
+    );
+    assert_eq!(resolved.source(&[("a", 0)]), Some(Path::new("main.res")));
+    assert_eq!(resolved.source(&[("b", 0)]), Some(Path::new("base.res")));
+    assert_eq!(
+        resolved.source(&[("nested", 0), ("x", 0)]),
+        Some(Path::new("main.res"))
+    );
+    assert_eq!(
+        resolved.source(&[("nested", 0), ("y", 0)]),
+        Some(Path::new("base.res"))
+    );
+    assert_eq!(resolved.source(&[("missing", 0)]), None);
+}
+
+#[test]
+fn earlier_bases_take_precedence() {
+    let loader = MemLoader::default()
+        .file(
+            "main.res",
+            r#"
+            #base "first.res"
+            #base "second.res"
+            "Main" {}
+            "#,
+        )
+        .file("first.res", r#""First" { "a" "first" }"#)
+        .file("second.res", r#""Second" { "a" "second" "b" "second" }"#);
+    let resolved = resolve(loader, "main.res");
+
+    let obj = resolved.vdf.value.get_obj().unwrap();
+    assert_eq!(obj["a"][0].get_str(), Some("first"));
+    assert_eq!(obj["b"][0].get_str(), Some("second"));
+    assert_eq!(resolved.source(&[("a", 0)]), Some(Path::new("first.res")));
+    assert_eq!(resolved.source(&[("b", 0)]), Some(Path::new("second.res")));
+}
+
+#[test]
+fn base_fills_every_value_of_missing_keys() {
+    let loader = MemLoader::default()
+        .file(
+            "main.res",
+            r#"
+            #base "base.res"
+            "Main" { "Existing" "main" }
+            "#,
+        )
+        .file(
+            "base.res",
+            r#"
+            "Base"
+            {
+                "SearchPath" "a"
+                "Existing" "base"
+                "SearchPath" "b"
+                "SearchPath" "c"
+                "Existing" "base"
+            }
+            "#,
+        );
+    let resolved = resolve(loader, "main.res");
+
+    let obj = resolved.vdf.value.get_obj().unwrap();
+    let search_paths: Vec<_> = obj["SearchPath"].iter().map(|v| v.get_str()).collect();
+    assert_eq!(search_paths, [Some("a"), Some("b"), Some("c")]);
+    assert_eq!(obj["Existing"].len(), 1);
+    assert_eq!(
+        resolved.source(&[("SearchPath", 2)]),
+        Some(Path::new("base.res"))
+    );
+    assert_eq!(
+        resolved.source(&[("Existing", 0)]),
+        Some(Path::new("main.res"))
+    );
+}
+
+#[test]
+fn include_appends_pairs() {
+    let loader = MemLoader::default()
+        .file(
+            "main.res",
+            r#"
+            #include "included.res"
+            "Main" { "a" "main" }
+            "#,
+        )
+        .file(
+            "included.res",
+            r#""Included" { "a" "included" "b" "included" }"#,
+        );
+    let resolved = resolve(loader, "main.res");
+
+    let obj = resolved.vdf.value.get_obj().unwrap();
+    let values: Vec<_> = obj["a"].iter().map(|value| value.get_str()).collect();
+    assert_eq!(values, [Some("main"), Some("included")]);
+    assert_eq!(resolved.source(&[("a", 0)]), Some(Path::new("main.res")));
+    assert_eq!(
+        resolved.source(&[("a", 1)]),
+        Some(Path::new("included.res"))
+    );
+    assert_eq!(
+        resolved.source(&[("b", 0)]),
+        Some(Path::new("included.res"))
+    );
+}
+
+#[test]
+fn nested_directives_are_relative() {
+    let loader = MemLoader::default()
+        .file(
+            "scripts/main.res",
+            r#"
+            #base "base/base.res"
+            "Main" { "a" "main" }
+            "#,
+        )
+        .file(
+            "scripts/base/base.res",
+            r#"
+            #base "..\..\shared\shared.res"
+            "Base" { "b" "base" }
+            "#,
+        )
+        .file("shared/shared.res", r#""Shared" { "c" "shared" }"#);
+    let resolved = resolve(loader, "scripts/main.res");
+
+    let obj = resolved.vdf.value.get_obj().unwrap();
+    assert_eq!(obj.len(), 3);
+    assert_eq!(
+        resolved.source(&[("c", 0)]),
+        Some(Path::new("shared/shared.res"))
+    );
+}
+
+#[test]
+fn shared_bases_are_not_cycles() {
+    let loader = MemLoader::default()
+        .file(
+            "main.res",
+            r#"
+            #base "left.res"
+            #base "right.res"
+            "Main" {}
+            "#,
+        )
+        .file("left.res", "#base \"common.res\"\n\"Left\" {}")
+        .file("right.res", "#base \"common.res\"\n\"Right\" {}")
+        .file("common.res", r#""Common" { "a" "common" }"#);
+    let resolved = resolve(loader, "main.res");
+    assert_eq!(resolved.files().len(), 5);
+    assert_eq!(resolved.source(&[("a", 0)]), Some(Path::new("common.res")));
+}
+
+#[test]
+fn resolve_errors() {
+    let cyclic = MemLoader::default()
+        .file("a.res", "#base \"b.res\"\n\"A\" {}")
+        .file("b.res", "#include \"a.res\"\n\"B\" {}");
+    let (path, kind) = resolve_err(cyclic, "a.res");
+    assert_eq!(path, Path::new("a.res"));
+    let ResolveErrorKind::Cycle(cycle) = kind else {
+        panic!("Unexpected error: {kind:?}");
+    };
+    assert_eq!(
+        cycle,
+        [Path::new("a.res"), Path::new("b.res"), Path::new("a.res")]
+    );
+
+    let escapes = MemLoader::default().file("a.res", "#base \"../../b.res\"\n\"A\" {}");
+    let (_, kind) = resolve_err(escapes, "a.res");
+    assert!(matches!(kind, ResolveErrorKind::EscapesRoot));
+
+    let absolute = MemLoader::default().file("a.res", "#base \"C:\\b.res\"\n\"A\" {}");
+    let (_, kind) = resolve_err(absolute, "a.res");
+    assert!(matches!(kind, ResolveErrorKind::EscapesRoot));
+
+    let missing = MemLoader::default().file("a.res", "#base \"b.res\"\n\"A\" {}");
+    let err = Resolver::new(missing).resolve("a.res").unwrap_err();
+    // The loader's error is kept around as the source
+    let source = std::error::Error::source(&err).unwrap();
+    assert_eq!(
+        source.downcast_ref::<io::Error>().unwrap().kind(),
+        io::ErrorKind::NotFound
+    );
+    let Error::ResolveError(ResolveError { path, kind }) = err else {
+        panic!("Unexpected error: {err:?}");
+    };
+    assert_eq!(path, Path::new("b.res"));
+    assert!(matches!(kind, ResolveErrorKind::Load(e) if e.kind() == io::ErrorKind::NotFound));
+
+    let invalid = MemLoader::default()
+        .file("a.res", "#base \"b.res\"\n\"A\" {}")
+        .file("b.res", "\"B\" {");
+    let (path, kind) = resolve_err(invalid, "a.res");
+    assert_eq!(path, Path::new("b.res"));
+    assert!(matches!(kind, ResolveErrorKind::Parse(_)));
+}
+
+#[test]
+fn resolve_from_fs() {
+    let root = Path::new("tests").join("assets").join("resolve");
+    let resolved = Resolver::from_root(root)
+        .resolve("resource/ui/hudlayout.res")
+        .unwrap();
+
+    assert_eq!(
+        resolved.source(&[("HudHealth", 0), ("ypos", 0)]),
+        Some(Path::new("resource/ui/hudlayout_base.res"))
+    );
+    assert_eq!(
+        resolved.source(&[("HudCrosshair", 0)]),
+        Some(Path::new("shared/extra.res"))
+    );
+    assert_snapshot!(resolved.vdf.to_string());
+}
@@ -0,0 +1,129 @@
+use insta::assert_snapshot;
+use keyvalues_parser::{
+    error::{Error, ParseErrorKind},
+    DuplicateKeys, Parser, RootVdf,
+};
+
+const VMF: &str = r#"#base "shared.vmf"
+
+versioninfo
+{
+    "editorversion" "400"
+    "mapversion" "12"
+}
+world
+{
+    "id" "1"
+    "classname" "worldspawn"
+}
+entity
+{
+    "id" "2"
+    "classname" "info_player_start"
+}
+"skyname" "sky_day01_01" [$WIN32]
+entity
+{
+    "id" "3"
+    "classname" "light"
+}
+"#;
+
+#[test]
+fn multiple_pairs() {
+    let vdf = Parser::new().parse_root(VMF).unwrap();
+    assert_eq!(vdf.bases, ["shared.vmf"]);
+    let keys: Vec<_> = vdf
+        .root
+        .ordered_pairs()
+        .into_iter()
+        .map(|(key, index, _)| (key.as_ref(), index))
+        .collect();
+    assert_eq!(
+        keys,
+        [
+            ("versioninfo", 0),
+            ("world", 0),
+            ("entity", 0),
+            ("skyname", 0),
+            ("entity", 1),
+        ]
+    );
+    assert!(vdf.root.conditional("skyname", 0).is_some());
+}
+
+#[test]
+fn render_round_trip() {
+    let vdf = Parser::new().parse_root(VMF).unwrap();
+    let rendered = vdf.to_string();
+    assert_snapshot!(rendered);
+    assert_eq!(Parser::new().parse_root(&rendered).unwrap(), vdf);
+
+    let mut raw = String::new();
+    vdf.render_raw(&mut raw).unwrap();
+    assert_eq!(raw, rendered);
+}
+
+#[test]
+fn empty_documents() {
+    for text in ["", "  \n", "// Just a comment\n", "\0"] {
+        let vdf = Parser::new().parse_root(text).unwrap();
+        assert_eq!(vdf, RootVdf::default(), "{text:?}");
+        assert_eq!(vdf.to_string(), "");
+    }
+
+    let vdf = Parser::new().parse_root("#base base.txt\n").unwrap();
+    assert!(vdf.root.is_empty());
+    assert_eq!(vdf.to_string(), "#base \"base.txt\"\n\n");
+}
+
+#[test]
+fn errors() {
+    let err_at = |text: &str| match Parser::new().parse_root(text).unwrap_err() {
+        Error::ParseError(err) => (err.line(), err.col(), err.kind()),
+        other => panic!("Unexpected error: {other:?}"),
+    };
+    assert_eq!(
+        err_at("First { }\n}\nSecond { }"),
+        (2, 1, ParseErrorKind::MissingKey)
+    );
+    assert_eq!(
+        err_at("First { }\nSecond"),
+        (2, 7, ParseErrorKind::MissingValue)
+    );
+    let (line, col, kind) = err_at("First { }\nSecond {\n");
+    assert_eq!((line, col), (3, 1));
+    assert!(matches!(kind, ParseErrorKind::UnbalancedBrace { .. }));
+
+    let parser = Parser::new().duplicate_keys(DuplicateKeys::Error);
+    let Error::ParseError(err) = parser.parse_root("A 1\nB 2\nA 3").unwrap_err() else {
+        panic!("Expected a parse error");
+    };
+    assert_eq!(
+        err.kind(),
+        ParseErrorKind::DuplicateKey {
+            first: keyvalues_parser::Position {
+                offset: 0,
+                line: 1,
+                col: 1
+            }
+        }
+    );
+}
+
+#[test]
+fn spans() {
+    let vdf = Parser::new().spans(true).parse_root(VMF).unwrap();
+    let spans = vdf.spans.unwrap();
+    assert_eq!(&VMF[spans.bases[0].range()], "#base \"shared.vmf\"");
+    let span = vdf.root.span("entity", 1).unwrap();
+    assert_eq!((span.key.start.line, span.key.start.col), (19, 1));
+}
+
+#[test]
+fn from_partial_vdf() {
+    let text = "#base base.vdf\nKey Value [$WIN32]";
+    let partial = Parser::new().spans(true).parse(text).unwrap();
+    let root = RootVdf::from(partial);
+    assert_eq!(root, Parser::new().spans(true).parse_root(text).unwrap());
+}
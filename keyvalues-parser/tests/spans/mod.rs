@@ -0,0 +1,97 @@
+use keyvalues_parser::{Parser, Span};
+
+const TEXT: &str = r#"#base "base.vdf"
+#include "extra.vdf"
+Config
+{
+    LibraryPath "/mnt/games"
+    "Nested"
+    {
+        "key"   "one"
+        "key"   "two"
+    }
+}
+"#;
+
+fn slice(span: &Span) -> &str {
+    &TEXT[span.range()]
+}
+
+#[test]
+fn spans_are_opt_in() {
+    let vdf = Parser::new().parse(TEXT).unwrap();
+    assert_eq!(vdf.spans, None);
+    let obj = vdf.value.get_obj().unwrap();
+    assert_eq!(obj.span("LibraryPath", 0), None);
+}
+
+#[test]
+fn document_spans() {
+    let vdf = Parser::new().spans(true).parse(TEXT).unwrap();
+    let spans = vdf.spans.as_ref().unwrap();
+
+    assert_eq!(spans.bases.len(), 1);
+    assert_eq!(slice(&spans.bases[0]), r#"#base "base.vdf""#);
+    assert_eq!(spans.includes.len(), 1);
+    assert_eq!(slice(&spans.includes[0]), r#"#include "extra.vdf""#);
+
+    assert_eq!(slice(&spans.pair.key), "Config");
+    assert_eq!(
+        (spans.pair.key.start.line, spans.pair.key.start.col),
+        (3, 1)
+    );
+    // Objects span from their opening brace to their closing brace
+    let value = slice(&spans.pair.value);
+    assert!(value.starts_with('{') && value.ends_with('}'));
+    assert_eq!(
+        (spans.pair.value.start.line, spans.pair.value.end.line),
+        (4, 11)
+    );
+}
+
+#[test]
+fn obj_spans() {
+    let vdf = Parser::new().spans(true).parse(TEXT).unwrap();
+    let obj = vdf.value.get_obj().unwrap();
+
+    let library = obj.span("LibraryPath", 0).unwrap();
+    assert_eq!(slice(&library.key), "LibraryPath");
+    // Quoted strings include their quotes
+    assert_eq!(slice(&library.value), r#""/mnt/games""#);
+    assert_eq!((library.value.start.line, library.value.start.col), (5, 17));
+
+    let nested = obj["Nested"][0].get_obj().unwrap();
+    let second = nested.span("key", 1).unwrap();
+    assert_eq!(slice(&second.value), r#""two""#);
+    assert_eq!(second.key.start.line, 9);
+    assert_eq!(nested.span("key", 2), None);
+}
+
+#[test]
+fn spans_dont_affect_equality() {
+    let with = Parser::new().spans(true).parse(TEXT).unwrap();
+    let without = Parser::new().parse(TEXT).unwrap();
+    assert_eq!(with.value, without.value);
+}
+
+#[test]
+fn raw_mode_spans() {
+    let text = "Path \"C:\\Games\\\"\n";
+    let vdf = Parser::new()
+        .literal_special_chars(true)
+        .spans(true)
+        .parse(text)
+        .unwrap();
+    let spans = vdf.spans.unwrap();
+    assert_eq!(&text[spans.pair.value.range()], "\"C:\\Games\\\"");
+    assert_eq!(spans.pair.value.end.col, 17);
+}
+
+#[test]
+fn cols_count_chars() {
+    let text = "\"ключ\" \"значение\"";
+    let vdf = Parser::new().spans(true).parse(text).unwrap();
+    let spans = vdf.spans.unwrap();
+    assert_eq!(spans.pair.value.start.col, 8);
+    assert_eq!(spans.pair.value.start.offset, "\"ключ\" ".len());
+}
@@ -0,0 +1,450 @@
+use crate::utils::read_asset_file;
+
+use keyvalues_parser::{
+    error::{
+        Error, Limit, ParseErrorKind, StreamError, StreamErrorKind, WriteError, WriteErrorKind,
+    },
+    stream::{Event, Reader, Writer},
+    Conditional, Limits, Parser, PartialVdf, Value, Vdf,
+};
+
+use std::io::{self, BufReader, Read};
+
+// The events that the reader should produce for a parsed document, minus any conditionals since
+// where they go depends on the text
+fn expected_events(vdf: &PartialVdf<'_>) -> Vec<Event> {
+    fn value_events(value: &Value<'_>, events: &mut Vec<Event>) {
+        match value {
+            Value::Obj(obj) => {
+                events.push(Event::ObjBegin);
+                for (key, _, value) in obj.ordered_pairs() {
+                    events.push(Event::Key(key.to_string()));
+                    value_events(value, events);
+                }
+                events.push(Event::ObjEnd);
+            }
+            other => events.push(Event::Str(other.get_str().unwrap().to_owned())),
+        }
+    }
+
+    let mut events = Vec::new();
+    let mut bases = vdf.bases.iter();
+    let mut includes = vdf.includes.iter().peekable();
+    for position in 0..vdf.bases.len() + vdf.includes.len() {
+        match includes.next_if(|include| include.position == position) {
+            Some(include) => events.push(Event::Include(include.path.to_string())),
+            None => events.push(Event::Base(bases.next().unwrap().to_string())),
+        }
+    }
+    events.push(Event::Key(vdf.key.to_string()));
+    value_events(&vdf.value, &mut events);
+    events
+}
+
+fn read_events<R: io::BufRead>(reader: Reader<R>) -> Vec<Event> {
+    reader
+        .map(Result::unwrap)
+        .filter(|event| !matches!(event, Event::Conditional(_)))
+        .collect()
+}
+
+fn stream_err(text: &str) -> (usize, usize, StreamErrorKind) {
+    reader_err(Reader::new(text.as_bytes()))
+}
+
+fn reader_err(mut reader: Reader<&[u8]>) -> (usize, usize, StreamErrorKind) {
+    let err = loop {
+        match reader.next() {
+            Some(Ok(_)) => {}
+            Some(Err(err)) => break err,
+            None => panic!("Expected an error"),
+        }
+    };
+    // The reader is done after an error
+    assert!(reader.next().is_none());
+
+    match err {
+        Error::StreamError(StreamError { position, kind }) => (position.line, position.col, kind),
+        other => panic!("Unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn matches_parser() {
+    let assets = [
+        "base_multiple.vdf",
+        "base_quoted.vdf",
+        "base_unquoted.vdf",
+        "basic.vdf",
+        "comments.vdf",
+        "compact.vdf",
+        "conditionals.vdf",
+        "include_mixed.vdf",
+        "null_byte.vdf",
+        "special_characters.vdf",
+        "unquoted_strings.vdf",
+    ];
+    for asset in assets {
+        let text = read_asset_file(asset);
+        let expected = expected_events(&keyvalues_parser::parse(&text).unwrap());
+        assert_eq!(
+            read_events(Reader::new(text.as_bytes())),
+            expected,
+            "{asset}"
+        );
+        // Tokens get split across lots of tiny reads
+        let tiny = BufReader::with_capacity(1, text.as_bytes());
+        assert_eq!(read_events(Reader::new(tiny)), expected, "{asset}");
+    }
+
+    let parser = Parser::new().literal_special_chars(true);
+    for asset in ["base_multiple_raw_strings.vdf", "raw_strings.vdf"] {
+        let text = read_asset_file(asset);
+        let expected = expected_events(&parser.parse(&text).unwrap());
+        let reader = Reader::from_read(text.as_bytes()).literal_special_chars(true);
+        assert_eq!(read_events(reader), expected, "{asset}");
+    }
+}
+
+#[test]
+fn conditionals_in_text_order() {
+    let text = r#"
+Outer [$WIN32]
+{
+    Str Value [!$X360 && ($A || $B)]
+    Trailing { } [$LINUX]
+    // Not a conditional, so it's the value
+    Bracketed [$OSX] Next Value
+}
+"#;
+    let events: Vec<_> = Reader::new(text.as_bytes())
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let sym = Conditional::symbol;
+    let str = |s: &str| Event::Str(s.to_owned());
+    let key = |s: &str| Event::Key(s.to_owned());
+    assert_eq!(
+        events,
+        [
+            key("Outer"),
+            Event::Conditional(sym("WIN32")),
+            Event::ObjBegin,
+            key("Str"),
+            str("Value"),
+            Event::Conditional(Conditional::And(
+                Box::new(Conditional::Not(Box::new(sym("X360")))),
+                Box::new(Conditional::Or(Box::new(sym("A")), Box::new(sym("B")))),
+            )),
+            key("Trailing"),
+            Event::ObjBegin,
+            Event::ObjEnd,
+            Event::Conditional(sym("LINUX")),
+            key("Bracketed"),
+            str("[$OSX]"),
+            key("Next"),
+            str("Value"),
+            Event::ObjEnd,
+        ]
+    );
+}
+
+#[test]
+fn errors_match_parser() {
+    let texts = [
+        "",
+        "Key",
+        "Key \"C:\\Games\"",
+        "Key Value\nOther Value",
+        "Outer\n{\n\tInner\n}",
+        "Outer\n{\n\tInner\n\t{\n\t\tKey Value\n}\n",
+        "Key\n{\n\t\"unterminated\" \"value\n}",
+        "Key { { } }",
+        "#base \"unterminated",
+        "} Key Value",
+    ];
+    for text in texts {
+        let Error::ParseError(expected) = keyvalues_parser::parse(text).unwrap_err() else {
+            panic!("Unexpected error kind");
+        };
+        let (line, col, kind) = stream_err(text);
+        assert_eq!((line, col), (expected.line(), expected.col()), "{text:?}");
+        assert!(
+            matches!(kind, StreamErrorKind::Parse(kind) if kind == expected.kind()),
+            "{text:?}"
+        );
+    }
+}
+
+#[test]
+fn directive_like_keys() {
+    let events: Vec<_> = Reader::new(&b"#base\n{\n}"[..])
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        events,
+        [Event::Key("#base".into()), Event::ObjBegin, Event::ObjEnd]
+    );
+}
+
+#[test]
+fn invalid_utf8() {
+    let mut reader = Reader::new(&b"Key \"\xff\""[..]);
+    assert_eq!(reader.next().unwrap().unwrap(), Event::Key("Key".into()));
+    let err = reader.next().unwrap().unwrap_err();
+    let Error::StreamError(StreamError { position, kind }) = err else {
+        panic!("Unexpected error: {err:?}");
+    };
+    assert_eq!((position.line, position.col), (1, 5));
+    assert!(matches!(kind, StreamErrorKind::InvalidUtf8));
+}
+
+#[test]
+fn io_errors() {
+    struct Flaky<'a>(&'a [u8]);
+
+    impl Read for Flaky<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::ErrorKind::ConnectionReset.into());
+            }
+            let len = buf.len().min(self.0.len());
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    let mut reader = Reader::from_read(Flaky(b"Outer { Inner"));
+    assert_eq!(reader.next().unwrap().unwrap(), Event::Key("Outer".into()));
+    assert_eq!(reader.next().unwrap().unwrap(), Event::ObjBegin);
+    let err = reader.next().unwrap().unwrap_err();
+    let Error::StreamError(StreamError { position, kind }) = err else {
+        panic!("Unexpected error: {err:?}");
+    };
+    assert_eq!(position.offset, 13);
+    let StreamErrorKind::Io(err) = kind else {
+        panic!("Unexpected kind: {kind:?}");
+    };
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionReset);
+}
+
+#[test]
+fn limits() {
+    fn limit_err(limits: Limits, text: &str) -> (usize, usize, ParseErrorKind) {
+        let (line, col, kind) = reader_err(Reader::new(text.as_bytes()).limits(limits));
+        let StreamErrorKind::Parse(kind) = kind else {
+            panic!("Unexpected kind: {kind:?}");
+        };
+        (line, col, kind)
+    }
+
+    // Every limit is reported the same way that the parser reports it
+    let cases = [
+        (
+            Limits {
+                max_string_len: Some(4),
+                ..Limits::new()
+            },
+            r#"Key { "Long" Value }"#,
+        ),
+        (
+            Limits {
+                max_string_len: Some(4),
+                ..Limits::new()
+            },
+            r#"Key "\"\"\"""#,
+        ),
+        (
+            Limits {
+                max_depth: Some(1),
+                ..Limits::new()
+            },
+            "Outer { Inner { } }",
+        ),
+        (
+            Limits {
+                max_depth: Some(3),
+                ..Limits::new()
+            },
+            "Key Value [!($A && !$B)]",
+        ),
+        (
+            Limits {
+                max_pairs: Some(3),
+                ..Limits::new()
+            },
+            "Outer { A 1 B { C 3 } }",
+        ),
+        (
+            Limits {
+                max_input_len: Some(16),
+                ..Limits::new()
+            },
+            "Key \"Some Values\"",
+        ),
+    ];
+    for (limits, text) in cases {
+        let Error::ParseError(expected) = Parser::new().limits(limits).parse(text).unwrap_err()
+        else {
+            panic!("Unexpected error kind");
+        };
+        assert_eq!(
+            limit_err(limits, text),
+            (expected.line(), expected.col(), expected.kind()),
+            "{text:?}"
+        );
+    }
+
+    // A single huge token is cut off instead of getting buffered
+    let limits = Limits {
+        max_string_len: Some(64),
+        ..Limits::none()
+    };
+    let mut text = b"Key \"".to_vec();
+    text.resize(1_000_000, b'a');
+    let (line, col, kind) = reader_err(Reader::new(&text[..]).limits(limits));
+    assert_eq!((line, col), (1, 5));
+    assert!(matches!(
+        kind,
+        StreamErrorKind::Parse(ParseErrorKind::LimitExceeded {
+            limit: Limit::StringLen,
+            max: 64,
+        })
+    ));
+}
+
+#[test]
+fn unclosed_obj_points_to_opening() {
+    let (line, col, kind) = stream_err("Outer\n{\n\tInner Value\n");
+    assert_eq!((line, col), (4, 1));
+    let StreamErrorKind::Parse(ParseErrorKind::UnbalancedBrace { opening }) = kind else {
+        panic!("Unexpected kind: {kind:?}");
+    };
+    assert_eq!((opening.line, opening.col), (2, 1));
+}
+
+fn write_err(res: keyvalues_parser::error::Result<()>) -> (usize, WriteErrorKind) {
+    match res.unwrap_err() {
+        Error::WriteError(WriteError { depth, kind }) => (depth, kind),
+        other => panic!("Unexpected error: {other:?}"),
+    }
+}
+
+#[test]
+fn writer_matches_render() {
+    let assets = [
+        "basic.vdf",
+        "comments.vdf",
+        "compact.vdf",
+        "special_characters.vdf",
+        "unquoted_strings.vdf",
+    ];
+    for asset in assets {
+        let text = read_asset_file(asset);
+        let mut writer = Writer::new(Vec::new());
+        let mut key = None;
+        for event in Reader::new(text.as_bytes()) {
+            match event.unwrap() {
+                Event::Key(k) => key = Some(k),
+                Event::Str(value) => writer.pair(&key.take().unwrap(), &value).unwrap(),
+                Event::ObjBegin => writer.begin_obj(&key.take().unwrap()).unwrap(),
+                Event::ObjEnd => writer.end_obj().unwrap(),
+                other => panic!("Unexpected event: {other:?}"),
+            }
+        }
+        let written = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        let vdf = Vdf::from(keyvalues_parser::parse(&text).unwrap());
+        let mut rendered = String::new();
+        vdf.render(&mut rendered).unwrap();
+        assert_eq!(written, rendered, "{asset}");
+    }
+}
+
+#[test]
+fn writer_validates_nesting() {
+    let mut writer = Writer::new(Vec::new());
+    for path in ["quote\".vdf", "new\nline.vdf"] {
+        assert!(matches!(
+            write_err(writer.include(path)),
+            (0, WriteErrorKind::InvalidDirectivePath)
+        ));
+    }
+    assert!(matches!(
+        write_err(writer.end_obj()),
+        (0, WriteErrorKind::UnbalancedEndObj)
+    ));
+    writer.begin_obj("Outer").unwrap();
+    writer.pair("Inner", "Value").unwrap();
+    assert!(matches!(
+        write_err(writer.base("late.vdf")),
+        (1, WriteErrorKind::MisplacedDirective)
+    ));
+    writer.end_obj().unwrap();
+    assert!(matches!(
+        write_err(writer.pair("Another", "Pair")),
+        (0, WriteErrorKind::MultipleTopLevelPairs)
+    ));
+    assert!(matches!(
+        write_err(writer.end_obj()),
+        (0, WriteErrorKind::UnbalancedEndObj)
+    ));
+    // None of the misuse made it into the output
+    let text = String::from_utf8(writer.finish().unwrap()).unwrap();
+    assert_eq!(text, "\"Outer\"\n{\n\t\"Inner\"\t\"Value\"\n}\n");
+
+    let mut writer = Writer::new(Vec::new());
+    writer.begin_obj("Unclosed").unwrap();
+    let Err(Error::WriteError(err)) = writer.finish() else {
+        panic!("Expected an error");
+    };
+    assert!(matches!(
+        (err.depth, err.kind),
+        (1, WriteErrorKind::UnclosedObj)
+    ));
+
+    let mut writer = Writer::new(Vec::new());
+    writer.base("base.vdf").unwrap();
+    let Err(Error::WriteError(err)) = writer.finish() else {
+        panic!("Expected an error");
+    };
+    assert!(matches!(err.kind, WriteErrorKind::MissingTopLevelPair));
+}
+
+#[test]
+fn writer_raw() {
+    let mut writer = Writer::new(Vec::new()).literal_special_chars(true);
+    writer.pair("Path", r"C:\Games\tf2").unwrap();
+    assert!(matches!(
+        writer.pair("Quote", "\"").unwrap_err(),
+        Error::RawRenderError { invalid_char: '"' }
+    ));
+    let text = String::from_utf8(writer.finish().unwrap()).unwrap();
+    assert_eq!(text, "\"Path\"\t\"C:\\Games\\tf2\"\n");
+}
+
+#[test]
+fn writer_io_errors() {
+    struct Full;
+
+    impl io::Write for Full {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::WriteZero.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut writer = Writer::new(Full);
+    let err = writer.begin_obj("Key").unwrap_err();
+    // The writer's error is kept around as the source
+    let source = std::error::Error::source(&err).unwrap();
+    let source = source.downcast_ref::<io::Error>().unwrap();
+    assert_eq!(source.kind(), io::ErrorKind::WriteZero);
+    assert!(matches!(
+        write_err(Err(err)),
+        (0, WriteErrorKind::Io(e)) if e.kind() == io::ErrorKind::WriteZero
+    ));
+}
@@ -1,4 +1,21 @@
+mod binary;
+mod case_insensitive;
+mod conditionals;
+mod cst;
+mod dmx;
+mod duplicate_keys;
+mod encoding;
 mod known_issues;
+mod kv3;
+mod lenient;
+mod limits;
+mod parse_errors;
 mod regressions;
+mod renderer;
+mod resolve;
+mod root;
+mod spans;
+mod stream;
 mod text_parser;
+mod utils;
 mod vdf_iteration;
@@ -4,7 +4,7 @@ use insta::{assert_debug_snapshot, assert_snapshot};
 
 use std::{borrow::Cow, collections::BTreeMap, error::Error, fs, path::Path};
 
-use keyvalues_parser::{Obj, PartialVdf, Value, Vdf};
+use keyvalues_parser::{Conditional, Include, Obj, Parser, PartialVdf, TypedValue, Value, Vdf};
 
 type BoxedResult<T> = Result<T, Box<dyn Error>>;
 
@@ -19,16 +19,27 @@ fn read_asset_file(file_name: &str) -> BoxedResult<String> {
 struct PartialVdfDef<'a> {
     key: Cow<'a, str>,
     value: ValueDef<'a>,
+    conditional: Option<Conditional<'a>>,
     bases: Vec<Cow<'a, str>>,
+    includes: Vec<Include<'a>>,
 }
 
 impl<'a> From<PartialVdf<'a>> for PartialVdfDef<'a> {
     fn from(partial_vdf: PartialVdf<'a>) -> Self {
-        let PartialVdf { key, value, bases } = partial_vdf;
+        let PartialVdf {
+            key,
+            value,
+            conditional,
+            bases,
+            includes,
+            ..
+        } = partial_vdf;
         Self {
             key,
             value: ValueDef::from(value),
+            conditional,
             bases,
+            includes,
         }
     }
 }
@@ -38,14 +49,20 @@ impl<'a> From<PartialVdf<'a>> for PartialVdfDef<'a> {
 struct VdfDef<'a> {
     key: Cow<'a, str>,
     value: ValueDef<'a>,
+    conditional: Option<Conditional<'a>>,
 }
 
 impl<'a> From<Vdf<'a>> for VdfDef<'a> {
     fn from(vdf: Vdf<'a>) -> Self {
-        let Vdf { key, value } = vdf;
+        let Vdf {
+            key,
+            value,
+            conditional,
+        } = vdf;
         Self {
             key,
             value: ValueDef::from(value),
+            conditional,
         }
     }
 }
@@ -55,6 +72,7 @@ impl<'a> From<Vdf<'a>> for VdfDef<'a> {
 enum ValueDef<'a> {
     Str(Cow<'a, str>),
     Obj(ObjDef<'a>),
+    Typed(TypedValue<'a>),
 }
 
 impl<'a> From<Value<'a>> for ValueDef<'a> {
@@ -62,17 +80,30 @@ impl<'a> From<Value<'a>> for ValueDef<'a> {
         match value {
             Value::Str(s) => Self::Str(s),
             Value::Obj(obj) => Self::Obj(ObjDef::from(obj)),
+            Value::Typed(typed) => Self::Typed(typed),
         }
     }
 }
 
 #[derive(Debug)]
 #[allow(dead_code)]
-struct ObjDef<'a>(BTreeMap<Cow<'a, str>, Vec<ValueDef<'a>>>);
+struct ObjDef<'a> {
+    pairs: BTreeMap<Cow<'a, str>, Vec<ValueDef<'a>>>,
+    // Keyed by each pair's `(key, index)`
+    conditionals: BTreeMap<(Cow<'a, str>, usize), Conditional<'a>>,
+}
 
 impl<'a> From<Obj<'a>> for ObjDef<'a> {
     fn from(obj: Obj<'a>) -> Self {
-        let inner = obj
+        let conditionals = obj
+            .ordered_pairs()
+            .into_iter()
+            .filter_map(|(key, index, _)| {
+                let conditional = obj.conditional(key, index)?.clone();
+                Some(((key.clone(), index), conditional))
+            })
+            .collect();
+        let pairs = obj
             .into_inner()
             .into_iter()
             .map(|(key, values)| {
@@ -80,7 +111,10 @@ impl<'a> From<Obj<'a>> for ObjDef<'a> {
                 (key, value_defs)
             })
             .collect();
-        Self(inner)
+        Self {
+            pairs,
+            conditionals,
+        }
     }
 }
 
@@ -168,6 +202,7 @@ parse_test_generator!(
     basic,
     comments,
     compact,
+    conditionals,
     unquoted_strings,
     special_characters,
     null_byte
@@ -179,10 +214,34 @@ parse_test_generator!(
     snapshot_test_partial_parse_and_render,
     base_multiple,
     base_quoted,
-    base_unquoted
+    base_unquoted,
+    include_mixed
 );
 
 parse_test_generator!(
     snapshot_test_partial_raw_parse_render,
     base_multiple_raw_strings
 );
+
+#[test]
+fn borrows_unless_unescaping() {
+    let text = r#"Outer { "plain" "value" escaped "line\nbreak" }"#;
+    let vdf = keyvalues_parser::parse(text).unwrap();
+    let obj = vdf.value.get_obj().unwrap();
+    let (key, values) = obj.get_key_value("plain").unwrap();
+    assert!(matches!(key, Cow::Borrowed(_)));
+    assert!(matches!(values[0], Value::Str(Cow::Borrowed(_))));
+    assert!(matches!(obj["escaped"][0], Value::Str(Cow::Owned(_))));
+    assert_eq!(obj["escaped"][0].get_str(), Some("line\nbreak"));
+
+    let text = r#"Outer { raw "C:\Games" }"#;
+    let vdf = Parser::new()
+        .literal_special_chars(true)
+        .parse(text)
+        .unwrap();
+    let obj = vdf.value.get_obj().unwrap();
+    assert!(matches!(
+        obj["raw"][0],
+        Value::Str(Cow::Borrowed(r"C:\Games"))
+    ));
+}
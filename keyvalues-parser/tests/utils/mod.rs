@@ -0,0 +1,5 @@
+use std::{fs, path::Path};
+
+pub fn read_asset_file(file_name: &str) -> String {
+    fs::read_to_string(Path::new("tests").join("assets").join(file_name)).unwrap()
+}
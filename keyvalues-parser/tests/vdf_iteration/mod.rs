@@ -5,7 +5,7 @@ use std::{borrow::Cow, collections::BTreeMap};
 #[test]
 fn simple_vdfs_iteration() {
     let inner = BTreeMap::new();
-    let obj = Obj(inner);
+    let obj = Obj::from(inner);
     let mut vdfs_iter = obj.into_vdfs();
 
     assert_eq!(vdfs_iter.next(), None);
@@ -38,3 +38,66 @@ fn complex_vdfs_iteration() {
         ]
     );
 }
+
+#[test]
+fn vdfs_iteration_keeps_document_order() {
+    let text = r#"
+    "SearchPaths"
+    {
+        "Game"  "hl2"
+        "Mod"   "mod"
+        "Game"  "base"
+        "10"    "ten"
+        "2"     "two"
+    }
+    "#;
+    let vdf = keyvalues_parser::parse(text).unwrap();
+    let mut obj = vdf.value.unwrap_obj();
+    // Inserted pairs come after everything else
+    obj.insert(Cow::from("Added"), vec![Value::Str(Cow::from("last"))]);
+
+    let pairs: Vec<_> = obj
+        .into_vdfs()
+        .map(|vdf| (vdf.key.into_owned(), vdf.value.unwrap_str().into_owned()))
+        .collect();
+    let expected = [
+        ("Game", "hl2"),
+        ("Mod", "mod"),
+        ("Game", "base"),
+        ("10", "ten"),
+        ("2", "two"),
+        ("Added", "last"),
+    ];
+    let expected: Vec<_> = expected
+        .iter()
+        .map(|&(key, value)| (key.to_owned(), value.to_owned()))
+        .collect();
+    assert_eq!(pairs, expected);
+}
+
+#[test]
+fn render_keeps_document_order() {
+    let text = "\"SearchPaths\"\n{\n\t\"Game\"\t\"hl2\"\n\t\"Mod\"\t\"mod\"\n\t\"Game\"\t\"base\"\n\t\"10\"\t\"ten\"\n\t\"2\"\t\"two\"\n}\n";
+    let vdf = keyvalues_parser::parse(text).unwrap();
+    assert_eq!(vdf.to_string(), text);
+}
+
+#[test]
+fn removed_pairs_lose_their_place() {
+    let vdf = Vdf::parse("Outer { a 1 b 2 c 3 c 4 }").unwrap();
+    let mut obj = vdf.value.unwrap_obj();
+    obj.remove("a");
+    obj.push(Cow::from("a"), Value::Str(Cow::from("5")), None);
+    obj.remove_value("c", 0);
+    obj.push(Cow::from("c"), Value::Str(Cow::from("6")), None);
+
+    let pairs: Vec<_> = obj
+        .ordered_pairs()
+        .into_iter()
+        .map(|(key, index, value)| (key.as_ref(), index, value.get_str().unwrap()))
+        .collect();
+    assert_eq!(
+        pairs,
+        [("b", 0, "2"), ("c", 0, "4"), ("a", 0, "5"), ("c", 1, "6")]
+    );
+}
@@ -46,9 +46,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Switch all the entries with keys that are an index (0, 1, ...) to `"libraries"`
     let mut index = 0;
     while let Some(mut library) = obj.remove(index.to_string().as_str()) {
-        obj.entry(Cow::from("libraries"))
-            .or_insert(Vec::new())
-            .push(library.pop().unwrap());
+        obj.push(Cow::from("libraries"), library.pop().unwrap(), None);
 
         index += 1;
     }
@@ -66,7 +66,11 @@ impl From<ParseFloatError> for Error {
 
 impl From<ParserError> for Error {
     fn from(e: ParserError) -> Self {
-        Self::Parse(e)
+        match e {
+            // Writer errors from rendering are kept as I/O errors
+            ParserError::Io(e) => Self::Io(e),
+            e => Self::Parse(e),
+        }
     }
 }
 
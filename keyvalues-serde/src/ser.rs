@@ -6,6 +6,7 @@ use std::io::Write;
 
 use crate::{
     error::{Error, Result},
+    parser::Renderer,
     tokens::{naive::vdf_from_naive_tokens, NaiveToken},
 };
 
@@ -35,7 +36,7 @@ where
     W: Write,
     T: Serialize,
 {
-    _to_writer(writer, value, None)
+    _to_writer(writer, value, None, &Renderer::new())
 }
 
 /// Serialize the `value` into an IO stream of VDF text with a custom top level VDF key
@@ -48,7 +49,37 @@ where
     W: Write,
     T: Serialize,
 {
-    _to_writer(writer, value, Some(key))
+    _to_writer(writer, value, Some(key), &Renderer::new())
+}
+
+/// Serialize the `value` into an IO stream of VDF text formatted by the `renderer`
+///
+/// # Errors
+///
+/// This will return an error if the input can't be represented with valid VDF
+///
+/// # Example
+///
+/// ```
+/// use keyvalues_serde::{parser::Renderer, to_writer_with_renderer};
+/// # use serde::Serialize;
+/// #[derive(Serialize)]
+/// struct Config {
+///     volume: u8,
+/// }
+///
+/// let mut text = Vec::new();
+/// let renderer = Renderer::new().indent("  ").quote_only_when_needed(true);
+/// to_writer_with_renderer(&mut text, &Config { volume: 80 }, &renderer)?;
+/// assert_eq!(text, b"Config\n{\n  volume\t80\n}\n");
+/// # Ok::<(), keyvalues_serde::Error>(())
+/// ```
+pub fn to_writer_with_renderer<W, T>(writer: &mut W, value: &T, renderer: &Renderer) -> Result<()>
+where
+    W: Write,
+    T: Serialize,
+{
+    _to_writer(writer, value, None, renderer)
 }
 
 // Serialization process goes as follows:
@@ -58,7 +89,12 @@ where
 // -> Formatted
 // Which is a bit of a long-winded process just to serialize some text, but it comes with
 // validation (NaiveTokenStream -> Vdf) and reuses portions from the parser (Vdf -> Formatted)
-fn _to_writer<W, T>(writer: &mut W, value: &T, maybe_key: Option<&str>) -> Result<()>
+fn _to_writer<W, T>(
+    writer: &mut W,
+    value: &T,
+    maybe_key: Option<&str>,
+    renderer: &Renderer,
+) -> Result<()>
 where
     W: Write,
     T: Serialize,
@@ -79,7 +115,7 @@ where
     }
 
     let vdf = vdf_from_naive_tokens(&serializer.tokens)?;
-    write!(writer, "{vdf}")?;
+    vdf.render_to_writer(writer, renderer)?;
 
     Ok(())
 }
@@ -116,6 +152,22 @@ where
     Ok(s)
 }
 
+/// Attempts to serialize some input to VDF text formatted by the `renderer`
+///
+/// # Errors
+///
+/// This will return an error if the input can't be represented with valid VDF
+pub fn to_string_with_renderer<T>(value: &T, renderer: &Renderer) -> Result<String>
+where
+    T: Serialize,
+{
+    let mut buffer = Vec::new();
+    to_writer_with_renderer(&mut buffer, value, renderer)?;
+    let s = String::from_utf8(buffer).expect("Input was all valid UTF-8");
+
+    Ok(s)
+}
+
 macro_rules! forward_serialize_as_str {
     ( $( ( $method:ident, $ty:ty ) ),* $(,)? ) => {
         $(
@@ -5,49 +5,96 @@ pub(crate) mod naive;
 #[cfg(test)]
 mod tests;
 
-use keyvalues_parser::{Obj, Value, Vdf};
+use keyvalues_parser::{Key, Obj, Value, Vdf};
 
-use std::borrow::Cow;
+use std::{borrow::Cow, collections::BTreeMap, vec};
 
 pub use crate::tokens::naive::NaiveToken;
 
 pub(crate) fn tokens_from_vdf(vdf: Vdf<'_>) -> Vec<Token<'_>> {
-    let Vdf { key, value } = vdf;
+    let Vdf { key, value, .. } = vdf;
 
     let mut tokens = vec![Token::Key(key)];
-    push_tokens_from_value(&mut tokens, value);
+    let mut stack = Vec::new();
+    push_tokens_from_value(&mut tokens, &mut stack, value);
+    while let Some(frame) = stack.last_mut() {
+        match frame {
+            Frame::Obj(pairs) => match pairs.next() {
+                Some((key, values)) => {
+                    tokens.push(Token::Key(key));
+
+                    // For ease of use a sequence is only marked when len != 1
+                    let is_seq = values.len() != 1;
+                    if is_seq {
+                        tokens.push(Token::SeqBegin);
+                    }
+                    stack.push(Frame::Values(values.into_iter(), is_seq));
+                }
+                None => {
+                    tokens.push(Token::ObjEnd);
+                    stack.pop();
+                }
+            },
+            Frame::Values(values, is_seq) => match values.next() {
+                Some(value) => push_tokens_from_value(&mut tokens, &mut stack, value),
+                None => {
+                    if *is_seq {
+                        tokens.push(Token::SeqEnd);
+                    }
+                    stack.pop();
+                }
+            },
+        }
+    }
+
     tokens
 }
 
-fn push_tokens_from_value<'text>(tokens: &mut Vec<Token<'text>>, value: Value<'text>) {
+enum Frame<'text> {
+    Obj(vec::IntoIter<(Key<'text>, Vec<Value<'text>>)>),
+    // Along with whether the values are a sequence
+    Values(vec::IntoIter<Value<'text>>, bool),
+}
+
+fn push_tokens_from_value<'text>(
+    tokens: &mut Vec<Token<'text>>,
+    stack: &mut Vec<Frame<'text>>,
+    value: Value<'text>,
+) {
     match value {
         Value::Str(s) => tokens.push(Token::Str(s)),
+        // Typed values get deserialized from their text representation like any other string
+        Value::Typed(typed) => tokens.push(Token::Str(Cow::Owned(typed.to_string()))),
         Value::Obj(obj) => {
             tokens.push(Token::ObjBegin);
-            push_tokens_from_obj(tokens, obj);
-            tokens.push(Token::ObjEnd);
+            stack.push(Frame::Obj(ordered_entries(obj).into_iter()));
         }
     }
 }
 
-fn push_tokens_from_obj<'text>(tokens: &mut Vec<Token<'text>>, obj: Obj<'text>) {
-    for (key, values) in obj.into_inner().into_iter() {
-        tokens.push(Token::Key(key));
+// Each key along with all of its values in the order that the key first appears in the object.
+// Keys without any values don't have a place in the order, so they go at the end
+fn ordered_entries(obj: Obj<'_>) -> Vec<(Key<'_>, Vec<Value<'_>>)> {
+    let empty: Vec<_> = obj
+        .iter()
+        .filter(|(_, values)| values.is_empty())
+        .map(|(key, _)| (key.clone(), Vec::new()))
+        .collect();
 
-        // For ease of use a sequence is only marked when len != 1
-        let num_values = values.len();
-        if num_values != 1 {
-            tokens.push(Token::SeqBegin);
-        }
-
-        for value in values {
-            push_tokens_from_value(tokens, value);
-        }
-
-        if num_values != 1 {
-            tokens.push(Token::SeqEnd);
+    let mut positions: BTreeMap<Key<'_>, usize> = BTreeMap::new();
+    let mut entries: Vec<(Key<'_>, Vec<Value<'_>>)> = Vec::new();
+    for Vdf { key, value, .. } in obj.into_vdfs() {
+        match positions.get(&key) {
+            Some(&pos) => entries[pos].1.push(value),
+            None => {
+                positions.insert(key.clone(), entries.len());
+                entries.push((key, vec![value]));
+            }
         }
     }
+    entries.extend(empty);
+
+    entries
 }
 
 /// A single VDF token
@@ -10,13 +10,18 @@ use crate::{Error, Result};
 use keyvalues_parser::{Key, Obj, Value, Vdf};
 use serde_core::ser::Error as _;
 
-// The conversion leverages all the `process_*` functions which pass off an owned iterator through
-// all of them to deal with the borrow checker
 pub(crate) fn vdf_from_naive_tokens(naive_tokens: &[NaiveToken]) -> Result<Vdf<'_>> {
-    // Just some helper functions for munching through tokens
-    fn process_key_values<'a, I>(
-        mut tokens: Peekable<I>,
-    ) -> Result<(Peekable<I>, Key<'a>, Vec<Value<'a>>)>
+    enum Frame<'a> {
+        // A pair that's collecting its values. Only sequences have more than one
+        Pair {
+            key: Key<'a>,
+            values: Vec<Value<'a>>,
+            in_seq: bool,
+        },
+        Obj(Obj<'a>),
+    }
+
+    fn pair<'a, I>(tokens: &mut Peekable<I>) -> Result<Frame<'a>>
     where
         I: Iterator<Item = &'a NaiveToken>,
     {
@@ -35,99 +40,87 @@ pub(crate) fn vdf_from_naive_tokens(naive_tokens: &[NaiveToken]) -> Result<Vdf<'
             }
         };
 
-        let res = process_values(tokens)?;
-        tokens = res.0;
-        let values = res.1;
-
-        Ok((tokens, key, values))
+        Ok(Frame::Pair {
+            key,
+            values: Vec::new(),
+            in_seq: false,
+        })
     }
 
-    fn process_values<'a, I>(mut tokens: Peekable<I>) -> Result<(Peekable<I>, Vec<Value<'a>>)>
-    where
-        I: Iterator<Item = &'a NaiveToken>,
-    {
-        let pair = match tokens.next() {
-            // A `Str` is a single value
-            Some(NaiveToken::Str(s)) => (tokens, vec![Value::Str(Cow::from(s.clone()))]),
-            Some(NaiveToken::ObjBegin) => {
-                let (tokens, value) = process_obj(tokens)?;
-                (tokens, vec![value])
-            }
-            // Sequences are a series of values that can't contain a sequence (vdf limitation)
-            Some(NaiveToken::SeqBegin) => {
-                let mut values = Vec::new();
-                loop {
-                    if let Some(NaiveToken::SeqEnd) = tokens.peek() {
-                        // Pop off the marker
-                        tokens.next();
-                        break;
-                    } else {
-                        let res = process_non_seq_value(tokens)?;
-                        tokens = res.0;
-                        if let Some(val) = res.1 {
-                            values.push(val);
-                        }
+    let mut tokens = naive_tokens.iter().peekable();
+    let mut stack = vec![pair(&mut tokens)?];
+    let (key, mut values) = loop {
+        let mut pair_done = false;
+        match stack
+            .last_mut()
+            .expect("The top-level pair is always there")
+        {
+            Frame::Pair {
+                values,
+                in_seq: in_seq @ false,
+                ..
+            } => match tokens.next() {
+                // A `Str` is a single value
+                Some(NaiveToken::Str(s)) => {
+                    values.push(Value::Str(Cow::from(s)));
+                    pair_done = true;
+                }
+                Some(NaiveToken::ObjBegin) => stack.push(Frame::Obj(Obj::new())),
+                // Sequences are a series of values that can't contain a sequence (vdf limitation)
+                Some(NaiveToken::SeqBegin) => *in_seq = true,
+                // VDF represents `Null` as omitting the value
+                Some(NaiveToken::Null) => pair_done = true,
+                _ => return Err(Error::ExpectedSomeValue),
+            },
+            Frame::Pair { values, .. } => {
+                if let Some(NaiveToken::SeqEnd) = tokens.peek() {
+                    // Pop off the marker
+                    tokens.next();
+                    pair_done = true;
+                } else {
+                    match tokens.next() {
+                        Some(NaiveToken::Str(s)) => values.push(Value::Str(Cow::from(s))),
+                        Some(NaiveToken::ObjBegin) => stack.push(Frame::Obj(Obj::new())),
+                        // VDF represents `Null` as omitting the value
+                        Some(NaiveToken::Null) => {}
+                        _ => return Err(Error::ExpectedSomeNonSeqValue),
                     }
                 }
-
-                (tokens, values)
             }
-            // VDF represents `Null` as omitting the value
-            Some(NaiveToken::Null) => (tokens, Vec::new()),
-            _ => return Err(Error::ExpectedSomeValue),
-        };
-
-        Ok(pair)
-    }
-
-    fn process_non_seq_value<'a, I>(
-        mut tokens: Peekable<I>,
-    ) -> Result<(Peekable<I>, Option<Value<'a>>)>
-    where
-        I: Iterator<Item = &'a NaiveToken>,
-    {
-        let pair = match tokens.next() {
-            Some(NaiveToken::Str(s)) => (tokens, Some(Value::Str(Cow::from(s)))),
-            Some(NaiveToken::ObjBegin) => {
-                let (tokens, value) = process_obj(tokens)?;
-                (tokens, Some(value))
-            }
-            // VDF represents `Null` as omitting the value
-            Some(NaiveToken::Null) => (tokens, None),
-            _ => return Err(Error::ExpectedSomeNonSeqValue),
-        };
-
-        Ok(pair)
-    }
-
-    fn process_obj<'a, I>(mut tokens: Peekable<I>) -> Result<(Peekable<I>, Value<'a>)>
-    where
-        I: Iterator<Item = &'a NaiveToken>,
-    {
-        let mut obj = Obj::new();
-        loop {
-            match tokens.peek() {
+            // An object is a series of key-value pairs
+            Frame::Obj(_) => match tokens.peek() {
                 Some(NaiveToken::ObjEnd) => {
                     tokens.next();
-                    break;
+                    let Some(Frame::Obj(obj)) = stack.pop() else {
+                        unreachable!("Just matched an object");
+                    };
+                    let Some(Frame::Pair { values, in_seq, .. }) = stack.last_mut() else {
+                        unreachable!("Objects are always the value of a pair");
+                    };
+                    values.push(Value::Obj(obj));
+                    pair_done = !*in_seq;
                 }
-                // An object is a series of key-value pairs
                 Some(_) => {
-                    let res = process_key_values(tokens)?;
-                    tokens = res.0;
-                    let key = res.1;
-                    let values = res.2;
-                    obj.insert(key, values);
+                    let pair = pair(&mut tokens)?;
+                    stack.push(pair);
                 }
                 _ => return Err(Error::ExpectedObjectStart),
-            }
+            },
         }
 
-        Ok((tokens, Value::Obj(obj)))
-    }
-
-    let tokens = naive_tokens.iter().peekable();
-    let (mut tokens, key, mut values) = process_key_values(tokens)?;
+        if pair_done {
+            let Some(Frame::Pair { key, values, .. }) = stack.pop() else {
+                unreachable!("Only pairs get finished");
+            };
+            match stack.last_mut() {
+                Some(Frame::Obj(obj)) => {
+                    obj.insert(key, values);
+                }
+                Some(Frame::Pair { .. }) => unreachable!("Pairs are always within an object"),
+                None => break (key, values),
+            }
+        }
+    };
 
     if tokens.next().is_some() {
         return Err(Error::TrailingTokens);
@@ -50,10 +50,7 @@ fn vdf_from_token_stream_basics() {
             ],
         );
 
-        Vdf {
-            key: Cow::from("outer"),
-            value: Value::Obj(outer_val),
-        }
+        Vdf::new(Cow::from("outer"), Value::Obj(outer_val))
     };
 
     let actual = vdf_from_naive_tokens(&naive_token_stream).unwrap();
@@ -120,3 +117,61 @@ fn token_stream_from_vdf() {
         ]
     );
 }
+
+#[test]
+fn token_streams_keep_order() {
+    let vdf = Vdf::parse(r#"Outer { b "1" a "2" b "3" }"#).unwrap();
+    let token_stream = tokens_from_vdf(vdf);
+    assert_eq!(
+        token_stream,
+        &[
+            Token::Key(Cow::from("Outer")),
+            Token::ObjBegin,
+            Token::Key(Cow::from("b")),
+            Token::SeqBegin,
+            Token::Str(Cow::from("1")),
+            Token::Str(Cow::from("3")),
+            Token::SeqEnd,
+            Token::Key(Cow::from("a")),
+            Token::Str(Cow::from("2")),
+            Token::ObjEnd,
+        ]
+    );
+
+    let naive_token_stream = vec![
+        NaiveToken::str("Outer"),
+        NaiveToken::ObjBegin,
+        NaiveToken::str("b"),
+        NaiveToken::str("1"),
+        NaiveToken::str("a"),
+        NaiveToken::str("2"),
+        NaiveToken::ObjEnd,
+    ];
+    let vdf = vdf_from_naive_tokens(&naive_token_stream).unwrap();
+    let obj = vdf.value.unwrap_obj();
+    let keys: Vec<_> = obj
+        .ordered_pairs()
+        .into_iter()
+        .map(|(key, _, _)| key.as_ref())
+        .collect();
+    assert_eq!(keys, ["b", "a"]);
+}
+
+#[test]
+fn deeply_nested() {
+    const DEPTH: usize = 100_000;
+
+    let mut naive_token_stream = vec![NaiveToken::str("outer")];
+    for _ in 0..DEPTH {
+        naive_token_stream.extend([NaiveToken::ObjBegin, NaiveToken::str("key")]);
+    }
+    naive_token_stream.push(NaiveToken::str("value"));
+    naive_token_stream.extend((0..DEPTH).map(|_| NaiveToken::ObjEnd));
+
+    let vdf = vdf_from_naive_tokens(&naive_token_stream).unwrap();
+    // Tokenizing takes apart the value as it goes, so nothing is left to drop recursively
+    let token_stream = tokens_from_vdf(vdf);
+    assert_eq!(token_stream.len(), naive_token_stream.len());
+    assert_eq!(token_stream[DEPTH * 2 + 1], Token::Str(Cow::from("value")));
+    assert_eq!(token_stream.last(), Some(&Token::ObjEnd));
+}
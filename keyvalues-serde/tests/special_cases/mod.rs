@@ -1,11 +1,17 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+};
 
-use crate::utils::{read_asset_file, test_vdf_deserialization, BoxedResult, Container};
+use crate::utils::{
+    assert_same_lines, read_asset_file, test_vdf_deserialization, BoxedResult, Container,
+};
 
 use insta::{assert_debug_snapshot, assert_snapshot};
 use keyvalues_serde::{
-    from_str, from_str_raw, from_str_with_key, to_string, to_string_with_key, to_writer,
-    to_writer_with_key, Error,
+    from_str, from_str_raw, from_str_with_key,
+    parser::{error::Error as ParserError, Newline, Renderer},
+    to_string, to_string_with_key, to_string_with_renderer, to_writer, to_writer_with_key, Error,
 };
 use pretty_assertions::assert_eq;
 use serde::Deserialize;
@@ -37,6 +43,48 @@ fn snapshot_writing() -> BoxedResult<()> {
     Ok(())
 }
 
+#[test]
+fn custom_renderer() -> BoxedResult<()> {
+    let vdf_struct = Container::new(123);
+    let renderer = Renderer::new()
+        .indent("    ")
+        .quote_only_when_needed(true)
+        .newline(Newline::CrLf);
+    let vdf_text = to_string_with_renderer(&vdf_struct, &renderer)?;
+    assert_eq!(vdf_text, "Container\r\n{\r\n    inner\t123\r\n}\r\n");
+
+    // Still reads back the same
+    let deserialized: Container<u32> = from_str(&vdf_text)?;
+    assert_eq!(deserialized, vdf_struct);
+    Ok(())
+}
+
+#[test]
+fn writer_errors() {
+    struct FullWriter;
+
+    impl std::io::Write for FullWriter {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::WriteZero.into())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let err = to_writer(&mut FullWriter, &Container::new(123)).unwrap_err();
+    assert!(matches!(err, Error::Io(e) if e.kind() == std::io::ErrorKind::WriteZero));
+
+    // Parser errors from rendering come back as they are
+    let renderer = Renderer::new().literal_special_chars(true);
+    let err = to_string_with_renderer(&Container::new("\"quoted\""), &renderer).unwrap_err();
+    assert!(matches!(
+        err,
+        Error::Parse(ParserError::RawRenderError { invalid_char: '"' })
+    ));
+}
+
 #[test]
 fn hashmap_top_level() -> BoxedResult<()> {
     let val = HashMap::from([(0, "Foo"), (1, "Bar"), (2, "Baz")]);
@@ -46,6 +94,11 @@ fn hashmap_top_level() -> BoxedResult<()> {
     // Using a hashmap on the top level has no way of indicating what the key should be so it must
     // be passed in separately
     let val_text = to_string_with_key(&val, "Key")?;
+    assert_same_lines(&vdf_text, &val_text);
+
+    // Pairs are serialized in the map's iteration order, so a `BTreeMap` keeps the output stable
+    let val: BTreeMap<_, _> = val.into_iter().collect();
+    let val_text = to_string_with_key(&val, "Key")?;
     assert_eq!(vdf_text, val_text, "Failed serializing");
     Ok(())
 }
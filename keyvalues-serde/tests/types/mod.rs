@@ -1,9 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::utils::{
-    read_asset_file, test_vdf_deserialization, test_vdf_serialization, BoxedResult, Container,
+    assert_same_lines, read_asset_file, test_vdf_deserialization, test_vdf_serialization,
+    BoxedResult, Container,
 };
 
+use keyvalues_serde::to_string;
 use serde::{Deserialize, Serialize};
 
 // Helper that generates a test to ensure that the contents within `file_name` deserialize to
@@ -138,13 +140,21 @@ test_ser_de!(
     "tuple.vdf"
 );
 
-// TODO: it's not clear if the ordering of values is expected to stay the same in vdf. If that is
-// the case then it would be important to track down a map type that preserves insertion order. It
-// looks like something like hashlink should work out
-test_ser_de_infer_file!(hashmap_nested, {
-    let inner = HashMap::from([(0, "Foo"), (1, "Bar"), (2, "Baz")]);
-    Container::new(inner)
-});
+#[test]
+fn hashmap_nested() -> BoxedResult<()> {
+    let val = Container::new(HashMap::from([(0, "Foo"), (1, "Bar"), (2, "Baz")]));
+    let vdf_text = read_asset_file("hashmap_nested.vdf")?;
+    test_vdf_deserialization(&vdf_text, &val)?;
+    assert_same_lines(&vdf_text, &to_string(&val)?);
+    Ok(())
+}
+
+// Pairs are serialized in the map's iteration order, so a `BTreeMap` keeps the output stable
+test_ser_de!(
+    btreemap_nested,
+    Container::new(BTreeMap::from([(0, "Foo"), (1, "Bar"), (2, "Baz")])),
+    "hashmap_nested.vdf"
+);
 
 test_ser_de_infer_file!(option_none, Container::<Option<String>>::new(None));
 
@@ -34,6 +34,22 @@ where
     Ok(())
 }
 
+// Serializing a `HashMap` writes its pairs in whatever order it iterates in, so only the lines
+// themselves are compared
+#[allow(dead_code)]
+pub fn assert_same_lines(ideal_text: &str, val_text: &str) {
+    let sorted_lines = |text: &str| {
+        let mut lines: Vec<_> = text.lines().map(ToOwned::to_owned).collect();
+        lines.sort_unstable();
+        lines
+    };
+    assert_eq!(
+        sorted_lines(ideal_text),
+        sorted_lines(val_text),
+        "Failed serializing"
+    );
+}
+
 // I'm too tired to be able to wrap my head around why just this one function is causing trouble
 #[allow(dead_code)]
 pub fn test_vdf_serialization<T>(ideal_text: &str, val: &T) -> BoxedResult<()>